@@ -0,0 +1,132 @@
+// Reader for GameMaker's .lib action libraries.
+// .gm82 projects only store the library and action ids, so writing any of GM's binary formats
+// needs the rest of the action definition, which lives in these.
+
+use crate::{Error, Result, gm81::decode_string};
+use byteorder::{LE, ReadBytesExt};
+use std::{
+    io::{Cursor, Read},
+    path::Path,
+};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ActionDefinition {
+    pub lib_id: u32,
+    pub id: u32,
+    pub name: String,
+    pub kind: u32,
+    pub is_condition: bool,
+    pub applies_to_something: bool,
+    pub can_be_relative: bool,
+    pub param_count: u32,
+    pub param_types: [u32; 8],
+    pub param_defaults: [String; 8],
+    pub execution_type: u32,
+    pub fn_name: String,
+    pub fn_code: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ActionLibrary {
+    pub id: u32,
+    pub caption: String,
+    /// Runs when the game starts, so it goes in every exe.
+    pub init_code: String,
+    pub actions: Vec<ActionDefinition>,
+}
+
+fn read_bool(r: &mut Cursor<&[u8]>) -> Result<bool> {
+    Ok(r.read_u32::<LE>()? != 0)
+}
+
+fn read_string(r: &mut Cursor<&[u8]>) -> Result<String> {
+    let len = r.read_u32::<LE>()? as usize;
+    let mut buf = vec![0; len];
+    r.read_exact(&mut buf)?;
+    Ok(decode_string(buf))
+}
+
+/// Parses the contents of a .lib file.
+pub fn read_library(data: &[u8]) -> Result<ActionLibrary> {
+    let r = &mut Cursor::new(data);
+    let check_version =
+        |v| if v == 500 || v == 520 { Ok(v) } else { Err(Error::Other(format!("unsupported library version {}", v))) };
+    check_version(r.read_u32::<LE>()?)?;
+    let caption = read_string(r)?;
+    let id = r.read_u32::<LE>()?;
+    read_string(r)?; // author
+    r.read_u32::<LE>()?; // library version
+    r.read_f64::<LE>()?; // timestamp
+    read_string(r)?; // info
+    let init_code = read_string(r)?;
+    read_bool(r)?; // advanced
+    r.read_u32::<LE>()?; // last action id
+    let action_count = r.read_u32::<LE>()?;
+    let actions = (0..action_count)
+        .map(|_| {
+            let version = check_version(r.read_u32::<LE>()?)?;
+            let mut action =
+                ActionDefinition { lib_id: id, name: read_string(r)?, id: r.read_u32::<LE>()?, ..Default::default() };
+            let image_len = r.read_u32::<LE>()?;
+            r.set_position(r.position() + u64::from(image_len));
+            read_bool(r)?; // hidden
+            read_bool(r)?; // advanced
+            if version == 520 {
+                read_bool(r)?; // registered only
+            }
+            read_string(r)?; // description
+            read_string(r)?; // list text
+            read_string(r)?; // hint text
+            action.kind = r.read_u32::<LE>()?;
+            r.read_u32::<LE>()?; // interface kind
+            action.is_condition = read_bool(r)?;
+            action.applies_to_something = read_bool(r)?;
+            action.can_be_relative = read_bool(r)?;
+            action.param_count = r.read_u32::<LE>()?;
+            let param_slots = r.read_u32::<LE>()?;
+            for i in 0..param_slots as usize {
+                read_string(r)?; // caption
+                let kind = r.read_u32::<LE>()?;
+                let default = read_string(r)?;
+                read_string(r)?; // menu
+                if i < 8 {
+                    action.param_types[i] = kind;
+                    action.param_defaults[i] = default;
+                }
+            }
+            action.execution_type = r.read_u32::<LE>()?;
+            action.fn_name = read_string(r)?;
+            action.fn_code = read_string(r)?;
+            Ok(action)
+        })
+        .collect::<Result<_>>()?;
+    Ok(ActionLibrary { id, caption, init_code, actions })
+}
+
+pub fn load_library(path: &Path) -> Result<ActionLibrary> {
+    let data = std::fs::read(path).map_err(|e| Error::FileIoError(e, path.to_path_buf()))?;
+    read_library(&data).map_err(|e| match e {
+        Error::IoError(e) => Error::FileIoError(e, path.to_path_buf()),
+        e => e,
+    })
+}
+
+/// Loads every .lib file in a directory, such as the lib folder in GameMaker's install directory.
+pub fn load_libraries(dir: &Path) -> Result<Vec<ActionLibrary>> {
+    let mut paths = std::fs::read_dir(dir)
+        .map_err(|e| Error::DirIoError(e, dir.to_path_buf()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("lib")))
+        .collect::<Vec<_>>();
+    // same order as the tabs in the ide
+    paths.sort();
+    paths.iter().map(|p| load_library(p)).collect()
+}
+
+pub fn find_action(libs: &[ActionLibrary], lib_id: u32, id: u32) -> Result<&ActionDefinition> {
+    libs.iter()
+        .filter(|l| l.id == lib_id)
+        .flat_map(|l| &l.actions)
+        .find(|a| a.id == id)
+        .ok_or(Error::UnknownAction(lib_id, id))
+}
@@ -0,0 +1,400 @@
+// These mirror the structs in gm82save's asset.rs, but own their data,
+// and refer to other assets by name rather than by index.
+// Code is stored the same way the IDE keeps it in memory: every line ends in \r\n.
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trigger {
+    pub name: String,
+    pub condition: String,
+    pub constant_name: String,
+    pub kind: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Sound {
+    pub kind: u32,
+    pub extension: String,
+    pub effects: u32,
+    pub source: String,
+    pub volume: f64,
+    pub pan: f64,
+    pub preload: bool,
+    pub data: Option<Vec<u8>>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    /// BGRA8, same as in the IDE.
+    pub data: Vec<u8>,
+}
+
+impl Frame {
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Draws a 16x16 thumbnail, same as the one shown in the resource tree.
+    pub fn thumb(&self, out: &mut [u8], flip: bool, bg_col: [u8; 3]) {
+        // note: this assumes output format == input format
+        // these are stored as BGRA8 so make sure to double check what the output should be
+        use itertools::Itertools;
+        let data = &self.data;
+        let (width, height) = if self.width > self.height {
+            (16, (self.height * 16 / self.width) as usize)
+        } else {
+            ((self.width * 16 / self.height) as usize, 16)
+        };
+        let (hoffset, voffset) = (8 - width / 2, 8 - height / 2);
+        for (y, row) in out.chunks_exact_mut(16 * 4).enumerate() {
+            // vertical flip for BMP
+            let y = if flip { 15 - y } else { y };
+            if y < voffset || y >= voffset + height {
+                row.fill(0);
+                continue
+            }
+            let y = y - voffset;
+            for (x, px) in row.chunks_exact_mut(4).enumerate() {
+                if x < hoffset || x >= hoffset + width {
+                    px.fill(0);
+                    continue
+                }
+                let x = x - hoffset;
+                // get sample points
+                let ox = (x as f64) / (width as f64) * f64::from(self.width);
+                let ox2 = ox + 0.5 / (width as f64) * f64::from(self.width);
+                let oy = (y as f64) / (height as f64) * f64::from(self.height);
+                let oy2 = oy + 0.5 / (height as f64) * f64::from(self.height);
+                // sum all pixels
+                let mut px_count = 0.0;
+                let sum_px = [ox, ox2]
+                    .iter()
+                    .map(|x| x.floor() as usize)
+                    .cartesian_product([oy, oy2].iter().map(|x| x.floor() as usize))
+                    .fold([0.0f64; 4], |mut px, (ox, oy)| {
+                        let offset = (oy * self.width as usize + ox) * 4;
+                        let in_px = &data[offset..offset + 4];
+                        if in_px[3] != 0 {
+                            px_count += 1.0;
+                            px.iter_mut().zip(in_px).for_each(|(o, i)| *o += f64::from(*i));
+                        }
+                        px
+                    });
+                if px_count != 0.0 {
+                    // average and place into output
+                    px[..3].iter_mut().zip(sum_px).for_each(|(o, i)| *o = (i / px_count).floor() as u8);
+                    // blend semi-transparent to white
+                    let alpha = sum_px[3] / 4.0 / 255.0;
+                    if alpha != 1.0 {
+                        px[..3]
+                            .iter_mut()
+                            .zip(&bg_col)
+                            .for_each(|(c, &b)| *c = (f64::from(b) * (1.0 - alpha) + f64::from(*c) * alpha) as u8);
+                    }
+                    px[3] = 255;
+                } else {
+                    // fully transparent
+                    px.fill(0);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Sprite {
+    pub origin_x: i32,
+    pub origin_y: i32,
+    pub collision_shape: u32,
+    pub alpha_tolerance: u32,
+    pub per_frame_colliders: bool,
+    pub bbox_type: u32,
+    pub bbox_left: i32,
+    pub bbox_top: i32,
+    pub bbox_right: i32,
+    pub bbox_bottom: i32,
+    pub frames: Vec<Frame>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Background {
+    pub frame: Frame,
+    pub is_tileset: bool,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub h_offset: u32,
+    pub v_offset: u32,
+    pub h_sep: u32,
+    pub v_sep: u32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PathPoint {
+    pub x: f64,
+    pub y: f64,
+    pub speed: f64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Path {
+    pub points: Vec<PathPoint>,
+    pub connection: u32,
+    pub closed: bool,
+    pub precision: u32,
+    /// Room shown behind the path in the path editor.
+    pub background: String,
+    pub snap_x: u32,
+    pub snap_y: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Script {
+    pub source: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Font {
+    pub sys_name: String,
+    pub size: u32,
+    pub bold: bool,
+    pub italic: bool,
+    pub range_start: u32,
+    pub range_end: u32,
+    pub charset: u32,
+    /// This is 1 less than what you'll see saved in .gmk or .exe or .gm81 or whatever
+    pub aa_level: u32,
+    /// A .ttf or .otf file kept next to the font's .txt, rendered instead of `sys_name` outside of GameMaker.
+    pub file: Option<FontFile>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FontFile {
+    pub extension: String,
+    pub data: Vec<u8>,
+}
+
+/// Action parameters, in the shape they're written to disk.
+/// Which one an action uses depends on its kind, which only the action library knows.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum ActionParams {
+    /// Begin/end block, else, exit, code, etc.
+    #[default]
+    None,
+    /// Regular library action. Resource arguments are stored by name.
+    Normal {
+        invert: bool,
+        args: Vec<String>,
+    },
+    Repeat(String),
+    Variable {
+        name: String,
+        value: String,
+    },
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Action {
+    pub lib_id: u32,
+    pub id: u32,
+    /// Only present if the action can be relative.
+    pub is_relative: Option<bool>,
+    /// "self", "other" or an object name. Only present if the action applies to something.
+    pub applies_to: Option<String>,
+    pub params: ActionParams,
+    /// Only used by code actions.
+    pub code: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Event {
+    pub actions: Vec<Action>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Timeline {
+    pub moments: Vec<(u32, Event)>,
+}
+
+/// Event subtype. Collision and trigger events refer to an object or trigger by name, the rest use a number.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum EventNumber {
+    Number(usize),
+    Name(String),
+}
+
+impl std::fmt::Display for EventNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "{}", n),
+            Self::Name(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Object {
+    pub sprite: String,
+    pub solid: bool,
+    pub visible: bool,
+    pub depth: i32,
+    pub persistent: bool,
+    pub parent: String,
+    pub mask: String,
+    pub events: [Vec<(EventNumber, Event)>; 12],
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RoomBackground {
+    pub visible_on_start: bool,
+    pub is_foreground: bool,
+    pub source_bg: String,
+    pub xoffset: i32,
+    pub yoffset: i32,
+    pub tile_horz: bool,
+    pub tile_vert: bool,
+    pub hspeed: i32,
+    pub vspeed: i32,
+    pub stretch: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct View {
+    pub visible: bool,
+    pub source_x: i32,
+    pub source_y: i32,
+    pub source_w: u32,
+    pub source_h: u32,
+    pub port_x: i32,
+    pub port_y: i32,
+    pub port_w: u32,
+    pub port_h: u32,
+    pub following_hborder: i32,
+    pub following_vborder: i32,
+    pub following_hspeed: i32,
+    pub following_vspeed: i32,
+    pub following_target: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Instance {
+    pub x: i32,
+    pub y: i32,
+    pub object: String,
+    /// The hex name used for `<room>_XXXXXXXX` references. 0 means it hasn't been assigned yet.
+    pub name: u32,
+    /// The id the instance has in game. 0 means it gets one when the game is built.
+    pub id: usize,
+    pub creation_code: String,
+    pub locked: bool,
+    pub xscale: f64,
+    pub yscale: f64,
+    pub blend: u32,
+    pub angle: f64,
+}
+
+impl Default for Instance {
+    fn default() -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            object: String::new(),
+            name: 0,
+            id: 0,
+            creation_code: String::new(),
+            locked: false,
+            xscale: 1.0,
+            yscale: 1.0,
+            blend: u32::MAX,
+            angle: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tile {
+    pub x: i32,
+    pub y: i32,
+    pub source_bg: String,
+    pub u: i32,
+    pub v: i32,
+    pub width: i32,
+    pub height: i32,
+    pub depth: i32,
+    /// The id the tile has in game. 0 means it gets one when the game is built.
+    pub id: usize,
+    pub locked: bool,
+    pub xscale: f64,
+    pub yscale: f64,
+    pub blend: u32,
+}
+
+impl Default for Tile {
+    fn default() -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            source_bg: String::new(),
+            u: 0,
+            v: 0,
+            width: 0,
+            height: 0,
+            depth: 0,
+            id: 0,
+            locked: false,
+            xscale: 1.0,
+            yscale: 1.0,
+            blend: u32::MAX,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Room {
+    pub caption: String,
+    pub speed: u32,
+    pub width: u32,
+    pub height: u32,
+    pub snap_y: u32,
+    pub snap_x: u32,
+    pub isometric: bool,
+    pub persistent: bool,
+    pub bg_colour: i32,
+    pub clear_screen: bool,
+    pub backgrounds: [RoomBackground; 8],
+    pub views_enabled: bool,
+    pub clear_view: bool,
+    pub views: [View; 8],
+    pub creation_code: String,
+    pub instances: Vec<Instance>,
+    pub tiles: Vec<Tile>,
+    pub remember_room_editor_info: bool,
+    pub editor_width: u32,
+    pub editor_height: u32,
+    pub show_grid: bool,
+    pub show_objects: bool,
+    pub show_tiles: bool,
+    pub show_backgrounds: bool,
+    pub show_foregrounds: bool,
+    pub show_views: bool,
+    pub delete_underlying_objects: bool,
+    pub delete_underlying_tiles: bool,
+    pub tab: u32,
+    pub x_position_scroll: u32,
+    pub y_position_scroll: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IncludedFile {
+    pub file_name: String,
+    /// Where the file's contents live on disk, normally `datafiles/include/<name>`.
+    pub source_path: std::path::PathBuf,
+    pub stored_in_gmk: bool,
+    /// Only loaded if the file is stored in the project.
+    pub data: Option<Vec<u8>>,
+    pub export_setting: u32,
+    pub export_custom_folder: String,
+    pub overwrite_file: bool,
+    pub free_memory: bool,
+    pub remove_at_end: bool,
+}
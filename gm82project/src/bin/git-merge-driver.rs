@@ -0,0 +1,54 @@
+use gm82project::{Error, Result, merge::merge_file};
+use std::process::{Command, ExitCode};
+
+const USAGE: &str = "usage: git merge-driver <base> <ours> <theirs> <path>
+
+merges .gm82 asset indexes, resource trees, timestamps, instances and tiles, leaving the result in <ours>
+<path> is where the file is in the repository, which says what kind of file it is
+set it up for a repository with:
+    git config merge.gm82.driver \"git merge-driver %O %A %B %P\"
+and in .gitattributes:
+    index.yyd merge=gm82
+    tree.yyd merge=gm82
+    timestamps.yyd merge=gm82
+    rooms/**/*.txt merge=gm82";
+
+/// Returns whether there were any conflicts.
+fn merge(base: &str, ours: &str, theirs: &str, path: &str) -> Result<bool> {
+    let read = |path: &str| std::fs::read(path).map_err(|e| Error::FileIoError(e, path.into()));
+    let (base_text, ours_text, theirs_text) = (read(base)?, read(ours)?, read(theirs)?);
+    if let (Ok(base_text), Ok(ours_text), Ok(theirs_text)) =
+        (std::str::from_utf8(&base_text), std::str::from_utf8(&ours_text), std::str::from_utf8(&theirs_text))
+    {
+        if let Some((text, conflicts)) = merge_file(path, base_text, ours_text, theirs_text) {
+            std::fs::write(ours, text).map_err(|e| Error::FileIoError(e, ours.into()))?;
+            return Ok(conflicts != 0)
+        }
+    }
+    // anything else, like room.txt, gets merged the usual way
+    let status = Command::new("git")
+        .args(["merge-file", "-L", "ours", "-L", "base", "-L", "theirs", ours, base, theirs])
+        .status()
+        .map_err(Error::IoError)?;
+    Ok(!status.success())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        [base, ours, theirs, path] => merge(base, ours, theirs, path),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2)
+        },
+    };
+    match result {
+        Ok(false) => ExitCode::SUCCESS,
+        Ok(true) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(2)
+        },
+    }
+}
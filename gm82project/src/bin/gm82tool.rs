@@ -0,0 +1,406 @@
+use gm82project::{
+    AssetList, Error, Project, Result, TreeNode,
+    actionlib::load_libraries,
+    diff::diff_projects,
+    gamedata::{load_gamedata, save_gamedata},
+    gm81::GmkVersion,
+    lint::{self, Severity},
+    load_gm81, load_gmk,
+    rename::rename_asset,
+    save::{check_path_lengths, check_project, find_orphans},
+    save_gm81, save_gmk, transaction,
+    xref::CrossReference,
+};
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+const USAGE: &str = "usage: gm82tool <command> [args]
+
+commands:
+    check <project>                 load a project and report the first problem found
+        [--root <folder>]           also check that its paths fit on Windows when it's in this folder
+    info <project>                  print general information and asset counts
+    convert <input> <output>        load a project and save it somewhere else
+        [--libs <folder>]           GameMaker's lib folder, needed to save .gm81 and .gmk files
+        [--preserve-ids]            keep instance and tile ids when saving a .gm82 project
+    tree <project> [type]           print the resource tree, optionally only for one asset type
+    gamedata <project> <output>     build the encrypted game data that goes into an exe,
+                                    which can't be done for projects that use extension packages
+        --libs <folder>
+    extract <game> <output>         rebuild a .gm82 project from an exe or its game data
+        [--preserve-ids]
+    clean <project>                 resave a .gm82 project, removing files left over from deleted or renamed assets
+        [--dry-run]                 only list the files that would be removed, changing nothing
+    diff <old> <new>                list what changed between two revisions of a project, asset by asset
+    rename <project> <type> <old> <new>
+                                    rename an asset in a .gm82 project and update everything that refers to it
+        [--libs <folder>]           also update action arguments, which are only listed without it
+    xref <project> [<type> <name>]  list what refers to each asset, or only to one
+        [--libs <folder>]           also look inside action arguments that are expressions
+    xref <project> --json           print every reference as JSON
+        [--libs <folder>]
+    lint <project>                  look for likely mistakes and problems that would stop the project from saving,
+                                    failing if any are errors
+        [--libs <folder>]           also check that every action is in a library, and look inside action arguments
+        [--fix]                     fix what can be fixed and save the project, which has to be a .gm82
+    lint --rules                    list the rules
+
+projects can be .gm82, .gm81 or .gmk files, and flags can be given in any order";
+
+/// Points at the .gm82 file inside a directory if there's exactly one.
+fn find_gm82(path: &Path) -> Result<PathBuf> {
+    let mut files = std::fs::read_dir(path)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension() == Some(OsStr::new("gm82")) && p.is_file());
+    match (files.next(), files.next()) {
+        (Some(file), None) => Ok(file),
+        _ => Err(gm82project::Error::Other(format!("couldn't find a .gm82 file in {}", path.display()))),
+    }
+}
+
+fn load_project(path: &str) -> Result<Project> {
+    let path = Path::new(path);
+    if path.is_dir() {
+        return load_gmk(find_gm82(path)?)
+    }
+    match path.extension().and_then(OsStr::to_str) {
+        Some("gmk" | "gm81") => load_gm81(path),
+        _ => load_gmk(path),
+    }
+}
+
+fn check(path: &str, root: Option<&str>) -> Result<()> {
+    let mut project = load_project(path)?;
+    check_project(&project)?;
+    if let Some(root) = root {
+        let file = if Path::new(path).is_dir() { find_gm82(Path::new(path))? } else { PathBuf::from(path) };
+        let file_name = file.with_extension("gm82").file_name().map(|f| f.to_string_lossy().into_owned());
+        check_path_lengths(&mut project, file_name.as_deref().unwrap_or_default(), Path::new(root))?;
+    }
+    println!("{}: ok", path);
+    Ok(())
+}
+
+fn info(path: &str) -> Result<()> {
+    fn count<T>(list: &AssetList<T>) -> usize {
+        list.iter().count()
+    }
+    let project = load_project(path)?;
+    let settings = &project.settings;
+    println!("game id: {}", project.game_id);
+    println!(
+        "version: {}.{}.{}.{}",
+        settings.version_major, settings.version_minor, settings.version_release, settings.version_build
+    );
+    if !settings.info_author.is_empty() {
+        println!("author: {}", settings.info_author);
+    }
+    if !settings.exe_product.is_empty() {
+        println!("product: {}", settings.exe_product);
+    }
+    if !project.extensions.is_empty() {
+        println!("extensions: {}", project.extensions.join(", "));
+    }
+    println!();
+    println!("triggers: {}", project.triggers.iter().flatten().count());
+    println!("constants: {}", project.constants.len());
+    println!("sounds: {}", count(&project.sounds));
+    println!("sprites: {}", count(&project.sprites));
+    println!("backgrounds: {}", count(&project.backgrounds));
+    println!("paths: {}", count(&project.paths));
+    println!("scripts: {}", count(&project.scripts));
+    println!("fonts: {}", count(&project.fonts));
+    println!("timelines: {}", count(&project.timelines));
+    println!("objects: {}", count(&project.objects));
+    println!("rooms: {}", count(&project.rooms));
+    println!("included files: {}", project.included_files.len());
+    let (instances, tiles) =
+        project.rooms.iter().fold((0, 0), |(i, t), (_, room)| (i + room.instances.len(), t + room.tiles.len()));
+    println!("instances: {}", instances);
+    println!("tiles: {}", tiles);
+    Ok(())
+}
+
+fn convert(input: &str, output: &str, libs: Option<&str>, preserve_ids: bool) -> Result<()> {
+    let mut project = load_project(input)?;
+    project.preserve_ids |= preserve_ids;
+    let version = match Path::new(output).extension().and_then(OsStr::to_str) {
+        Some("gm82") => None,
+        Some("gm81") => Some(GmkVersion::Gm81),
+        Some("gmk") => Some(GmkVersion::Gm80),
+        _ => return Err(gm82project::Error::Other(format!("don't know how to save {}", output))),
+    };
+    match (version, libs) {
+        (None, _) => {
+            save_gmk(&mut project, output)?;
+        },
+        (Some(version), Some(libs)) => save_gm81(&project, &load_libraries(Path::new(libs))?, output, version)?,
+        (Some(_), None) => {
+            return Err(gm82project::Error::Other(format!(
+                "saving {} needs GameMaker's action libraries, use --libs <folder>",
+                output
+            )))
+        },
+    }
+    println!("converted {} to {}", input, output);
+    Ok(())
+}
+
+fn gamedata(path: &str, output: &str, libs: &str) -> Result<()> {
+    let project = load_project(path)?;
+    save_gamedata(&project, &load_libraries(Path::new(libs))?, output)?;
+    println!("built game data for {} in {}", path, output);
+    Ok(())
+}
+
+fn extract(path: &str, output: &str, preserve_ids: bool) -> Result<()> {
+    let mut project = load_gamedata(path)?;
+    project.preserve_ids = preserve_ids;
+    save_gmk(&mut project, output)?;
+    println!("extracted {} to {}", path, output);
+    Ok(())
+}
+
+fn clean(path: &str, dry_run: bool) -> Result<()> {
+    let path = Path::new(path);
+    let path = if path.is_dir() { find_gm82(path)? } else { path.to_path_buf() };
+    // loading finishes off an interrupted save, and a dry run mustn't change anything
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    if dry_run && transaction::needs_recovery(dir) {
+        return Err(Error::UnfinishedSave(dir.to_path_buf()))
+    }
+    let mut project = load_gmk(&path)?;
+    if dry_run {
+        for orphan in find_orphans(&mut project, &path)? {
+            println!("would remove {}", orphan);
+        }
+    } else {
+        for orphan in save_gmk(&mut project, &path)? {
+            println!("removed {}", orphan);
+        }
+    }
+    Ok(())
+}
+
+fn rename(path: &str, kind: &str, old: &str, new: &str, libs: Option<&str>) -> Result<()> {
+    let path = Path::new(path);
+    let path = if path.is_dir() { find_gm82(path)? } else { path.to_path_buf() };
+    let mut project = load_gmk(&path)?;
+    let libs = libs.map(|libs| load_libraries(Path::new(libs))).transpose()?;
+    let renamed = rename_asset(&mut project, kind, old, new, libs.as_deref())?;
+    save_gmk(&mut project, &path)?;
+    let count = renamed.count;
+    println!("renamed {} to {}, updating {} reference{}", old, new, count, if count == 1 { "" } else { "s" });
+    for owner in &renamed.unknown {
+        println!("{} has action arguments named {} that were left alone, pass --libs to update them", owner, old);
+    }
+    Ok(())
+}
+
+fn diff(old: &str, new: &str) -> Result<()> {
+    let changes = diff_projects(&load_project(old)?, &load_project(new)?)?;
+    if changes.is_empty() {
+        println!("no changes");
+    }
+    for change in changes {
+        println!("{}", change);
+    }
+    Ok(())
+}
+
+fn xref(path: &str, asset: Option<(&str, &str)>, libs: Option<&str>, json: bool) -> Result<()> {
+    let project = load_project(path)?;
+    let libs = libs.map(|libs| load_libraries(Path::new(libs))).transpose()?;
+    let xref = CrossReference::build(&project, libs.as_deref())?;
+    if json {
+        print!("{}", xref.to_json());
+    } else if let Some((kind, name)) = asset {
+        let mut users = xref.users(kind, name).peekable();
+        if users.peek().is_none() {
+            println!("nothing refers to {}/{}", kind, name);
+        }
+        for r in users {
+            println!("{}/{}: {}", r.from_kind, r.from_name, r.how);
+        }
+    } else {
+        let mut last = None;
+        for r in &xref.references {
+            if last != Some((r.kind, &r.name)) {
+                println!("{}/{}", r.kind, r.name);
+                last = Some((r.kind, &r.name));
+            }
+            println!("    {}/{}: {}", r.from_kind, r.from_name, r.how);
+        }
+    }
+    Ok(())
+}
+
+fn lint(path: &str, libs: Option<&str>, fix: bool) -> Result<()> {
+    let libs = libs.map(|libs| load_libraries(Path::new(libs))).transpose()?;
+    let project = if fix {
+        let path = Path::new(path);
+        let path = if path.is_dir() { find_gm82(path)? } else { path.to_path_buf() };
+        let mut project = load_gmk(&path)?;
+        let count = lint::fix(&mut project, libs.as_deref())?;
+        if count != 0 {
+            save_gmk(&mut project, &path)?;
+        }
+        println!("fixed {} problem{}", count, if count == 1 { "" } else { "s" });
+        project
+    } else {
+        load_project(path)?
+    };
+    let problems = lint::lint(&project, libs.as_deref());
+    for problem in &problems {
+        println!("{} [{}] {}: {}", problem.severity, problem.rule, problem.location, problem.message);
+    }
+    let errors = problems.iter().filter(|p| p.severity == Severity::Error).count();
+    match (errors, problems.len()) {
+        (0, 0) => println!("no problems"),
+        (0, warnings) => println!("{} warning{}", warnings, if warnings == 1 { "" } else { "s" }),
+        (errors, _) => {
+            return Err(gm82project::Error::Other(format!(
+                "found {} error{}",
+                errors,
+                if errors == 1 { "" } else { "s" }
+            )))
+        },
+    }
+    Ok(())
+}
+
+fn rules() -> Result<()> {
+    for rule in &lint::RULES {
+        println!(
+            "{:<26}{:<9}{}{}",
+            rule.name,
+            rule.severity,
+            rule.description,
+            if rule.can_fix() { " (fixable)" } else { "" }
+        );
+    }
+    Ok(())
+}
+
+fn tree(path: &str, kind: Option<&str>) -> Result<()> {
+    fn print_nodes(nodes: &[TreeNode], depth: usize) {
+        for node in nodes {
+            match node {
+                TreeNode::Folder(name, children) => {
+                    println!("{:indent$}{}/", "", name, indent = depth * 2);
+                    print_nodes(children, depth + 1);
+                },
+                TreeNode::Asset(name) => println!("{:indent$}{}", "", name, indent = depth * 2),
+            }
+        }
+    }
+    let project = load_project(path)?;
+    let trees = [
+        ("sprites", &project.sprites.tree),
+        ("sounds", &project.sounds.tree),
+        ("backgrounds", &project.backgrounds.tree),
+        ("paths", &project.paths.tree),
+        ("scripts", &project.scripts.tree),
+        ("fonts", &project.fonts.tree),
+        ("timelines", &project.timelines.tree),
+        ("objects", &project.objects.tree),
+        ("rooms", &project.rooms.tree),
+    ];
+    if let Some(kind) = kind {
+        if !trees.iter().any(|(name, _)| *name == kind) {
+            return Err(gm82project::Error::Other(format!("unknown asset type {}", kind)))
+        }
+    }
+    for (name, nodes) in trees.iter().filter(|(name, _)| kind.is_none_or(|k| k == *name)) {
+        println!("{}/", name);
+        print_nodes(nodes, 1);
+    }
+    Ok(())
+}
+
+/// Flags from the command line, which can come in any order and anywhere after the command.
+#[derive(Default)]
+struct Flags<'a> {
+    given: Vec<&'a str>,
+    libs: Option<&'a str>,
+    root: Option<&'a str>,
+    preserve_ids: bool,
+    dry_run: bool,
+    json: bool,
+    fix: bool,
+    rules: bool,
+}
+
+impl Flags<'_> {
+    /// Whether every flag that was given is one of these.
+    fn only(&self, allowed: &[&str]) -> bool {
+        self.given.iter().all(|flag| allowed.contains(flag))
+    }
+}
+
+/// Separates the flags from the other arguments, or returns None if a flag is unknown, repeated or missing its value.
+fn parse_args<'a>(args: &[&'a str]) -> Option<(Vec<&'a str>, Flags<'a>)> {
+    let mut positional = Vec::new();
+    let mut flags = Flags::default();
+    let mut iter = args.iter().copied();
+    while let Some(arg) = iter.next() {
+        if !arg.starts_with("--") {
+            positional.push(arg);
+            continue
+        }
+        if flags.given.contains(&arg) {
+            return None
+        }
+        flags.given.push(arg);
+        match arg {
+            "--libs" => flags.libs = Some(iter.next()?),
+            "--root" => flags.root = Some(iter.next()?),
+            "--preserve-ids" => flags.preserve_ids = true,
+            "--dry-run" => flags.dry_run = true,
+            "--json" => flags.json = true,
+            "--fix" => flags.fix = true,
+            "--rules" => flags.rules = true,
+            _ => return None,
+        }
+    }
+    Some((positional, flags))
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let (args, flags) = parse_args(&args).unwrap_or_else(|| (Vec::new(), Flags::default()));
+    let result = match (args.as_slice(), flags.libs) {
+        (["check", path], _) if flags.only(&["--root"]) => check(path, flags.root),
+        (["info", path], _) if flags.only(&[]) => info(path),
+        (["convert", input, output], libs) if flags.only(&["--libs", "--preserve-ids"]) => {
+            convert(input, output, libs, flags.preserve_ids)
+        },
+        (["tree", path], _) if flags.only(&[]) => tree(path, None),
+        (["tree", path, kind], _) if flags.only(&[]) => tree(path, Some(kind)),
+        (["gamedata", path, output], Some(libs)) if flags.only(&["--libs"]) => gamedata(path, output, libs),
+        (["extract", path, output], _) if flags.only(&["--preserve-ids"]) => extract(path, output, flags.preserve_ids),
+        (["clean", path], _) if flags.only(&["--dry-run"]) => clean(path, flags.dry_run),
+        (["diff", old, new], _) if flags.only(&[]) => diff(old, new),
+        (["rename", path, kind, old, new], libs) if flags.only(&["--libs"]) => rename(path, kind, old, new, libs),
+        (["lint"], _) if flags.rules && flags.only(&["--rules"]) => rules(),
+        (["lint", path], libs) if flags.only(&["--libs", "--fix"]) => lint(path, libs, flags.fix),
+        (["xref", path], libs) if flags.only(&["--libs", "--json"]) => xref(path, None, libs, flags.json),
+        (["xref", path, kind, name], libs) if flags.only(&["--libs"]) => xref(path, Some((kind, name)), libs, false),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2)
+        },
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
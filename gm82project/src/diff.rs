@@ -0,0 +1,379 @@
+// Compares two revisions of a project asset by asset, so changes can be reviewed without opening the IDE.
+// Properties are compared under the keys they're saved with, so what's reported matches what's in the files.
+
+use crate::{AssetList, Project, Result, asset::*, events::EVENT_NAMES, save::*};
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+};
+use xxhash_rust::xxh3::xxh3_64_with_seed;
+
+/// Lists what changed between two revisions of a project, one change per line,
+/// each starting with what it's about, like `sprite spr_player: origin_x 0 -> 16`.
+pub fn diff_projects(old: &Project, new: &Project) -> Result<Vec<String>> {
+    let mut out = Vec::new();
+    diff_settings(old, new, &mut out)?;
+    diff_named(
+        "constant",
+        &old.constants,
+        &new.constants,
+        |(n, _)| n,
+        &mut out,
+        |(_, old), (_, new), changes| {
+            if old != new {
+                changes.push(format!("{} -> {}", value(old), value(new)));
+            }
+            Ok(())
+        },
+    )?;
+    fn triggers(project: &Project) -> Vec<&Trigger> {
+        project.triggers.iter().flatten().collect()
+    }
+    diff_named(
+        "trigger",
+        &triggers(old),
+        &triggers(new),
+        |t| &t.name,
+        &mut out,
+        |old, new, changes| {
+            if old.constant_name != new.constant_name {
+                changes.push(format!("constant {} -> {}", value(&old.constant_name), value(&new.constant_name)));
+            }
+            if old.kind != new.kind {
+                changes.push(format!("kind {} -> {}", old.kind, new.kind));
+            }
+            if let Some(change) = code_change(&old.condition, &new.condition) {
+                changes.push(format!("condition changed ({})", change));
+            }
+            Ok(())
+        },
+    )?;
+    diff_assets("sound", &old.sounds, &new.sounds, &mut out, |old, new, changes| {
+        diff_txt(old, new, write_sound_txt, changes)?;
+        if old.data.is_some() && new.data.is_some() && old.data != new.data {
+            changes.push("audio changed".into());
+        }
+        Ok(())
+    })?;
+    diff_assets("sprite", &old.sprites, &new.sprites, &mut out, |old, new, changes| {
+        diff_txt(old, new, write_sprite_txt, changes)?;
+        diff_frames(&old.frames, &new.frames, changes);
+        Ok(())
+    })?;
+    diff_assets("background", &old.backgrounds, &new.backgrounds, &mut out, |old, new, changes| {
+        diff_txt(old, new, write_background_txt, changes)?;
+        diff_frames(std::slice::from_ref(&old.frame), std::slice::from_ref(&new.frame), changes);
+        Ok(())
+    })?;
+    diff_assets("path", &old.paths, &new.paths, &mut out, |old, new, changes| {
+        diff_txt(old, new, write_path_txt, changes)?;
+        if old.points.len() != new.points.len() {
+            changes.push(format!("points {} -> {}", old.points.len(), new.points.len()));
+        } else if old.points != new.points {
+            changes.push("points moved".into());
+        }
+        Ok(())
+    })?;
+    diff_assets("script", &old.scripts, &new.scripts, &mut out, |old, new, changes| {
+        if let Some(change) = code_change(&old.source, &new.source) {
+            changes.push(format!("code changed ({})", change));
+        }
+        Ok(())
+    })?;
+    diff_assets("font", &old.fonts, &new.fonts, &mut out, |old, new, changes| {
+        diff_txt(old, new, write_font_txt, changes)?;
+        match (&old.file, &new.file) {
+            (None, Some(file)) => changes.push(format!("added .{} file", file.extension)),
+            (Some(file), None) => changes.push(format!("removed .{} file", file.extension)),
+            (Some(a), Some(b)) if a != b => changes.push(format!(".{} file changed", b.extension)),
+            _ => (),
+        }
+        Ok(())
+    })?;
+    diff_assets("timeline", &old.timelines, &new.timelines, &mut out, |old, new, changes| {
+        fn moments(tl: &Timeline) -> BTreeMap<u32, &Event> {
+            tl.moments.iter().filter(|(_, ev)| !ev.actions.is_empty()).map(|(t, ev)| (*t, ev)).collect()
+        }
+        diff_events(&moments(old), &moments(new), |t| format!("moment {}", t), changes)
+    })?;
+    diff_assets("object", &old.objects, &new.objects, &mut out, |old, new, changes| {
+        diff_txt(old, new, write_object_txt, changes)?;
+        fn events(obj: &Object) -> BTreeMap<(usize, EventNumber), &Event> {
+            obj.events
+                .iter()
+                .enumerate()
+                .flat_map(|(t, group)| group.iter().map(move |(n, ev)| ((t, n.clone()), ev)))
+                .filter(|(_, ev)| !ev.actions.is_empty())
+                .collect()
+        }
+        diff_events(&events(old), &events(new), |(t, n)| format!("{}_{}", EVENT_NAMES[*t], n), changes)
+    })?;
+    diff_assets("room", &old.rooms, &new.rooms, &mut out, diff_room)?;
+    diff_named(
+        "included file",
+        &old.included_files,
+        &new.included_files,
+        |f| &f.file_name,
+        &mut out,
+        |old, new, changes| {
+            if old.data != new.data {
+                changes.push("contents changed".into());
+            }
+            if (&old.export_setting, &old.export_custom_folder, old.overwrite_file, old.free_memory, old.remove_at_end)
+                != (
+                    &new.export_setting,
+                    &new.export_custom_folder,
+                    new.overwrite_file,
+                    new.free_memory,
+                    new.remove_at_end,
+                )
+            {
+                changes.push("settings changed".into());
+            }
+            Ok(())
+        },
+    )?;
+    Ok(out)
+}
+
+/// Shows an empty value as something that can be seen.
+fn value(s: &str) -> &str {
+    if s.is_empty() { "(none)" } else { s }
+}
+
+/// How many items were added and removed going from `old` to `new`, not caring about order.
+fn count_changes<T: Eq + Hash>(old: impl IntoIterator<Item = T>, new: impl IntoIterator<Item = T>) -> (usize, usize) {
+    let mut counts = HashMap::new();
+    old.into_iter().for_each(|x| *counts.entry(x).or_insert(0isize) -= 1);
+    new.into_iter().for_each(|x| *counts.entry(x).or_insert(0isize) += 1);
+    counts.values().fold((0, 0), |(added, removed), &n| {
+        if n > 0 { (added + n as usize, removed) } else { (added, removed + n.unsigned_abs()) }
+    })
+}
+
+/// Sums up a code change as how many lines were added and removed, or None if nothing changed.
+fn code_change(old: &str, new: &str) -> Option<String> {
+    (old != new).then(|| {
+        let (added, removed) = count_changes(old.lines(), new.lines());
+        format!("+{} -{} lines", added, removed)
+    })
+}
+
+/// Compares the property files two versions of something would be saved as.
+fn diff_txt<T>(old: &T, new: &T, write: fn(&T, &mut Vec<u8>) -> Result<()>, changes: &mut Vec<String>) -> Result<()> {
+    let props = |x: &T| -> Result<Vec<(String, String)>> {
+        let mut buf = Vec::new();
+        write(x, &mut buf)?;
+        Ok(String::from_utf8_lossy(&buf)
+            .lines()
+            .filter_map(|l| l.split_once('='))
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect())
+    };
+    let (old, new) = (props(old)?, props(new)?);
+    let old_map = old.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect::<HashMap<_, _>>();
+    let new_map = new.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect::<HashMap<_, _>>();
+    for (k, v) in &old {
+        match new_map.get(k.as_str()) {
+            Some(n) if n != v => changes.push(format!("{} {} -> {}", k, value(v), value(n))),
+            Some(_) => (),
+            None => changes.push(format!("{} removed", k)),
+        }
+    }
+    for (k, v) in new.iter().filter(|(k, _)| !old_map.contains_key(k.as_str())) {
+        changes.push(format!("{} {} added", k, value(v)));
+    }
+    Ok(())
+}
+
+/// Frames are compared by a hash of their pixels, so frames that were only moved around aren't reported as changed.
+fn diff_frames(old: &[Frame], new: &[Frame], changes: &mut Vec<String>) {
+    let hash = |f: &Frame| xxh3_64_with_seed(&f.data, u64::from(f.width) << 32 | u64::from(f.height));
+    let (old, new) = (old.iter().map(hash).collect::<Vec<_>>(), new.iter().map(hash).collect::<Vec<_>>());
+    if old == new {
+        return
+    }
+    let (added, removed) = count_changes(&old, &new);
+    if added == 0 && removed == 0 {
+        changes.push("frames reordered".into());
+    } else if old.len() == new.len() {
+        let changed = old.iter().zip(&new).enumerate().filter(|(_, (a, b))| a != b).map(|(i, _)| i.to_string());
+        let changed = changed.collect::<Vec<_>>();
+        let plural = if changed.len() == 1 { "frame" } else { "frames" };
+        changes.push(format!("{} {} changed", plural, changed.join(", ")));
+    } else {
+        changes.push(format!("frames {} added, {} removed", added, removed));
+    }
+}
+
+fn diff_events<K: Ord>(
+    old: &BTreeMap<K, &Event>,
+    new: &BTreeMap<K, &Event>,
+    name: impl Fn(&K) -> String,
+    changes: &mut Vec<String>,
+) -> Result<()> {
+    // events are compared as they're written to the .gml, so that code changes can be counted in lines
+    let text = |ev: &Event| -> Result<String> {
+        let mut buf = Vec::new();
+        save_event(ev, "", &mut buf)?;
+        Ok(String::from_utf8_lossy(&buf).into_owned())
+    };
+    for (k, ev) in old {
+        match new.get(k) {
+            Some(new_ev) => {
+                if let Some(change) = code_change(&text(ev)?, &text(new_ev)?) {
+                    changes.push(format!("{} changed ({})", name(k), change));
+                }
+            },
+            None => changes.push(format!("{} removed", name(k))),
+        }
+    }
+    for k in new.keys().filter(|k| !old.contains_key(k)) {
+        changes.push(format!("{} added", name(k)));
+    }
+    Ok(())
+}
+
+fn diff_room(old: &Room, new: &Room, changes: &mut Vec<String>) -> Result<()> {
+    diff_txt(old, new, write_room_txt, changes)?;
+    if let Some(change) = code_change(&old.creation_code, &new.creation_code) {
+        changes.push(format!("creation code changed ({})", change));
+    }
+    // instances are told apart by name, which they keep no matter what else about them changes
+    let old_instances = old.instances.iter().filter(|i| i.name != 0).map(|i| (i.name, i)).collect::<HashMap<_, _>>();
+    let new_instances = new.instances.iter().filter(|i| i.name != 0).map(|i| (i.name, i)).collect::<HashMap<_, _>>();
+    let (mut moved, mut changed) = (0, 0);
+    for (name, old) in &old_instances {
+        if let Some(new) = new_instances.get(name) {
+            if (old.x, old.y) != (new.x, new.y) {
+                moved += 1;
+            }
+            if **old != (Instance { x: old.x, y: old.y, ..(*new).clone() }) {
+                changed += 1;
+            }
+        }
+    }
+    let unnamed = |instances: &[Instance]| instances.iter().filter(|i| i.name == 0).count();
+    let added = new_instances.keys().filter(|n| !old_instances.contains_key(n)).count() + unnamed(&new.instances);
+    let removed = old_instances.keys().filter(|n| !new_instances.contains_key(n)).count() + unnamed(&old.instances);
+    let counts = [(added, "added"), (removed, "removed"), (moved, "moved"), (changed, "changed")];
+    if counts.iter().any(|(n, _)| *n != 0) {
+        let counts = counts.iter().filter(|(n, _)| *n != 0).map(|(n, what)| format!("{} {}", n, what));
+        changes.push(format!("instances {}", counts.collect::<Vec<_>>().join(", ")));
+    }
+    // tiles don't have anything to tell them apart, so moving one counts as removing it and adding another
+    let tile = |t: &Tile| format!("{:?}", Tile { id: 0, ..t.clone() });
+    let (added, removed) = count_changes(old.tiles.iter().map(tile), new.tiles.iter().map(tile));
+    if added != 0 || removed != 0 {
+        changes.push(format!("tiles {} added, {} removed", added, removed));
+    }
+    Ok(())
+}
+
+fn diff_settings(old: &Project, new: &Project, out: &mut Vec<String>) -> Result<()> {
+    let mut changes = Vec::new();
+    if old.game_id != new.game_id {
+        changes.push(format!("game_id {} -> {}", old.game_id, new.game_id));
+    }
+    diff_txt(&old.settings, &new.settings, write_settings_txt, &mut changes)?;
+    if old.settings.icon != new.settings.icon {
+        changes.push("icon changed".into());
+    }
+    let (a, b) = (&old.settings, &new.settings);
+    if (&a.loading_background, &a.loading_foreground, &a.custom_load_image)
+        != (&b.loading_background, &b.loading_foreground, &b.custom_load_image)
+    {
+        changes.push("loading images changed".into());
+    }
+    for extension in new.extensions.iter().filter(|e| !old.extensions.contains(e)) {
+        changes.push(format!("extension {} added", extension));
+    }
+    for extension in old.extensions.iter().filter(|e| !new.extensions.contains(e)) {
+        changes.push(format!("extension {} removed", extension));
+    }
+    out.extend(changes.drain(..).map(|change| format!("settings: {}", change)));
+    diff_txt(&old.game_info, &new.game_info, write_game_information_txt, &mut changes)?;
+    if old.game_info.rtf != new.game_info.rtf {
+        changes.push("text changed".into());
+    }
+    out.extend(changes.into_iter().map(|change| format!("game information: {}", change)));
+    Ok(())
+}
+
+/// Compares lists of things that are only known by name.
+fn diff_named<T>(
+    kind: &str,
+    old: &[T],
+    new: &[T],
+    name: impl Fn(&T) -> &String,
+    out: &mut Vec<String>,
+    diff: impl Fn(&T, &T, &mut Vec<String>) -> Result<()>,
+) -> Result<()> {
+    let old_map = old.iter().map(|x| (name(x), x)).collect::<HashMap<_, _>>();
+    let new_map = new.iter().map(|x| (name(x), x)).collect::<HashMap<_, _>>();
+    let mut changes = Vec::new();
+    for x in new {
+        let name = name(x);
+        match old_map.get(name) {
+            Some(old) => {
+                diff(old, x, &mut changes)?;
+                out.extend(changes.drain(..).map(|change| format!("{} {}: {}", kind, name, change)));
+            },
+            None => out.push(format!("{} {}: added", kind, name)),
+        }
+    }
+    for x in old.iter().filter(|x| !new_map.contains_key(name(x))) {
+        out.push(format!("{} {}: removed", kind, name(x)));
+    }
+    Ok(())
+}
+
+/// Compares one asset type. Assets keep their slot when they're renamed, so anything that isn't found by name
+/// is matched up with whatever was in the same slot before.
+fn diff_assets<T>(
+    kind: &str,
+    old: &AssetList<T>,
+    new: &AssetList<T>,
+    out: &mut Vec<String>,
+    diff: impl Fn(&T, &T, &mut Vec<String>) -> Result<()>,
+) -> Result<()> {
+    fn slots<T>(list: &AssetList<T>) -> HashMap<&str, (usize, &T)> {
+        list.names
+            .iter()
+            .zip(&list.assets)
+            .enumerate()
+            .filter_map(|(i, (name, asset))| Some((name.as_str(), (i, asset.as_ref()?))))
+            .collect()
+    }
+    let (old_slots, new_slots) = (slots(old), slots(new));
+    let renamed_from = |i: usize| {
+        let name = old.names.get(i)?;
+        let asset = old.assets.get(i)?.as_ref()?;
+        (!new_slots.contains_key(name.as_str())).then_some((name, asset))
+    };
+    let mut changes = Vec::new();
+    let mut renamed = Vec::new();
+    for (name, asset) in new.iter() {
+        let i = new_slots[name].0;
+        let old_asset = match old_slots.get(name) {
+            Some((_, old_asset)) => old_asset,
+            None => match renamed_from(i) {
+                Some((old_name, old_asset)) => {
+                    out.push(format!("{} {}: renamed to {}", kind, old_name, name));
+                    renamed.push(old_name.as_str());
+                    old_asset
+                },
+                None => {
+                    out.push(format!("{} {}: added", kind, name));
+                    continue
+                },
+            },
+        };
+        diff(old_asset, asset, &mut changes)?;
+        out.extend(changes.drain(..).map(|change| format!("{} {}: {}", kind, name, change)));
+    }
+    for (name, _) in old.iter().filter(|(name, _)| !new_slots.contains_key(name) && !renamed.contains(name)) {
+        out.push(format!("{} {}: removed", kind, name));
+    }
+    Ok(())
+}
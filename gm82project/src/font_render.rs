@@ -0,0 +1,102 @@
+// Renders fonts from a .ttf or .otf file into the glyph tables and atlas that exes store.
+// This follows gm82save's font_render.rs, which does the same for installed fonts through gdi,
+// so the layout of the atlas matches what the IDE would build.
+
+use crate::{Error, Result, asset::Font, gm81::CP1252, pack};
+use ab_glyph::{Font as _, FontRef, OutlinedGlyph, ScaleFont, point};
+
+pub struct RenderedFont {
+    pub s_x: [u32; 256],
+    pub s_y: [u32; 256],
+    pub s_w: [u32; 256],
+    pub s_h: [u32; 256],
+    pub s_shift: [u32; 256],
+    pub s_offset: [u32; 256],
+    pub s_bw: u32,
+    pub s_bh: u32,
+    pub s_bytes: Vec<u8>,
+}
+
+/// The character GameMaker draws for a byte. Only Windows-1252 is known here, so every charset gets that.
+fn byte_to_char(c: u8) -> char {
+    if (0x80..0xa0).contains(&c) { CP1252[usize::from(c - 0x80)] } else { char::from(c) }
+}
+
+/// Renders `font` using the contents of its font file. Bold and italic aren't applied, since the file is one face.
+pub fn render_font(font: &Font, file: &[u8]) -> Result<RenderedFont> {
+    let bad_font = || Error::Other(format!("couldn't read font file for {}", font.sys_name));
+    let face = FontRef::try_from_slice(file).map_err(|_| bad_font())?;
+    // the ide renders at 96 dpi regardless of the screen
+    let scale = face.pt_to_px_scale(font.size as f32).ok_or_else(bad_font)?;
+    let scaled = face.as_scaled(scale);
+    let ascent = scaled.ascent().round();
+    // same number of grey levels as gdi's GGO_BITMAP, GGO_GRAY2_BITMAP, GGO_GRAY4_BITMAP and GGO_GRAY8_BITMAP
+    let levels = [1.0, 4.0, 16.0, 64.0].get(font.aa_level as usize).copied().unwrap_or(1.0);
+    let mut r = RenderedFont {
+        s_x: [0; 256],
+        s_y: [0; 256],
+        s_w: [0; 256],
+        s_h: [0; 256],
+        s_shift: [0; 256],
+        s_offset: [0; 256],
+        s_bw: 0,
+        s_bh: 0,
+        s_bytes: Vec::new(),
+    };
+    let range = font.range_start.min(255)..=font.range_end.min(255);
+    let mut outlines: Vec<Option<OutlinedGlyph>> = (0..256).map(|_| None).collect();
+    // calculate character sizes
+    for c in range.clone() {
+        let c = c as usize;
+        let glyph = face.glyph_id(byte_to_char(c as u8)).with_scale_and_position(scale, point(0.0, ascent));
+        let advance = scaled.h_advance(glyph.id).round() as i32;
+        if let Some(outline) = face.outline_glyph(glyph) {
+            let bounds = outline.px_bounds();
+            r.s_w[c] = (bounds.max.x - bounds.min.x) as u32;
+            r.s_h[c] = bounds.max.y.max(0.0) as u32;
+            r.s_shift[c] = (advance - (bounds.min.x as i32).min(0)) as u32;
+            r.s_offset[c] = bounds.min.x as i32 as u32;
+            outlines[c] = Some(outline);
+        }
+    }
+    if r.s_h[0x20] == 0 {
+        r.s_h[0x20] = scaled.height().round() as u32;
+    }
+    if r.s_w[0x20] == 0 {
+        r.s_w[0x20] = scaled.h_advance(face.glyph_id(' ')).round() as u32;
+        r.s_shift[0x20] = r.s_w[0x20];
+    }
+    // pack the glyphs, leaving a gap so they don't bleed into each other when drawn scaled
+    let chars = range.collect::<Vec<_>>();
+    let sizes = chars.iter().map(|&c| (r.s_w[c as usize], r.s_h[c as usize])).collect::<Vec<_>>();
+    let packing = pack::pack(&sizes, 2, 0x800)
+        .ok_or_else(|| Error::Other(format!("font {} doesn't fit on a 2048x2048 texture", font.sys_name)))?;
+    r.s_bw = packing.width;
+    r.s_bh = packing.height;
+    r.s_bytes = vec![0; (r.s_bw * r.s_bh) as usize];
+    // draw the characters
+    for (c, (x, y)) in chars.into_iter().zip(packing.positions) {
+        let c = c as usize;
+        r.s_x[c] = x;
+        r.s_y[c] = y;
+        if let Some(outline) = outlines[c].as_ref().filter(|_| c != 0x20) {
+            let top = outline.px_bounds().min.y as i32;
+            let (bw, bh, cell_h) = (r.s_bw, r.s_bh, r.s_h[c]);
+            let bytes = &mut r.s_bytes;
+            outline.draw(|gx, gy, coverage| {
+                // anything poking out above the ascent gets cut off, like it does with gdi
+                let Ok(cell_y) = u32::try_from(top + gy as i32) else { return };
+                let (px, py) = (x + gx, y + cell_y);
+                if cell_y < cell_h && px < bw && py < bh {
+                    let coverage = coverage.clamp(0.0, 1.0);
+                    bytes[(py * bw + px) as usize] = if levels == 1.0 {
+                        if coverage >= 0.5 { 255 } else { 0 }
+                    } else {
+                        ((coverage * levels).round() / levels * 255.0) as u8
+                    };
+                }
+            });
+        }
+    }
+    Ok(r)
+}
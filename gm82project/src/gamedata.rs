@@ -0,0 +1,374 @@
+// Builds the game data that goes into an exe, same as gm82save's write_encrypted_gamedata does inside the IDE,
+// and reads it back out of built games.
+// The runner reads the same sections as a .gm81, minus anything only the IDE cares about,
+// plus collision masks and constants for every instance name that's referenced in code.
+// Extension packages aren't supported when building, since the IDE takes those from the installed packages.
+
+use crate::{
+    AssetList, Error, Project, Result, TreeNode,
+    actionlib::ActionLibrary,
+    asset::{ActionParams, Event},
+    gm81::{self, GmkVersion, Indices, Reader, expect, read_bool, read_buffer, read_string},
+    gml,
+};
+use byteorder::{LE, ReadBytesExt, WriteBytesExt};
+use rayon::prelude::*;
+use std::{
+    collections::{HashMap, HashSet},
+    io::{Cursor, Read, Write},
+    path::Path,
+};
+
+fn event_strings(event: &Event) -> impl Iterator<Item = &str> {
+    event.actions.iter().flat_map(|a| {
+        let params = match &a.params {
+            ActionParams::Normal { args, .. } => args.iter().map(String::as_str).collect(),
+            ActionParams::Repeat(times) => vec![times.as_str()],
+            ActionParams::Variable { name, value } => vec![name.as_str(), value.as_str()],
+            ActionParams::None => Vec::new(),
+        };
+        params.into_iter().chain(std::iter::once(a.code.as_str()))
+    })
+}
+
+/// Writes the constants section, with `<room>_XXXXXXXX` constants for every instance that's referenced in code.
+fn write_constants(project: &Project, mut out: impl Write) -> Result<()> {
+    // we want to collect instance names that are actually used
+    // iterate over all code
+    let rooms = project.rooms.iter().flat_map(|(_, room)| {
+        room.instances.iter().map(|i| i.creation_code.as_str()).chain(std::iter::once(room.creation_code.as_str()))
+    });
+    let objects =
+        project.objects.iter().flat_map(|(_, o)| o.events.iter().flatten().flat_map(|(_, e)| event_strings(e)));
+    let timelines = project.timelines.iter().flat_map(|(_, t)| t.moments.iter().flat_map(|(_, e)| event_strings(e)));
+    let scripts = project.scripts.iter().map(|(_, s)| s.source.as_str());
+    let triggers = project.triggers.iter().flatten().map(|t| t.condition.as_str());
+    let constants = project.constants.iter().map(|(_, v)| v.as_str());
+    let code =
+        rooms.chain(objects).chain(timelines).chain(scripts).chain(triggers).chain(constants).collect::<Vec<_>>();
+
+    // find instance names in code
+    let instance_names: HashSet<u32> = code
+        .par_iter()
+        .flat_map_iter(|s| gml::identifiers(s).filter_map(|t| Some(gml::instance_name(t.text)?.1)))
+        .collect();
+
+    // collect data for referenced instances, using the same ids the rooms get written with
+    let (room_ids, _) = gm81::instance_ids(&project.rooms);
+    let instances = project
+        .rooms
+        .assets
+        .iter()
+        .zip(&project.rooms.names)
+        .zip(room_ids)
+        .filter_map(|((room, name), ids)| Some((room.as_ref()?, name, ids)))
+        .flat_map(|(room, room_name, (ids, _))| {
+            let instance_names = &instance_names;
+            room.instances
+                .iter()
+                .zip(ids)
+                .filter(move |(inst, _)| inst.name != 0 && instance_names.contains(&inst.name))
+                .map(move |(inst, id)| (room_name, id, inst.name))
+        })
+        .collect::<Vec<_>>();
+
+    out.write_u32::<LE>(800)?;
+    out.write_u32::<LE>((project.constants.len() + instances.len()) as u32)?;
+    // write instance ids
+    for (room_name, id, name) in instances {
+        gm81::write_string(&format!("{}_{:08X}", room_name, name), GmkVersion::Gm81, &mut out)?;
+        gm81::write_string(&id.to_string(), GmkVersion::Gm81, &mut out)?;
+    }
+    // write original constants
+    for (name, value) in &project.constants {
+        gm81::write_string(name, GmkVersion::Gm81, &mut out)?;
+        gm81::write_string(value, GmkVersion::Gm81, &mut out)?;
+    }
+    Ok(())
+}
+
+/// The settings block, which goes in front of the game data rather than inside it.
+pub fn write_settings(project: &Project) -> Result<Vec<u8>> {
+    let ctx = Indices::new(project, &[], GmkVersion::Gm81, true);
+    let mut out = Vec::new();
+    gm81::write_settings(project, &ctx, &mut out)?;
+    Ok(out)
+}
+
+/// Builds the encrypted game data, including the swap table header in front of it.
+/// Action definitions aren't part of the project, so they get looked up in `libs`.
+pub fn write_gamedata(project: &Project, libs: &[ActionLibrary]) -> Result<Vec<u8>> {
+    crate::save::check_project(project)?;
+    if !project.extensions.is_empty() {
+        // the ide builds these from the installed packages, which we don't have
+        return Err(Error::Other(format!(
+            "can't build extension packages into a game: {}",
+            project.extensions.join(", ")
+        )))
+    }
+    let ctx = Indices::new(project, libs, GmkVersion::Gm81, true);
+    let mut out = Vec::new();
+    let stream = &mut out;
+    // write encryption headers
+    // no garbage data
+    stream.write_u32::<LE>(0)?;
+    stream.write_u32::<LE>(0)?;
+    // no swap table: it's just 0,1,2,etc
+    for i in 0..=255 {
+        stream.write_u8(i)?;
+    }
+    let length_pos = stream.len();
+    stream.write_u32::<LE>(0)?;
+    // encrypted data start
+    let data_pos = stream.len();
+    // no garbage data
+    stream.write_u32::<LE>(0)?;
+    stream.write_u32::<LE>(1)?;
+    // generate gamedata
+    stream.write_u32::<LE>(project.game_id)?;
+    stream.write_all(&[0; 16])?; // guid
+    stream.write_u32::<LE>(700)?;
+    stream.write_u32::<LE>(0)?; // extensions
+    gm81::write_triggers(project, &ctx, &mut *stream)?;
+    write_constants(project, &mut *stream)?;
+    gm81::write_all_assets(project, &ctx, &mut *stream)?;
+    gm81::write_included_files(project, &ctx, &mut *stream)?;
+    gm81::write_game_information(&project.game_info, &ctx, &mut *stream)?;
+    stream.write_u32::<LE>(500)?;
+    stream.write_u32::<LE>(libs.len() as u32)?;
+    for lib in libs {
+        gm81::write_string(&lib.init_code, GmkVersion::Gm81, &mut *stream)?;
+    }
+    gm81::write_room_order(project, &ctx, &mut *stream)?;
+    // write a few null bytes so i don't have to figure out how to fix the decompiler
+    for _ in 0..4 {
+        stream.write_u32::<LE>(0)?;
+    }
+    let data_len = stream.len() - data_pos;
+    stream[length_pos..data_pos].copy_from_slice(&(data_len as u32).to_le_bytes());
+    let data = &mut stream[data_pos..];
+    // first pass: swap bytes around
+    for i in 0..data_len {
+        data.swap(i, i & !0xff);
+    }
+    // second pass
+    for i in 1..data_len {
+        data[i] = data[i].wrapping_add(data[i - 1]).wrapping_add(i as u8);
+    }
+    Ok(out)
+}
+
+/// Builds a project's game data and writes it to a file.
+pub fn save_gamedata(project: &Project, libs: &[ActionLibrary], path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    let data = write_gamedata(project, libs)?;
+    std::fs::write(path, data).map_err(|e| Error::FileIoError(e, path.to_path_buf()))
+}
+
+/// Skips over padding, failing if it runs past the end instead of leaving the reader out of bounds.
+fn skip(r: &mut Reader, len: u64) -> Result<()> {
+    match r.position().checked_add(len) {
+        Some(end) if end <= r.get_ref().len() as u64 => {
+            r.set_position(end);
+            Ok(())
+        },
+        _ => Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()),
+    }
+}
+
+/// Undoes the encryption on a game data section, leaving just the decrypted contents.
+/// Fails if the data is cut short or the swap table isn't a permutation of 0 to 255.
+pub fn decrypt_gamedata(data: &[u8]) -> Result<Vec<u8>> {
+    let r = &mut Cursor::new(data);
+    let garbage1 = u64::from(r.read_u32::<LE>()?) * 4;
+    let garbage2 = u64::from(r.read_u32::<LE>()?) * 4;
+    skip(r, garbage1)?;
+    let mut swap_table = [0u8; 256];
+    r.read_exact(&mut swap_table)?;
+    skip(r, garbage2)?;
+    let mut data = read_buffer(r)?;
+    let mut reverse_table = [None; 256];
+    for (i, &b) in swap_table.iter().enumerate() {
+        if reverse_table[usize::from(b)].replace(i as u8).is_some() {
+            return Err(Error::Other(format!("game data has a corrupt swap table: {} appears twice", b)))
+        }
+    }
+    // every slot is filled, since 256 distinct values were written into 256 slots
+    let reverse_table = reverse_table.map(Option::unwrap);
+    // undo the second pass, back to front so the previous byte is still encrypted
+    for i in (1..data.len()).rev() {
+        data[i] = reverse_table[usize::from(data[i])].wrapping_sub(data[i - 1]).wrapping_sub(i as u8);
+    }
+    // undo the swaps in reverse order
+    // offsets that go past the start swap with the first byte, same as in the runner,
+    // so a valid table can still reach back that far in the first 256 bytes
+    for i in (0..data.len()).rev() {
+        data.swap(i, i.saturating_sub(usize::from(swap_table[i & 0xff])));
+    }
+    Ok(data)
+}
+
+/// Only the names are kept, since a project just lists which packages it uses.
+fn read_extensions(r: &mut Reader) -> Result<Vec<String>> {
+    fn skip_strings(r: &mut Reader, count: usize) -> Result<()> {
+        for _ in 0..count {
+            read_buffer(r)?;
+        }
+        Ok(())
+    }
+    expect(r, "extensions version", &[700])?;
+    let count = r.read_u32::<LE>()?;
+    (0..count)
+        .map(|_| {
+            expect(r, "extension version", &[700])?;
+            let name = read_string(r)?;
+            skip_strings(r, 1)?; // folder name
+            let file_count = r.read_u32::<LE>()?;
+            for _ in 0..file_count {
+                expect(r, "extension file version", &[700])?;
+                skip_strings(r, 1)?; // file name
+                r.read_u32::<LE>()?; // kind
+                skip_strings(r, 2)?; // initializer and finalizer
+                let function_count = r.read_u32::<LE>()?;
+                for _ in 0..function_count {
+                    expect(r, "extension function version", &[700])?;
+                    skip_strings(r, 2)?; // name and external name
+                    // calling convention, id, argument count, 17 argument types, return type
+                    r.set_position(r.position() + 4 * 21);
+                }
+                let constant_count = r.read_u32::<LE>()?;
+                for _ in 0..constant_count {
+                    expect(r, "extension constant version", &[700])?;
+                    skip_strings(r, 2)?; // name and value
+                }
+            }
+            read_buffer(r)?; // file contents
+            Ok(name)
+        })
+        .collect()
+}
+
+/// The tree a list would get if all its assets were added in order.
+fn flat_tree<T>(list: &AssetList<T>) -> Vec<TreeNode> {
+    list.iter().map(|(name, _)| TreeNode::Asset(name.to_string())).collect()
+}
+
+/// Turns a decrypted game data section back into a project, as far as that's possible.
+/// Anything that only lives in the IDE, like room editor settings, comes back as defaults,
+/// and settings aren't part of this section at all.
+pub fn read_decrypted_gamedata(data: &[u8]) -> Result<Project> {
+    let r = &mut Cursor::new(data);
+    let garbage = r.read_u32::<LE>()?;
+    r.set_position(r.position() + u64::from(garbage) * 4);
+    let _pro = read_bool(r)?;
+    let mut project = Project { game_id: r.read_u32::<LE>()?, ..Default::default() };
+    let mut guid = [0; 16];
+    r.read_exact(&mut guid)?;
+    project.extensions = read_extensions(r)?;
+    project.triggers = gm81::read_triggers(r, true)?;
+    expect(r, "constants version", &[800])?;
+    let constant_count = r.read_u32::<LE>()?;
+    let constants = (0..constant_count).map(|_| Ok((read_string(r)?, read_string(r)?))).collect::<Result<Vec<_>>>()?;
+    gm81::read_all_assets(r, &mut project, 810, true)?;
+    project.included_files = gm81::read_included_files(r, true)?;
+    project.game_info = gm81::read_game_information(r, true)?;
+    expect(r, "library initialization code version", &[500])?;
+    let lib_code_count = r.read_u32::<LE>()?;
+    for _ in 0..lib_code_count {
+        read_buffer(r)?;
+    }
+    expect(r, "room order version", &[700])?;
+    let room_count = r.read_u32::<LE>()?;
+    let room_order = (0..room_count).map(|_| r.read_i32::<LE>()).collect::<std::io::Result<Vec<_>>>()?;
+
+    // the constants say which name goes with which instance id
+    let instance_rooms: HashMap<usize, &str> = project
+        .rooms
+        .iter()
+        .flat_map(|(room_name, room)| room.instances.iter().map(move |i| (i.id, room_name)))
+        .collect();
+    let mut instance_names = HashMap::new();
+    project.constants = constants
+        .into_iter()
+        .filter(|(name, value)| {
+            let Some((room, hex)) = name.rsplit_once('_') else { return true };
+            if hex.len() != 8 || !hex.bytes().all(|b| b.is_ascii_digit() || (b'A'..=b'F').contains(&b)) {
+                return true
+            }
+            match (u32::from_str_radix(hex, 16), value.parse::<usize>()) {
+                (Ok(inst_name), Ok(id)) if instance_rooms.get(&id) == Some(&room) => {
+                    instance_names.insert(id, inst_name);
+                    false
+                },
+                _ => true,
+            }
+        })
+        .collect();
+    // instances that weren't referenced get a new name when the project is saved
+    for (_, room) in project.rooms.iter_mut() {
+        for inst in &mut room.instances {
+            inst.name = instance_names.get(&inst.id).copied().unwrap_or(0);
+        }
+    }
+
+    project.sprites.tree = flat_tree(&project.sprites);
+    project.sounds.tree = flat_tree(&project.sounds);
+    project.backgrounds.tree = flat_tree(&project.backgrounds);
+    project.paths.tree = flat_tree(&project.paths);
+    project.scripts.tree = flat_tree(&project.scripts);
+    project.fonts.tree = flat_tree(&project.fonts);
+    project.timelines.tree = flat_tree(&project.timelines);
+    project.objects.tree = flat_tree(&project.objects);
+    // rooms go in the order they're played in, followed by any that were left out of it
+    let rooms = &project.rooms;
+    let mut room_tree = room_order
+        .iter()
+        .filter_map(|&i| {
+            let i = usize::try_from(i).ok()?;
+            rooms.assets.get(i)?.as_ref().map(|_| rooms.names[i].clone())
+        })
+        .collect::<Vec<_>>();
+    for (name, _) in rooms.iter() {
+        if !room_tree.iter().any(|n| n == name) {
+            room_tree.push(name.to_string());
+        }
+    }
+    project.rooms.tree = room_tree.into_iter().map(TreeNode::Asset).collect();
+    Ok(project)
+}
+
+/// Turns an encrypted game data section, as written by `write_gamedata`, back into a project.
+pub fn read_gamedata(data: &[u8]) -> Result<Project> {
+    read_decrypted_gamedata(&decrypt_gamedata(data)?)
+}
+
+/// Finds the game data in an exe and turns it back into a project, settings included.
+/// This expects the header to be unobfuscated, like it is in exes built by GameMaker 8.0 and gm82.
+pub fn read_exe(exe: &[u8]) -> Result<Project> {
+    let mut header = Vec::with_capacity(8);
+    header.extend_from_slice(&gm81::GMK_MAGIC.to_le_bytes());
+    for version in [810u32, 800] {
+        header.truncate(4);
+        header.extend_from_slice(&version.to_le_bytes());
+        let Some(pos) = exe.windows(8).position(|w| w == header) else { continue };
+        let r = &mut Cursor::new(exe);
+        r.set_position((pos + 8) as u64);
+        let _debug = read_bool(r)?;
+        let mut settings = Default::default();
+        gm81::read_settings(r, &mut settings, true)?;
+        // d3dx8.dll
+        read_buffer(r)?;
+        read_buffer(r)?;
+        let mut project = read_gamedata(&exe[r.position() as usize..])?;
+        project.settings = settings;
+        return Ok(project)
+    }
+    Err(Error::Other("couldn't find any game data".into()))
+}
+
+/// Loads a game's data, either from an exe or from a file containing just the game data section.
+pub fn load_gamedata(path: impl AsRef<Path>) -> Result<Project> {
+    let path = path.as_ref();
+    let data = std::fs::read(path).map_err(|e| Error::FileIoError(e, path.to_path_buf()))?;
+    if data.starts_with(b"MZ") { read_exe(&data) } else { read_gamedata(&data) }
+}
@@ -0,0 +1,1679 @@
+// Reader and writer for GameMaker's own binary project format: .gmk (8.0) and .gm81 (8.1).
+// Assets refer to each other by index in these files, so every block is inflated first,
+// then all the names are collected, and only then are the assets themselves parsed.
+
+use crate::{
+    AssetList, Error, GameInformation, Project, Result, Settings, TreeNode,
+    actionlib::{self, ActionLibrary},
+    asset::*,
+    events, font_render, mask,
+};
+use byteorder::{LE, ReadBytesExt, WriteBytesExt};
+use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
+use rayon::prelude::*;
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read, Write},
+    path::Path as FsPath,
+};
+
+pub const GMK_MAGIC: u32 = 1234321;
+
+pub(crate) type Reader<'a> = Cursor<&'a [u8]>;
+
+pub(crate) fn expect(r: &mut Reader, what: &str, allowed: &[u32]) -> Result<u32> {
+    let value = r.read_u32::<LE>()?;
+    if allowed.contains(&value) { Ok(value) } else { Err(Error::Other(format!("unsupported {} {}", what, value))) }
+}
+
+pub(crate) fn read_bool(r: &mut Reader) -> Result<bool> {
+    Ok(r.read_u32::<LE>()? != 0)
+}
+
+pub(crate) fn read_buffer(r: &mut Reader) -> Result<Vec<u8>> {
+    let len = r.read_u32::<LE>()? as usize;
+    let start = r.position() as usize;
+    let data =
+        r.get_ref().get(start..start + len).ok_or_else(|| std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?;
+    r.set_position((start + len) as u64);
+    Ok(data.to_vec())
+}
+
+// the part of windows-1252 that isn't the same as latin-1
+pub(crate) const CP1252: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}', '\u{90}', '‘', '’',
+    '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+/// Strings are UTF-8 if the file was saved with gm82save, and the system codepage otherwise.
+/// There's no way to know which codepage that was, so assume Windows-1252 like most GM8 users.
+pub fn decode_string(data: Vec<u8>) -> String {
+    match String::from_utf8(data) {
+        Ok(s) => s,
+        Err(e) => e
+            .into_bytes()
+            .into_iter()
+            .map(|b| if (0x80..0xa0).contains(&b) { CP1252[usize::from(b - 0x80)] } else { char::from(b) })
+            .collect(),
+    }
+}
+
+pub(crate) fn read_string(r: &mut Reader) -> Result<String> {
+    Ok(decode_string(read_buffer(r)?))
+}
+
+fn inflate(data: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() * 2);
+    ZlibDecoder::new(data).read_to_end(&mut out)?;
+    Ok(out)
+}
+
+pub(crate) fn read_block(r: &mut Reader) -> Result<Vec<u8>> {
+    inflate(&read_buffer(r)?)
+}
+
+/// An asset block that's been inflated and had its name read, but hasn't been parsed yet.
+struct RawAsset {
+    name: String,
+    timestamp: f64,
+    data: Vec<u8>,
+    // where the asset data starts
+    pos: u64,
+}
+
+fn read_asset_blocks(r: &mut Reader, what: &str, has_timestamp: bool) -> Result<Vec<Option<RawAsset>>> {
+    expect(r, &format!("{} version", what), &[800])?;
+    let count = r.read_u32::<LE>()? as usize;
+    let blocks = (0..count).map(|_| read_buffer(r)).collect::<Result<Vec<_>>>()?;
+    blocks
+        .into_par_iter()
+        .map(|block| {
+            let data = inflate(&block)?;
+            let mut r = Cursor::new(data.as_slice());
+            if !read_bool(&mut r)? {
+                return Ok(None)
+            }
+            let name = read_string(&mut r)?;
+            let timestamp = if has_timestamp { r.read_f64::<LE>()? } else { 0.0 };
+            let pos = r.position();
+            Ok(Some(RawAsset { name, timestamp, data, pos }))
+        })
+        .collect()
+}
+
+fn names_of(raw: &[Option<RawAsset>]) -> Vec<String> {
+    raw.iter().map(|a| a.as_ref().map(|a| a.name.clone()).unwrap_or_default()).collect()
+}
+
+fn timestamps_of(raw: &[Option<RawAsset>]) -> Vec<f64> {
+    raw.iter().map(|a| a.as_ref().map_or(0.0, |a| a.timestamp)).collect()
+}
+
+/// Names of every asset, so indices can be turned back into names, and what's being read.
+pub(crate) struct Names {
+    triggers: Vec<String>,
+    sprites: Vec<String>,
+    sounds: Vec<String>,
+    backgrounds: Vec<String>,
+    paths: Vec<String>,
+    scripts: Vec<String>,
+    fonts: Vec<String>,
+    timelines: Vec<String>,
+    objects: Vec<String>,
+    rooms: Vec<String>,
+    // 800 for .gmk, 810 for .gm81
+    version: u32,
+    /// Whether this came out of an exe rather than a project file.
+    exe: bool,
+}
+
+fn name_of(names: &[String], index: i32) -> String {
+    usize::try_from(index).ok().and_then(|i| names.get(i)).cloned().unwrap_or_default()
+}
+
+fn parse_assets<T: Send>(
+    raw: Vec<Option<RawAsset>>,
+    names: &Names,
+    parse: fn(&mut Reader, &Names) -> Result<T>,
+) -> Result<Vec<Option<T>>> {
+    raw.into_par_iter()
+        .map(|raw| {
+            raw.map(|raw| {
+                let mut r = Cursor::new(raw.data.as_slice());
+                r.set_position(raw.pos);
+                parse(&mut r, names).map_err(|e| match e {
+                    Error::IoError(_) => Error::Other(format!("{} is corrupted", raw.name)),
+                    e => e,
+                })
+            })
+            .transpose()
+        })
+        .collect()
+}
+
+pub(crate) fn read_settings(r: &mut Reader, settings: &mut Settings, exe: bool) -> Result<()> {
+    let version = expect(r, "settings version", if exe { &[800, 825] } else { &[800, 810] })?;
+    let data = read_block(r)?;
+    let r = &mut Cursor::new(data.as_slice());
+    settings.fullscreen = read_bool(r)?;
+    settings.interpolate_pixels = read_bool(r)?;
+    settings.dont_draw_border = read_bool(r)?;
+    settings.display_cursor = read_bool(r)?;
+    settings.scaling = r.read_i32::<LE>()?;
+    settings.allow_resize = read_bool(r)?;
+    settings.window_on_top = read_bool(r)?;
+    settings.clear_colour = r.read_u32::<LE>()?;
+    settings.set_resolution = read_bool(r)?;
+    settings.colour_depth = r.read_u32::<LE>()?;
+    settings.resolution = r.read_u32::<LE>()?;
+    settings.frequency = r.read_u32::<LE>()?;
+    settings.dont_show_buttons = read_bool(r)?;
+    let vsync_and_force_cpu = r.read_u32::<LE>()?;
+    settings.vsync = vsync_and_force_cpu & 1 != 0;
+    settings.swap_creation_events = !exe && vsync_and_force_cpu & (1 << 31) != 0;
+    settings.disable_screensaver = read_bool(r)?;
+    settings.f4_fullscreen = read_bool(r)?;
+    settings.f1_help = read_bool(r)?;
+    settings.esc_close = read_bool(r)?;
+    settings.f5_save_f6_load = read_bool(r)?;
+    settings.f9_screenshot = read_bool(r)?;
+    settings.treat_close_as_esc = read_bool(r)?;
+    settings.priority = r.read_u32::<LE>()?;
+    settings.freeze_on_lose_focus = read_bool(r)?;
+    settings.loading_bar = r.read_u32::<LE>()?;
+    if settings.loading_bar == 2 {
+        if read_bool(r)? {
+            settings.loading_background = Some(read_block(r)?);
+        }
+        if read_bool(r)? {
+            settings.loading_foreground = Some(read_block(r)?);
+        }
+    }
+    if read_bool(r)? && read_bool(r)? {
+        settings.custom_load_image = Some(read_block(r)?);
+    }
+    settings.loading_transparent = read_bool(r)?;
+    settings.loading_translucency = r.read_u32::<LE>()?;
+    settings.loading_progress_bar_scale = read_bool(r)?;
+    if !exe {
+        settings.icon = read_buffer(r)?;
+    }
+    settings.show_error_messages = read_bool(r)?;
+    settings.log_errors = read_bool(r)?;
+    settings.always_abort = read_bool(r)?;
+    let uninitialized = r.read_u32::<LE>()?;
+    settings.zero_uninitialized_vars = uninitialized & 1 != 0;
+    settings.error_on_uninitialized_args = uninitialized & 2 != 0;
+    if exe {
+        if version == 825 {
+            let _webgl = r.read_u32::<LE>()?;
+            settings.swap_creation_events = read_bool(r)?;
+        }
+        return Ok(())
+    }
+    settings.info_author = read_string(r)?;
+    settings.info_version = read_string(r)?;
+    r.read_f64::<LE>()?; // timestamp
+    settings.info_information = read_string(r)?;
+    settings.version_major = r.read_u32::<LE>()?;
+    settings.version_minor = r.read_u32::<LE>()?;
+    settings.version_release = r.read_u32::<LE>()?;
+    settings.version_build = r.read_u32::<LE>()?;
+    settings.exe_company = read_string(r)?;
+    settings.exe_product = read_string(r)?;
+    settings.exe_copyright = read_string(r)?;
+    settings.exe_description = read_string(r)?;
+    Ok(())
+}
+
+pub(crate) fn read_triggers(r: &mut Reader, exe: bool) -> Result<Vec<Option<Trigger>>> {
+    expect(r, "triggers version", &[800])?;
+    let count = r.read_u32::<LE>()? as usize;
+    let triggers = (0..count)
+        .map(|_| {
+            let data = read_block(r)?;
+            let r = &mut Cursor::new(data.as_slice());
+            if !read_bool(r)? {
+                return Ok(None)
+            }
+            expect(r, "trigger version", &[800])?;
+            Ok(Some(Trigger {
+                name: read_string(r)?,
+                condition: read_string(r)?,
+                kind: r.read_u32::<LE>()?,
+                constant_name: read_string(r)?,
+            }))
+        })
+        .collect::<Result<_>>()?;
+    if !exe {
+        r.read_f64::<LE>()?; // timestamp
+    }
+    Ok(triggers)
+}
+
+fn read_constants(r: &mut Reader) -> Result<Vec<(String, String)>> {
+    expect(r, "constants version", &[800])?;
+    let count = r.read_u32::<LE>()? as usize;
+    let constants = (0..count).map(|_| Ok((read_string(r)?, read_string(r)?))).collect::<Result<_>>()?;
+    r.read_f64::<LE>()?; // timestamp
+    Ok(constants)
+}
+
+fn read_frame(r: &mut Reader) -> Result<Frame> {
+    expect(r, "frame version", &[800])?;
+    let width = r.read_u32::<LE>()?;
+    let height = r.read_u32::<LE>()?;
+    let data = if width != 0 && height != 0 { read_buffer(r)? } else { Vec::new() };
+    if data.len() != width as usize * height as usize * 4 {
+        return Err(Error::Other(format!("frame of size {}x{} has {} bytes of data", width, height, data.len())))
+    }
+    Ok(Frame { width, height, data })
+}
+
+fn read_sound(r: &mut Reader, _names: &Names) -> Result<Sound> {
+    expect(r, "sound version", &[800])?;
+    let kind = r.read_u32::<LE>()?;
+    let extension = read_string(r)?;
+    let source = read_string(r)?;
+    let data = if read_bool(r)? { Some(read_buffer(r)?) } else { None };
+    Ok(Sound {
+        kind,
+        extension,
+        source,
+        data,
+        effects: r.read_u32::<LE>()?,
+        volume: r.read_f64::<LE>()?,
+        pan: r.read_f64::<LE>()?,
+        preload: read_bool(r)?,
+    })
+}
+
+fn read_sprite(r: &mut Reader, names: &Names) -> Result<Sprite> {
+    expect(r, "sprite version", &[800])?;
+    let origin_x = r.read_i32::<LE>()?;
+    let origin_y = r.read_i32::<LE>()?;
+    let frame_count = r.read_u32::<LE>()?;
+    let frames = (0..frame_count).map(|_| read_frame(r)).collect::<Result<Vec<_>>>()?;
+    if names.exe {
+        let per_frame_colliders = read_bool(r)?;
+        let mask_count = match frame_count {
+            0 => 0,
+            _ if per_frame_colliders => frame_count,
+            _ => 1,
+        };
+        let masks = (0..mask_count).map(|_| mask::read_mask(&mut *r)).collect::<std::io::Result<Vec<_>>>()?;
+        let mut sprite = Sprite { origin_x, origin_y, frames, per_frame_colliders, ..Default::default() };
+        mask::guess_collision(&mut sprite, &masks);
+        return Ok(sprite)
+    }
+    Ok(Sprite {
+        origin_x,
+        origin_y,
+        frames,
+        collision_shape: r.read_u32::<LE>()?,
+        alpha_tolerance: r.read_u32::<LE>()?,
+        per_frame_colliders: read_bool(r)?,
+        bbox_type: r.read_u32::<LE>()?,
+        bbox_left: r.read_i32::<LE>()?,
+        bbox_right: r.read_i32::<LE>()?,
+        bbox_bottom: r.read_i32::<LE>()?,
+        bbox_top: r.read_i32::<LE>()?,
+    })
+}
+
+fn read_background(r: &mut Reader, names: &Names) -> Result<Background> {
+    expect(r, "background version", &[710])?;
+    if names.exe {
+        return Ok(Background { frame: read_frame(r)?, ..Default::default() })
+    }
+    Ok(Background {
+        is_tileset: read_bool(r)?,
+        tile_width: r.read_u32::<LE>()?,
+        tile_height: r.read_u32::<LE>()?,
+        h_offset: r.read_u32::<LE>()?,
+        v_offset: r.read_u32::<LE>()?,
+        h_sep: r.read_u32::<LE>()?,
+        v_sep: r.read_u32::<LE>()?,
+        frame: read_frame(r)?,
+    })
+}
+
+fn read_path(r: &mut Reader, names: &Names) -> Result<Path> {
+    expect(r, "path version", &[530])?;
+    let connection = r.read_u32::<LE>()?;
+    let closed = read_bool(r)?;
+    let precision = r.read_u32::<LE>()?;
+    let (background, snap_x, snap_y) = if names.exe {
+        (String::new(), 16, 16)
+    } else {
+        (name_of(&names.rooms, r.read_i32::<LE>()?), r.read_u32::<LE>()?, r.read_u32::<LE>()?)
+    };
+    let point_count = r.read_u32::<LE>()?;
+    let points = (0..point_count)
+        .map(|_| Ok(PathPoint { x: r.read_f64::<LE>()?, y: r.read_f64::<LE>()?, speed: r.read_f64::<LE>()? }))
+        .collect::<Result<_>>()?;
+    Ok(Path { points, connection, closed, precision, background, snap_x, snap_y })
+}
+
+fn read_script(r: &mut Reader, _names: &Names) -> Result<Script> {
+    expect(r, "script version", &[800])?;
+    Ok(Script { source: read_string(r)? })
+}
+
+fn read_font(r: &mut Reader, names: &Names) -> Result<Font> {
+    expect(r, "font version", &[800])?;
+    let sys_name = read_string(r)?;
+    let size = r.read_u32::<LE>()?;
+    let bold = read_bool(r)?;
+    let italic = read_bool(r)?;
+    // 8.1 packs the charset and antialiasing level into the top bits of this
+    let range_start = r.read_u32::<LE>()?;
+    let range_end = r.read_u32::<LE>()?;
+    let (charset, aa_level) = match (names.version, range_start >> 24) {
+        // 8.0 fonts are always fully antialiased
+        (800, _) | (_, 0) => (0, 3),
+        (_, aa) => ((range_start >> 16) & 0xff, aa - 1),
+    };
+    if names.exe {
+        // skip the rendered glyphs
+        let glyph_data = 256 * 6 * 4;
+        r.set_position(r.position() + glyph_data);
+        let _width = r.read_u32::<LE>()?;
+        let _height = r.read_u32::<LE>()?;
+        read_buffer(r)?;
+    }
+    Ok(Font {
+        sys_name,
+        size,
+        bold,
+        italic,
+        range_start: range_start & 0xffff,
+        range_end,
+        charset,
+        aa_level,
+        file: None,
+    })
+}
+
+fn read_event(r: &mut Reader, names: &Names) -> Result<Event> {
+    expect(r, "event version", &[400])?;
+    let action_count = r.read_u32::<LE>()?;
+    let actions = (0..action_count)
+        .map(|_| {
+            expect(r, "action version", &[440])?;
+            let lib_id = r.read_u32::<LE>()?;
+            let id = r.read_u32::<LE>()?;
+            let kind = r.read_u32::<LE>()?;
+            let can_be_relative = read_bool(r)?;
+            let _is_condition = read_bool(r)?;
+            let applies_to_something = read_bool(r)?;
+            let _execution_type = r.read_u32::<LE>()?;
+            let _fn_name = read_string(r)?;
+            let _fn_code = read_string(r)?;
+            let param_count = r.read_u32::<LE>()? as usize;
+            let mut param_types = [0u32; 8];
+            expect(r, "action parameter type count", &[8])?;
+            for ty in &mut param_types {
+                *ty = r.read_u32::<LE>()?;
+            }
+            let applies_to = r.read_i32::<LE>()?;
+            let is_relative = read_bool(r)?;
+            expect(r, "action parameter count", &[8])?;
+            let mut param_strings = (0..8).map(|_| read_string(r)).collect::<Result<Vec<_>>>()?;
+            let invert = read_bool(r)?;
+            let mut action = Action { lib_id, id, ..Default::default() };
+            if can_be_relative {
+                action.is_relative = Some(is_relative);
+            }
+            if applies_to_something {
+                action.applies_to = Some(match applies_to {
+                    -2 => "other".into(),
+                    -1 => "self".into(),
+                    i => name_of(&names.objects, i),
+                });
+            }
+            match kind {
+                0 => {
+                    let args = param_strings
+                        .into_iter()
+                        .zip(param_types)
+                        .take(param_count.min(8))
+                        .map(|(arg, ty)| {
+                            let list = match ty {
+                                5 => &names.sprites,
+                                6 => &names.sounds,
+                                7 => &names.backgrounds,
+                                8 => &names.paths,
+                                9 => &names.scripts,
+                                10 => &names.objects,
+                                11 => &names.rooms,
+                                12 => &names.fonts,
+                                14 => &names.timelines,
+                                _ => return arg,
+                            };
+                            name_of(list, arg.parse().unwrap_or(-1))
+                        })
+                        .collect();
+                    action.params = ActionParams::Normal { invert, args };
+                },
+                5 => action.params = ActionParams::Repeat(param_strings.swap_remove(0)),
+                6 => {
+                    let value = param_strings.swap_remove(1);
+                    let name = param_strings.swap_remove(0);
+                    action.params = ActionParams::Variable { name, value };
+                },
+                7 => action.code = param_strings.swap_remove(0),
+                _ => (),
+            }
+            Ok(action)
+        })
+        .collect::<Result<_>>()?;
+    Ok(Event { actions })
+}
+
+fn read_timeline(r: &mut Reader, names: &Names) -> Result<Timeline> {
+    expect(r, "timeline version", &[500])?;
+    let moment_count = r.read_u32::<LE>()?;
+    let moments = (0..moment_count).map(|_| Ok((r.read_u32::<LE>()?, read_event(r, names)?))).collect::<Result<_>>()?;
+    Ok(Timeline { moments })
+}
+
+fn read_object(r: &mut Reader, names: &Names) -> Result<Object> {
+    expect(r, "object version", &[430])?;
+    let mut obj = Object {
+        sprite: name_of(&names.sprites, r.read_i32::<LE>()?),
+        solid: read_bool(r)?,
+        visible: read_bool(r)?,
+        depth: r.read_i32::<LE>()?,
+        persistent: read_bool(r)?,
+        parent: name_of(&names.objects, r.read_i32::<LE>()?),
+        mask: name_of(&names.sprites, r.read_i32::<LE>()?),
+        ..Default::default()
+    };
+    expect(r, "object event count", &[11])?;
+    for (ev_type, group) in obj.events.iter_mut().enumerate() {
+        loop {
+            let numb = r.read_i32::<LE>()?;
+            if numb < 0 {
+                break
+            }
+            let numb = match ev_type {
+                events::EV_COLLISION => EventNumber::Name(name_of(&names.objects, numb)),
+                events::EV_TRIGGER => EventNumber::Name(name_of(&names.triggers, numb)),
+                _ => EventNumber::Number(numb as usize),
+            };
+            let event = read_event(r, names)?;
+            // events pointing at deleted objects or triggers get thrown out, same as when gm82 projects are saved
+            if numb != EventNumber::Name(String::new()) {
+                group.push((numb, event));
+            }
+        }
+        // gm saves them backwards
+        group.reverse();
+    }
+    Ok(obj)
+}
+
+fn read_room(r: &mut Reader, names: &Names) -> Result<Room> {
+    // 811 is gm82's version with instance and tile scale, blend and angle
+    let version = expect(r, "room version", if names.exe { &[541, 811] } else { &[541] })?;
+    let mut room = Room {
+        caption: read_string(r)?,
+        width: r.read_u32::<LE>()?,
+        height: r.read_u32::<LE>()?,
+        ..Default::default()
+    };
+    if names.exe {
+        room.snap_x = 16;
+        room.snap_y = 16;
+    } else {
+        room.snap_x = r.read_u32::<LE>()?;
+        room.snap_y = r.read_u32::<LE>()?;
+        room.isometric = read_bool(r)?;
+    }
+    room.speed = r.read_u32::<LE>()?;
+    room.persistent = read_bool(r)?;
+    room.bg_colour = r.read_i32::<LE>()?;
+    let clear = r.read_u32::<LE>()?;
+    room.clear_screen = clear & 1 != 0;
+    // 8.0 didn't have the option to not clear the view
+    room.clear_view = names.version == 800 || clear & 2 != 0;
+    room.creation_code = read_string(r)?;
+    expect(r, "room background count", &[8])?;
+    for bg in &mut room.backgrounds {
+        bg.visible_on_start = read_bool(r)?;
+        bg.is_foreground = read_bool(r)?;
+        bg.source_bg = name_of(&names.backgrounds, r.read_i32::<LE>()?);
+        bg.xoffset = r.read_i32::<LE>()?;
+        bg.yoffset = r.read_i32::<LE>()?;
+        bg.tile_horz = read_bool(r)?;
+        bg.tile_vert = read_bool(r)?;
+        bg.hspeed = r.read_i32::<LE>()?;
+        bg.vspeed = r.read_i32::<LE>()?;
+        bg.stretch = read_bool(r)?;
+    }
+    room.views_enabled = read_bool(r)?;
+    expect(r, "room view count", &[8])?;
+    for view in &mut room.views {
+        view.visible = read_bool(r)?;
+        view.source_x = r.read_i32::<LE>()?;
+        view.source_y = r.read_i32::<LE>()?;
+        view.source_w = r.read_u32::<LE>()?;
+        view.source_h = r.read_u32::<LE>()?;
+        view.port_x = r.read_i32::<LE>()?;
+        view.port_y = r.read_i32::<LE>()?;
+        view.port_w = r.read_u32::<LE>()?;
+        view.port_h = r.read_u32::<LE>()?;
+        view.following_hborder = r.read_i32::<LE>()?;
+        view.following_vborder = r.read_i32::<LE>()?;
+        view.following_hspeed = r.read_i32::<LE>()?;
+        view.following_vspeed = r.read_i32::<LE>()?;
+        view.following_target = name_of(&names.objects, r.read_i32::<LE>()?);
+    }
+    let instance_count = r.read_u32::<LE>()?;
+    room.instances = (0..instance_count)
+        .map(|_| {
+            let x = r.read_i32::<LE>()?;
+            let y = r.read_i32::<LE>()?;
+            let object = name_of(&names.objects, r.read_i32::<LE>()?);
+            let id = r.read_u32::<LE>()? as usize;
+            let creation_code = read_string(r)?;
+            let mut inst = Instance { x, y, object, id, creation_code, ..Default::default() };
+            if !names.exe {
+                inst.locked = read_bool(r)?;
+            } else if version == 811 {
+                inst.xscale = r.read_f64::<LE>()?;
+                inst.yscale = r.read_f64::<LE>()?;
+                inst.blend = r.read_u32::<LE>()?;
+                inst.angle = r.read_f64::<LE>()?;
+            }
+            Ok(inst)
+        })
+        .collect::<Result<_>>()?;
+    let tile_count = r.read_u32::<LE>()?;
+    room.tiles = (0..tile_count)
+        .map(|_| {
+            let x = r.read_i32::<LE>()?;
+            let y = r.read_i32::<LE>()?;
+            let source_bg = name_of(&names.backgrounds, r.read_i32::<LE>()?);
+            let u = r.read_i32::<LE>()?;
+            let v = r.read_i32::<LE>()?;
+            let width = r.read_i32::<LE>()?;
+            let height = r.read_i32::<LE>()?;
+            let depth = r.read_i32::<LE>()?;
+            let id = r.read_u32::<LE>()? as usize;
+            let mut tile = Tile { x, y, source_bg, u, v, width, height, depth, id, ..Default::default() };
+            if !names.exe {
+                tile.locked = read_bool(r)?;
+            } else if version == 811 {
+                tile.xscale = r.read_f64::<LE>()?;
+                tile.yscale = r.read_f64::<LE>()?;
+                tile.blend = r.read_u32::<LE>()?;
+            }
+            Ok(tile)
+        })
+        .collect::<Result<_>>()?;
+    if names.exe {
+        return Ok(room)
+    }
+    room.remember_room_editor_info = read_bool(r)?;
+    room.editor_width = r.read_u32::<LE>()?;
+    room.editor_height = r.read_u32::<LE>()?;
+    room.show_grid = read_bool(r)?;
+    room.show_objects = read_bool(r)?;
+    room.show_tiles = read_bool(r)?;
+    room.show_backgrounds = read_bool(r)?;
+    room.show_foregrounds = read_bool(r)?;
+    room.show_views = read_bool(r)?;
+    room.delete_underlying_objects = read_bool(r)?;
+    room.delete_underlying_tiles = read_bool(r)?;
+    room.tab = r.read_u32::<LE>()?;
+    room.x_position_scroll = r.read_u32::<LE>()?;
+    room.y_position_scroll = r.read_u32::<LE>()?;
+    Ok(room)
+}
+
+pub(crate) fn read_included_files(r: &mut Reader, exe: bool) -> Result<Vec<IncludedFile>> {
+    expect(r, "included files version", &[800])?;
+    let count = r.read_u32::<LE>()?;
+    (0..count)
+        .map(|_| {
+            let data = read_block(r)?;
+            let r = &mut Cursor::new(data.as_slice());
+            if !exe {
+                r.read_f64::<LE>()?; // timestamp
+            }
+            expect(r, "included file version", &[800])?;
+            let file_name = read_string(r)?;
+            let source_path = read_string(r)?.into();
+            let _data_exists = read_bool(r)?;
+            let _source_length = r.read_u32::<LE>()?;
+            let stored_in_gmk = read_bool(r)?;
+            let data = if stored_in_gmk { Some(read_buffer(r)?) } else { None };
+            Ok(IncludedFile {
+                file_name,
+                source_path,
+                stored_in_gmk,
+                data,
+                export_setting: r.read_u32::<LE>()?,
+                export_custom_folder: read_string(r)?,
+                overwrite_file: read_bool(r)?,
+                free_memory: read_bool(r)?,
+                remove_at_end: read_bool(r)?,
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn read_game_information(r: &mut Reader, exe: bool) -> Result<GameInformation> {
+    expect(r, "game information version", &[800])?;
+    let data = read_block(r)?;
+    let r = &mut Cursor::new(data.as_slice());
+    let mut info = GameInformation {
+        colour: r.read_u32::<LE>()?,
+        new_window: read_bool(r)?,
+        caption: read_string(r)?,
+        left: r.read_i32::<LE>()?,
+        top: r.read_i32::<LE>()?,
+        width: r.read_i32::<LE>()?,
+        height: r.read_i32::<LE>()?,
+        border: read_bool(r)?,
+        resizable: read_bool(r)?,
+        window_on_top: read_bool(r)?,
+        freeze_game: read_bool(r)?,
+        ..Default::default()
+    };
+    if !exe {
+        r.read_f64::<LE>()?; // timestamp
+    }
+    // rtf is plain ascii with escapes, so keep the raw bytes
+    info.rtf = read_buffer(r)?;
+    Ok(info)
+}
+
+fn read_tree_children(r: &mut Reader, names: &[String]) -> Result<Vec<TreeNode>> {
+    let count = r.read_u32::<LE>()?;
+    (0..count)
+        .map(|_| {
+            let status = r.read_u32::<LE>()?;
+            let _group = r.read_u32::<LE>()?;
+            let index = r.read_u32::<LE>()? as usize;
+            let name = read_string(r)?;
+            let children = read_tree_children(r, names)?;
+            Ok(match status {
+                // asset
+                3 => TreeNode::Asset(names.get(index).cloned().unwrap_or(name)),
+                _ => TreeNode::Folder(name, children),
+            })
+        })
+        .collect()
+}
+
+/// Reads the resource tree, which has a root for every asset type, plus game information, settings and extensions.
+fn read_tree(r: &mut Reader, project: &mut Project) -> Result<()> {
+    for _ in 0..12 {
+        let _status = r.read_u32::<LE>()?;
+        let group = r.read_u32::<LE>()?;
+        let _index = r.read_u32::<LE>()?;
+        let _name = read_string(r)?;
+        fn children<T>(r: &mut Reader, list: &mut AssetList<T>) -> Result<()> {
+            list.tree = read_tree_children(r, &list.names)?;
+            Ok(())
+        }
+        match group {
+            1 => children(r, &mut project.objects)?,
+            2 => children(r, &mut project.sprites)?,
+            3 => children(r, &mut project.sounds)?,
+            4 => children(r, &mut project.rooms)?,
+            6 => children(r, &mut project.backgrounds)?,
+            7 => children(r, &mut project.scripts)?,
+            8 => children(r, &mut project.paths)?,
+            9 => children(r, &mut project.fonts)?,
+            12 => children(r, &mut project.timelines)?,
+            _ => drop(read_tree_children(r, &[])?),
+        }
+    }
+    Ok(())
+}
+
+/// Parses the contents of a .gmk or .gm81 file.
+pub fn read_gm81(data: &[u8]) -> Result<Project> {
+    let r = &mut Cursor::new(data);
+    if r.read_u32::<LE>()? != GMK_MAGIC {
+        return Err(Error::Other("not a .gmk or .gm81 file".into()))
+    }
+    let version = expect(r, "project version", &[800, 810])?;
+    let mut project = Project { game_id: r.read_u32::<LE>()?, ..Default::default() };
+    let mut guid = [0; 16];
+    r.read_exact(&mut guid)?;
+    read_settings(r, &mut project.settings, false)?;
+    project.triggers = read_triggers(r, false)?;
+    project.constants = read_constants(r)?;
+    read_all_assets(r, &mut project, version, false)?;
+    project.included_files = read_included_files(r, false)?;
+    expect(r, "extensions version", &[700])?;
+    let extension_count = r.read_u32::<LE>()?;
+    project.extensions = (0..extension_count).map(|_| read_string(r)).collect::<Result<_>>()?;
+    project.game_info = read_game_information(r, false)?;
+    expect(r, "library creation code version", &[500])?;
+    let lib_code_count = r.read_u32::<LE>()?;
+    for _ in 0..lib_code_count {
+        read_buffer(r)?;
+    }
+    expect(r, "room order version", &[500, 540, 700])?;
+    let room_count = r.read_u32::<LE>()?;
+    for _ in 0..room_count {
+        r.read_u32::<LE>()?;
+    }
+    read_tree(r, &mut project)?;
+    Ok(project)
+}
+
+/// Reads every asset section from sounds to rooms, followed by the last instance and tile ids.
+/// Triggers need to be read first, since events refer to them.
+pub(crate) fn read_all_assets(r: &mut Reader, project: &mut Project, version: u32, exe: bool) -> Result<()> {
+    let sounds = read_asset_blocks(r, "sounds", !exe)?;
+    let sprites = read_asset_blocks(r, "sprites", !exe)?;
+    let backgrounds = read_asset_blocks(r, "backgrounds", !exe)?;
+    let paths = read_asset_blocks(r, "paths", !exe)?;
+    let scripts = read_asset_blocks(r, "scripts", !exe)?;
+    let fonts = read_asset_blocks(r, "fonts", !exe)?;
+    let timelines = read_asset_blocks(r, "timelines", !exe)?;
+    let objects = read_asset_blocks(r, "objects", !exe)?;
+    let rooms = read_asset_blocks(r, "rooms", !exe)?;
+    let _last_instance_id = r.read_u32::<LE>()?;
+    let _last_tile_id = r.read_u32::<LE>()?;
+    let names = Names {
+        triggers: project.triggers.iter().map(|t| t.as_ref().map(|t| t.name.clone()).unwrap_or_default()).collect(),
+        sprites: names_of(&sprites),
+        sounds: names_of(&sounds),
+        backgrounds: names_of(&backgrounds),
+        paths: names_of(&paths),
+        scripts: names_of(&scripts),
+        fonts: names_of(&fonts),
+        timelines: names_of(&timelines),
+        objects: names_of(&objects),
+        rooms: names_of(&rooms),
+        version,
+        exe,
+    };
+    fn list<T: Send>(
+        raw: Vec<Option<RawAsset>>,
+        names: &Names,
+        parse: fn(&mut Reader, &Names) -> Result<T>,
+    ) -> Result<AssetList<T>> {
+        let asset_names = names_of(&raw);
+        let timestamps = timestamps_of(&raw);
+        Ok(AssetList { assets: parse_assets(raw, names, parse)?, names: asset_names, timestamps, tree: Vec::new() })
+    }
+    project.sounds = list(sounds, &names, read_sound)?;
+    project.sprites = list(sprites, &names, read_sprite)?;
+    project.backgrounds = list(backgrounds, &names, read_background)?;
+    project.paths = list(paths, &names, read_path)?;
+    project.scripts = list(scripts, &names, read_script)?;
+    project.fonts = list(fonts, &names, read_font)?;
+    project.timelines = list(timelines, &names, read_timeline)?;
+    project.objects = list(objects, &names, read_object)?;
+    project.rooms = list(rooms, &names, read_room)?;
+    Ok(())
+}
+
+/// Loads a .gmk or .gm81 file.
+pub fn load_gm81(path: impl AsRef<FsPath>) -> Result<Project> {
+    let path = path.as_ref();
+    let data = std::fs::read(path).map_err(|e| Error::FileIoError(e, path.to_path_buf()))?;
+    read_gm81(&data)
+}
+
+/// Which version of GameMaker a written project should open in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GmkVersion {
+    /// .gmk, for GameMaker 8.0
+    Gm80,
+    /// .gm81, for GameMaker 8.1
+    Gm81,
+}
+
+impl GmkVersion {
+    fn number(self) -> u32 {
+        match self {
+            Self::Gm80 => 800,
+            Self::Gm81 => 810,
+        }
+    }
+}
+
+/// Encodes a string the way vanilla GameMaker expects, erroring if that's not possible.
+pub fn encode_string(s: &str) -> Result<Vec<u8>> {
+    s.chars()
+        .map(|c| match u32::from(c) {
+            c @ (0..0x80 | 0xa0..0x100) => Ok(c as u8),
+            _ => CP1252
+                .iter()
+                .position(|&x| x == c)
+                .map(|i| i as u8 + 0x80)
+                .ok_or_else(|| Error::UnicodeError(s.to_string())),
+        })
+        .collect()
+}
+
+pub(crate) fn write_buffer(buf: &[u8], mut out: impl Write) -> Result<()> {
+    out.write_u32::<LE>(buf.len() as u32)?;
+    out.write_all(buf)?;
+    Ok(())
+}
+
+/// 8.0 expects ANSI strings, everything from 8.1 onwards uses UTF-8.
+pub(crate) fn write_string(s: &str, version: GmkVersion, out: impl Write) -> Result<()> {
+    match version {
+        GmkVersion::Gm80 => write_buffer(&encode_string(s)?, out),
+        GmkVersion::Gm81 => write_buffer(s.as_bytes(), out),
+    }
+}
+
+fn write_block(data: &[u8], out: impl Write) -> Result<()> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    write_buffer(&encoder.finish()?, out)
+}
+
+/// Indices of every asset, so names can be turned back into indices, and what's being written.
+pub(crate) struct Indices<'a> {
+    triggers: HashMap<&'a str, i32>,
+    sprites: HashMap<&'a str, i32>,
+    sounds: HashMap<&'a str, i32>,
+    backgrounds: HashMap<&'a str, i32>,
+    paths: HashMap<&'a str, i32>,
+    scripts: HashMap<&'a str, i32>,
+    fonts: HashMap<&'a str, i32>,
+    timelines: HashMap<&'a str, i32>,
+    objects: HashMap<&'a str, i32>,
+    rooms: HashMap<&'a str, i32>,
+    libs: &'a [ActionLibrary],
+    version: GmkVersion,
+    /// Whether this is going into an exe rather than a project file, which leaves out anything only the IDE uses.
+    exe: bool,
+}
+
+impl<'a> Indices<'a> {
+    pub(crate) fn new(project: &'a Project, libs: &'a [ActionLibrary], version: GmkVersion, exe: bool) -> Self {
+        Self {
+            triggers: project
+                .triggers
+                .iter()
+                .enumerate()
+                .filter_map(|(i, t)| Some((t.as_ref()?.name.as_str(), i as i32)))
+                .collect(),
+            sprites: index_map(&project.sprites),
+            sounds: index_map(&project.sounds),
+            backgrounds: index_map(&project.backgrounds),
+            paths: index_map(&project.paths),
+            scripts: index_map(&project.scripts),
+            fonts: index_map(&project.fonts),
+            timelines: index_map(&project.timelines),
+            objects: index_map(&project.objects),
+            rooms: index_map(&project.rooms),
+            libs,
+            version,
+            exe,
+        }
+    }
+}
+
+fn index_map<T>(list: &AssetList<T>) -> HashMap<&str, i32> {
+    list.names
+        .iter()
+        .zip(&list.assets)
+        .enumerate()
+        .filter(|(_, (_, a))| a.is_some())
+        .map(|(i, (n, _))| (n.as_str(), i as i32))
+        .collect()
+}
+
+fn index_of(map: &HashMap<&str, i32>, name: &str) -> i32 {
+    map.get(name).copied().unwrap_or(-1)
+}
+
+fn write_frame(frame: &Frame, mut out: impl Write) -> Result<()> {
+    out.write_u32::<LE>(800)?;
+    out.write_u32::<LE>(frame.width)?;
+    out.write_u32::<LE>(frame.height)?;
+    if !frame.data.is_empty() {
+        write_buffer(&frame.data, &mut out)?;
+    }
+    Ok(())
+}
+
+fn write_sound(sound: &Sound, mut out: &mut Vec<u8>, ctx: &Indices) -> Result<()> {
+    out.write_u32::<LE>(800)?;
+    out.write_u32::<LE>(sound.kind)?;
+    write_string(&sound.extension, ctx.version, &mut out)?;
+    write_string(&sound.source, ctx.version, &mut out)?;
+    out.write_u32::<LE>(sound.data.is_some().into())?;
+    if let Some(data) = sound.data.as_ref() {
+        write_buffer(data, &mut out)?;
+    }
+    out.write_u32::<LE>(sound.effects)?;
+    out.write_f64::<LE>(sound.volume)?;
+    out.write_f64::<LE>(sound.pan)?;
+    out.write_u32::<LE>(sound.preload.into())?;
+    Ok(())
+}
+
+fn write_sprite(sprite: &Sprite, mut out: &mut Vec<u8>, ctx: &Indices) -> Result<()> {
+    out.write_u32::<LE>(800)?;
+    out.write_i32::<LE>(sprite.origin_x)?;
+    out.write_i32::<LE>(sprite.origin_y)?;
+    out.write_u32::<LE>(sprite.frames.len() as u32)?;
+    for frame in &sprite.frames {
+        write_frame(frame, &mut out)?;
+    }
+    if !ctx.exe {
+        out.write_u32::<LE>(sprite.collision_shape)?;
+        out.write_u32::<LE>(sprite.alpha_tolerance)?;
+    }
+    out.write_u32::<LE>(sprite.per_frame_colliders.into())?;
+    if !ctx.exe {
+        out.write_u32::<LE>(sprite.bbox_type)?;
+        out.write_i32::<LE>(sprite.bbox_left)?;
+        out.write_i32::<LE>(sprite.bbox_right)?;
+        out.write_i32::<LE>(sprite.bbox_bottom)?;
+        out.write_i32::<LE>(sprite.bbox_top)?;
+    } else {
+        for m in mask::sprite_masks(sprite) {
+            mask::write_mask(&m, &mut out)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_background(back: &Background, out: &mut Vec<u8>, ctx: &Indices) -> Result<()> {
+    out.write_u32::<LE>(710)?;
+    if !ctx.exe {
+        out.write_u32::<LE>(back.is_tileset.into())?;
+        out.write_u32::<LE>(back.tile_width)?;
+        out.write_u32::<LE>(back.tile_height)?;
+        out.write_u32::<LE>(back.h_offset)?;
+        out.write_u32::<LE>(back.v_offset)?;
+        out.write_u32::<LE>(back.h_sep)?;
+        out.write_u32::<LE>(back.v_sep)?;
+    }
+    write_frame(&back.frame, out)?;
+    Ok(())
+}
+
+fn write_path(path: &Path, out: &mut Vec<u8>, ctx: &Indices) -> Result<()> {
+    out.write_u32::<LE>(530)?;
+    out.write_u32::<LE>(path.connection)?;
+    out.write_u32::<LE>(path.closed.into())?;
+    out.write_u32::<LE>(path.precision)?;
+    if !ctx.exe {
+        out.write_i32::<LE>(index_of(&ctx.rooms, &path.background))?;
+        out.write_u32::<LE>(path.snap_x)?;
+        out.write_u32::<LE>(path.snap_y)?;
+    }
+    out.write_u32::<LE>(path.points.len() as u32)?;
+    for p in &path.points {
+        out.write_f64::<LE>(p.x)?;
+        out.write_f64::<LE>(p.y)?;
+        out.write_f64::<LE>(p.speed)?;
+    }
+    Ok(())
+}
+
+fn write_script(script: &Script, out: &mut Vec<u8>, ctx: &Indices) -> Result<()> {
+    out.write_u32::<LE>(800)?;
+    write_string(&script.source, ctx.version, out)?;
+    Ok(())
+}
+
+fn write_font(font: &Font, mut out: &mut Vec<u8>, ctx: &Indices) -> Result<()> {
+    out.write_u32::<LE>(800)?;
+    write_string(&font.sys_name, ctx.version, &mut out)?;
+    out.write_u32::<LE>(font.size)?;
+    out.write_u32::<LE>(font.bold.into())?;
+    out.write_u32::<LE>(font.italic.into())?;
+    if ctx.version == GmkVersion::Gm80 {
+        out.write_u32::<LE>(font.range_start)?;
+    } else {
+        let charset = if font.charset == 1 { 0 } else { font.charset };
+        out.write_u32::<LE>((font.range_start & 0xffff) | (charset << 16) | ((font.aa_level + 1) << 24))?;
+    }
+    out.write_u32::<LE>(font.range_end)?;
+    if ctx.exe {
+        // the ide renders installed fonts through gdi, which only works inside GameMaker
+        let file = font.file.as_ref().ok_or_else(|| {
+            Error::Other(format!(
+                "can't render font {} outside of GameMaker without a .ttf or .otf file",
+                font.sys_name
+            ))
+        })?;
+        let rendered = font_render::render_font(font, &file.data)?;
+        for i in 0..256 {
+            out.write_u32::<LE>(rendered.s_x[i])?;
+            out.write_u32::<LE>(rendered.s_y[i])?;
+            out.write_u32::<LE>(rendered.s_w[i])?;
+            out.write_u32::<LE>(rendered.s_h[i])?;
+            out.write_u32::<LE>(rendered.s_shift[i])?;
+            out.write_u32::<LE>(rendered.s_offset[i])?;
+        }
+        out.write_u32::<LE>(rendered.s_bw)?;
+        out.write_u32::<LE>(rendered.s_bh)?;
+        write_buffer(&rendered.s_bytes, &mut out)?;
+    }
+    Ok(())
+}
+
+fn write_event(event: &Event, mut out: &mut Vec<u8>, ctx: &Indices) -> Result<()> {
+    out.write_u32::<LE>(400)?;
+    out.write_u32::<LE>(event.actions.len() as u32)?;
+    for action in &event.actions {
+        // define action from library
+        let def = actionlib::find_action(ctx.libs, action.lib_id, action.id)?;
+        let mut params: [String; 8] = Default::default();
+        let mut invert = false;
+        match &action.params {
+            ActionParams::Normal { invert: inv, args } => {
+                invert = *inv;
+                for ((param, arg), ty) in params.iter_mut().zip(args).zip(def.param_types) {
+                    let map = match ty {
+                        5 => &ctx.sprites,
+                        6 => &ctx.sounds,
+                        7 => &ctx.backgrounds,
+                        8 => &ctx.paths,
+                        9 => &ctx.scripts,
+                        10 => &ctx.objects,
+                        11 => &ctx.rooms,
+                        12 => &ctx.fonts,
+                        14 => &ctx.timelines,
+                        _ => {
+                            *param = arg.clone();
+                            continue
+                        },
+                    };
+                    *param = index_of(map, arg).to_string();
+                }
+            },
+            ActionParams::Repeat(repeats) => params[0] = repeats.clone(),
+            ActionParams::Variable { name, value } => {
+                params[0] = name.clone();
+                params[1] = value.clone();
+            },
+            ActionParams::None => (),
+        }
+        if def.kind == 7 {
+            params[0] = action.code.clone();
+        }
+        let applies_to = match action.applies_to.as_deref() {
+            None | Some("self") => -1,
+            Some("other") => -2,
+            Some(name) => ctx.objects.get(name).copied().unwrap_or(-4),
+        };
+        out.write_u32::<LE>(440)?;
+        out.write_u32::<LE>(def.lib_id)?;
+        out.write_u32::<LE>(def.id)?;
+        out.write_u32::<LE>(def.kind)?;
+        out.write_u32::<LE>(def.can_be_relative.into())?;
+        out.write_u32::<LE>(def.is_condition.into())?;
+        out.write_u32::<LE>(def.applies_to_something.into())?;
+        out.write_u32::<LE>(def.execution_type)?;
+        write_string(&def.fn_name, ctx.version, &mut out)?;
+        write_string(&def.fn_code, ctx.version, &mut out)?;
+        out.write_u32::<LE>(def.param_count)?;
+        out.write_u32::<LE>(8)?;
+        for ty in def.param_types {
+            out.write_u32::<LE>(ty)?;
+        }
+        out.write_i32::<LE>(applies_to)?;
+        out.write_u32::<LE>(action.is_relative.unwrap_or(false).into())?;
+        out.write_u32::<LE>(8)?;
+        for param in &params {
+            write_string(param, ctx.version, &mut out)?;
+        }
+        out.write_u32::<LE>(invert.into())?;
+    }
+    Ok(())
+}
+
+fn write_timeline(tl: &Timeline, out: &mut Vec<u8>, ctx: &Indices) -> Result<()> {
+    out.write_u32::<LE>(500)?;
+    out.write_u32::<LE>(tl.moments.len() as u32)?;
+    for (time, event) in &tl.moments {
+        out.write_u32::<LE>(*time)?;
+        write_event(event, out, ctx)?;
+    }
+    Ok(())
+}
+
+fn write_object(obj: &Object, out: &mut Vec<u8>, ctx: &Indices) -> Result<()> {
+    out.write_u32::<LE>(430)?;
+    out.write_i32::<LE>(index_of(&ctx.sprites, &obj.sprite))?;
+    out.write_u32::<LE>(obj.solid.into())?;
+    out.write_u32::<LE>(obj.visible.into())?;
+    out.write_i32::<LE>(obj.depth)?;
+    out.write_u32::<LE>(obj.persistent.into())?;
+    out.write_i32::<LE>(index_of(&ctx.objects, &obj.parent))?;
+    out.write_i32::<LE>(index_of(&ctx.sprites, &obj.mask))?;
+    out.write_u32::<LE>(11)?;
+    for (ev_type, events) in obj.events.iter().enumerate() {
+        let mut sorted = events
+            .iter()
+            .filter(|(_, ev)| !ev.actions.is_empty())
+            .filter_map(|(numb, ev)| {
+                let index = match (ev_type, numb) {
+                    (events::EV_COLLISION, EventNumber::Name(name)) => *ctx.objects.get(name.as_str())?,
+                    (events::EV_TRIGGER, EventNumber::Name(name)) => *ctx.triggers.get(name.as_str())?,
+                    (_, EventNumber::Number(n)) => *n as i32,
+                    _ => return None,
+                };
+                Some((index, ev))
+            })
+            .collect::<Vec<_>>();
+        // note: gm saves them backwards, might as well replicate lol
+        sorted.sort_by_key(|(i, _)| std::cmp::Reverse(*i));
+        for (i, event) in sorted {
+            out.write_i32::<LE>(i)?;
+            write_event(event, out, ctx)?;
+        }
+        out.write_i32::<LE>(-1)?;
+    }
+    Ok(())
+}
+
+/// `ids` are the ids of the room's instances and tiles, from [`instance_ids`].
+fn write_room(room: &Room, mut out: &mut Vec<u8>, ctx: &Indices, ids: &RoomIds) -> Result<()> {
+    // 811 is gm82's version with instance and tile scale, blend and angle
+    out.write_u32::<LE>(if ctx.exe { 811 } else { 541 })?;
+    write_string(&room.caption, ctx.version, &mut out)?;
+    out.write_u32::<LE>(room.width)?;
+    out.write_u32::<LE>(room.height)?;
+    if !ctx.exe {
+        out.write_u32::<LE>(room.snap_x)?;
+        out.write_u32::<LE>(room.snap_y)?;
+        out.write_u32::<LE>(room.isometric.into())?;
+    }
+    out.write_u32::<LE>(room.speed)?;
+    out.write_u32::<LE>(room.persistent.into())?;
+    out.write_i32::<LE>(room.bg_colour)?;
+    if ctx.version == GmkVersion::Gm80 {
+        out.write_u32::<LE>(room.clear_screen.into())?;
+    } else {
+        out.write_u32::<LE>(u32::from(room.clear_screen) | (u32::from(room.clear_view) << 1))?;
+    }
+    write_string(&room.creation_code, ctx.version, &mut out)?;
+    out.write_u32::<LE>(8)?;
+    for b in &room.backgrounds {
+        out.write_u32::<LE>(b.visible_on_start.into())?;
+        out.write_u32::<LE>(b.is_foreground.into())?;
+        out.write_i32::<LE>(index_of(&ctx.backgrounds, &b.source_bg))?;
+        out.write_i32::<LE>(b.xoffset)?;
+        out.write_i32::<LE>(b.yoffset)?;
+        out.write_u32::<LE>(b.tile_horz.into())?;
+        out.write_u32::<LE>(b.tile_vert.into())?;
+        out.write_i32::<LE>(b.hspeed)?;
+        out.write_i32::<LE>(b.vspeed)?;
+        out.write_u32::<LE>(b.stretch.into())?;
+    }
+    out.write_u32::<LE>(room.views_enabled.into())?;
+    out.write_u32::<LE>(8)?;
+    for v in &room.views {
+        out.write_u32::<LE>(v.visible.into())?;
+        out.write_i32::<LE>(v.source_x)?;
+        out.write_i32::<LE>(v.source_y)?;
+        out.write_u32::<LE>(v.source_w)?;
+        out.write_u32::<LE>(v.source_h)?;
+        out.write_i32::<LE>(v.port_x)?;
+        out.write_i32::<LE>(v.port_y)?;
+        out.write_u32::<LE>(v.port_w)?;
+        out.write_u32::<LE>(v.port_h)?;
+        out.write_i32::<LE>(v.following_hborder)?;
+        out.write_i32::<LE>(v.following_vborder)?;
+        out.write_i32::<LE>(v.following_hspeed)?;
+        out.write_i32::<LE>(v.following_vspeed)?;
+        out.write_i32::<LE>(index_of(&ctx.objects, &v.following_target))?;
+    }
+    out.write_u32::<LE>(room.instances.len() as u32)?;
+    for (i, id) in room.instances.iter().zip(&ids.0) {
+        out.write_i32::<LE>(i.x)?;
+        out.write_i32::<LE>(i.y)?;
+        out.write_i32::<LE>(index_of(&ctx.objects, &i.object))?;
+        out.write_u32::<LE>(*id as u32)?;
+        write_string(&i.creation_code, ctx.version, &mut out)?;
+        if !ctx.exe {
+            out.write_u32::<LE>(i.locked.into())?;
+        } else {
+            out.write_f64::<LE>(i.xscale)?;
+            out.write_f64::<LE>(i.yscale)?;
+            out.write_u32::<LE>(i.blend)?;
+            out.write_f64::<LE>(i.angle)?;
+        }
+    }
+    out.write_u32::<LE>(room.tiles.len() as u32)?;
+    for (t, id) in room.tiles.iter().zip(&ids.1) {
+        out.write_i32::<LE>(t.x)?;
+        out.write_i32::<LE>(t.y)?;
+        out.write_i32::<LE>(index_of(&ctx.backgrounds, &t.source_bg))?;
+        out.write_i32::<LE>(t.u)?;
+        out.write_i32::<LE>(t.v)?;
+        out.write_i32::<LE>(t.width)?;
+        out.write_i32::<LE>(t.height)?;
+        out.write_i32::<LE>(t.depth)?;
+        out.write_u32::<LE>(*id as u32)?;
+        if !ctx.exe {
+            out.write_u32::<LE>(t.locked.into())?;
+        } else {
+            out.write_f64::<LE>(t.xscale)?;
+            out.write_f64::<LE>(t.yscale)?;
+            out.write_u32::<LE>(t.blend)?;
+        }
+    }
+    if ctx.exe {
+        return Ok(())
+    }
+    out.write_u32::<LE>(room.remember_room_editor_info.into())?;
+    out.write_u32::<LE>(room.editor_width)?;
+    out.write_u32::<LE>(room.editor_height)?;
+    out.write_u32::<LE>(room.show_grid.into())?;
+    out.write_u32::<LE>(room.show_objects.into())?;
+    out.write_u32::<LE>(room.show_tiles.into())?;
+    out.write_u32::<LE>(room.show_backgrounds.into())?;
+    out.write_u32::<LE>(room.show_foregrounds.into())?;
+    out.write_u32::<LE>(room.show_views.into())?;
+    out.write_u32::<LE>(room.delete_underlying_objects.into())?;
+    out.write_u32::<LE>(room.delete_underlying_tiles.into())?;
+    out.write_u32::<LE>(room.tab)?;
+    out.write_u32::<LE>(room.x_position_scroll)?;
+    out.write_u32::<LE>(room.y_position_scroll)?;
+    Ok(())
+}
+
+fn write_assets<T: Sync>(
+    list: &AssetList<T>,
+    ctx: &Indices,
+    write: impl Fn(&T, &mut Vec<u8>, &Indices) -> Result<()> + Sync,
+    mut out: impl Write,
+) -> Result<()> {
+    out.write_u32::<LE>(800)?;
+    out.write_u32::<LE>(list.assets.len() as u32)?;
+    let blocks = (&list.assets, &list.names, &list.timestamps)
+        .into_par_iter()
+        .map(|(asset, name, timestamp)| {
+            let mut data = Vec::new();
+            data.write_u32::<LE>(asset.is_some().into())?;
+            if let Some(asset) = asset {
+                write_string(name, ctx.version, &mut data)?;
+                if !ctx.exe {
+                    data.write_f64::<LE>(*timestamp)?;
+                }
+                write(asset, &mut data, ctx)?;
+            }
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&data)?;
+            Ok(encoder.finish()?)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    for block in blocks {
+        write_buffer(&block, &mut out)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_settings(project: &Project, ctx: &Indices, mut out: impl Write) -> Result<()> {
+    let settings = &project.settings;
+    let mut data = Vec::new();
+    let w = &mut data;
+    w.write_u32::<LE>(settings.fullscreen.into())?;
+    w.write_u32::<LE>(settings.interpolate_pixels.into())?;
+    w.write_u32::<LE>(settings.dont_draw_border.into())?;
+    w.write_u32::<LE>(settings.display_cursor.into())?;
+    w.write_i32::<LE>(settings.scaling)?;
+    w.write_u32::<LE>(settings.allow_resize.into())?;
+    w.write_u32::<LE>(settings.window_on_top.into())?;
+    w.write_u32::<LE>(settings.clear_colour)?;
+    w.write_u32::<LE>(settings.set_resolution.into())?;
+    w.write_u32::<LE>(settings.colour_depth)?;
+    w.write_u32::<LE>(settings.resolution)?;
+    w.write_u32::<LE>(settings.frequency)?;
+    w.write_u32::<LE>(settings.dont_show_buttons.into())?;
+    if !ctx.exe {
+        w.write_u32::<LE>(u32::from(settings.vsync) | (u32::from(settings.swap_creation_events) << 31))?;
+    } else {
+        // the exe gets this through WriteBoolean, so the creation code flag doesn't come along
+        w.write_u32::<LE>(settings.vsync.into())?;
+    }
+    w.write_u32::<LE>(settings.disable_screensaver.into())?;
+    w.write_u32::<LE>(settings.f4_fullscreen.into())?;
+    w.write_u32::<LE>(settings.f1_help.into())?;
+    w.write_u32::<LE>(settings.esc_close.into())?;
+    w.write_u32::<LE>(settings.f5_save_f6_load.into())?;
+    w.write_u32::<LE>(settings.f9_screenshot.into())?;
+    w.write_u32::<LE>(settings.treat_close_as_esc.into())?;
+    w.write_u32::<LE>(settings.priority)?;
+    w.write_u32::<LE>(settings.freeze_on_lose_focus.into())?;
+    w.write_u32::<LE>(settings.loading_bar)?;
+    if settings.loading_bar == 2 {
+        for image in [&settings.loading_background, &settings.loading_foreground] {
+            w.write_u32::<LE>(image.is_some().into())?;
+            if let Some(image) = image {
+                write_block(image, &mut *w)?;
+            }
+        }
+    }
+    w.write_u32::<LE>(settings.custom_load_image.is_some().into())?;
+    if let Some(image) = &settings.custom_load_image {
+        w.write_u32::<LE>(1)?;
+        write_block(image, &mut *w)?;
+    }
+    w.write_u32::<LE>(settings.loading_transparent.into())?;
+    w.write_u32::<LE>(settings.loading_translucency)?;
+    w.write_u32::<LE>(settings.loading_progress_bar_scale.into())?;
+    if !ctx.exe {
+        write_buffer(&settings.icon, &mut *w)?;
+    }
+    w.write_u32::<LE>(settings.show_error_messages.into())?;
+    w.write_u32::<LE>(settings.log_errors.into())?;
+    w.write_u32::<LE>(settings.always_abort.into())?;
+    let mut uninitialized = u32::from(settings.zero_uninitialized_vars);
+    if ctx.version == GmkVersion::Gm81 {
+        uninitialized |= u32::from(settings.error_on_uninitialized_args) << 1;
+    }
+    w.write_u32::<LE>(uninitialized)?;
+    if ctx.exe {
+        // settings version 825 tells gm82's runner the creation code flag follows
+        let version = if settings.swap_creation_events {
+            w.write_u32::<LE>(0)?; // webgl
+            w.write_u32::<LE>(1)?; // creation code flag
+            825
+        } else {
+            800
+        };
+        out.write_u32::<LE>(version)?;
+        return write_block(&data, out)
+    }
+    write_string(&settings.info_author, ctx.version, &mut *w)?;
+    write_string(&settings.info_version, ctx.version, &mut *w)?;
+    w.write_f64::<LE>(0.0)?; // timestamp
+    write_string(&settings.info_information, ctx.version, &mut *w)?;
+    w.write_u32::<LE>(settings.version_major)?;
+    w.write_u32::<LE>(settings.version_minor)?;
+    w.write_u32::<LE>(settings.version_release)?;
+    w.write_u32::<LE>(settings.version_build)?;
+    write_string(&settings.exe_company, ctx.version, &mut *w)?;
+    write_string(&settings.exe_product, ctx.version, &mut *w)?;
+    write_string(&settings.exe_copyright, ctx.version, &mut *w)?;
+    write_string(&settings.exe_description, ctx.version, &mut *w)?;
+    w.write_f64::<LE>(0.0)?; // timestamp
+    out.write_u32::<LE>(ctx.version.number())?;
+    write_block(&data, out)
+}
+
+pub(crate) fn write_triggers(project: &Project, ctx: &Indices, mut out: impl Write) -> Result<()> {
+    out.write_u32::<LE>(800)?;
+    out.write_u32::<LE>(project.triggers.len() as u32)?;
+    for trigger in &project.triggers {
+        let mut data = Vec::new();
+        data.write_u32::<LE>(trigger.is_some().into())?;
+        if let Some(trigger) = trigger {
+            data.write_u32::<LE>(800)?;
+            write_string(&trigger.name, ctx.version, &mut data)?;
+            write_string(&trigger.condition, ctx.version, &mut data)?;
+            data.write_u32::<LE>(trigger.kind)?;
+            write_string(&trigger.constant_name, ctx.version, &mut data)?;
+        }
+        write_block(&data, &mut out)?;
+    }
+    if !ctx.exe {
+        out.write_f64::<LE>(0.0)?; // timestamp
+    }
+    Ok(())
+}
+
+fn write_constants(project: &Project, version: GmkVersion, mut out: impl Write) -> Result<()> {
+    out.write_u32::<LE>(800)?;
+    out.write_u32::<LE>(project.constants.len() as u32)?;
+    for (name, value) in &project.constants {
+        write_string(name, version, &mut out)?;
+        write_string(value, version, &mut out)?;
+    }
+    out.write_f64::<LE>(0.0)?; // timestamp
+    Ok(())
+}
+
+pub(crate) fn write_included_files(project: &Project, ctx: &Indices, mut out: impl Write) -> Result<()> {
+    out.write_u32::<LE>(800)?;
+    out.write_u32::<LE>(project.included_files.len() as u32)?;
+    for file in &project.included_files {
+        let mut data = Vec::new();
+        if !ctx.exe {
+            data.write_f64::<LE>(0.0)?; // timestamp
+        }
+        data.write_u32::<LE>(800)?;
+        write_string(&file.file_name, ctx.version, &mut data)?;
+        write_string(&file.source_path.to_string_lossy(), ctx.version, &mut data)?;
+        data.write_u32::<LE>(file.data.is_some().into())?;
+        data.write_u32::<LE>(file.data.as_ref().map_or(0, |d| d.len() as u32))?;
+        match file.data.as_ref().filter(|_| file.stored_in_gmk) {
+            Some(file_data) => {
+                data.write_u32::<LE>(1)?;
+                write_buffer(file_data, &mut data)?;
+            },
+            None => data.write_u32::<LE>(0)?,
+        }
+        data.write_u32::<LE>(file.export_setting)?;
+        write_string(&file.export_custom_folder, ctx.version, &mut data)?;
+        data.write_u32::<LE>(file.overwrite_file.into())?;
+        data.write_u32::<LE>(file.free_memory.into())?;
+        data.write_u32::<LE>(file.remove_at_end.into())?;
+        write_block(&data, &mut out)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_game_information(info: &GameInformation, ctx: &Indices, mut out: impl Write) -> Result<()> {
+    let mut data = Vec::new();
+    data.write_u32::<LE>(info.colour)?;
+    data.write_u32::<LE>(info.new_window.into())?;
+    write_string(&info.caption, ctx.version, &mut data)?;
+    data.write_i32::<LE>(info.left)?;
+    data.write_i32::<LE>(info.top)?;
+    data.write_i32::<LE>(info.width)?;
+    data.write_i32::<LE>(info.height)?;
+    data.write_u32::<LE>(info.border.into())?;
+    data.write_u32::<LE>(info.resizable.into())?;
+    data.write_u32::<LE>(info.window_on_top.into())?;
+    data.write_u32::<LE>(info.freeze_game.into())?;
+    if !ctx.exe {
+        data.write_f64::<LE>(0.0)?; // timestamp
+    }
+    write_buffer(&info.rtf, &mut data)?;
+    out.write_u32::<LE>(800)?;
+    write_block(&data, out)
+}
+
+fn write_tree_children(
+    nodes: &[TreeNode],
+    group: u32,
+    map: &HashMap<&str, i32>,
+    version: GmkVersion,
+    out: &mut Vec<u8>,
+) -> Result<()> {
+    // assets that no longer exist get left out
+    let nodes = nodes
+        .iter()
+        .filter(|n| match n {
+            TreeNode::Asset(name) => map.contains_key(name.as_str()),
+            TreeNode::Folder(..) => true,
+        })
+        .collect::<Vec<_>>();
+    out.write_u32::<LE>(nodes.len() as u32)?;
+    for node in nodes {
+        match node {
+            TreeNode::Folder(name, children) => {
+                out.write_u32::<LE>(2)?;
+                out.write_u32::<LE>(group)?;
+                out.write_u32::<LE>(0)?;
+                write_string(name, version, &mut *out)?;
+                write_tree_children(children, group, map, version, out)?;
+            },
+            TreeNode::Asset(name) => {
+                out.write_u32::<LE>(3)?;
+                out.write_u32::<LE>(group)?;
+                out.write_i32::<LE>(index_of(map, name))?;
+                write_string(name, version, &mut *out)?;
+                out.write_u32::<LE>(0)?;
+            },
+        }
+    }
+    Ok(())
+}
+
+fn write_tree(project: &Project, ctx: &Indices, out: &mut Vec<u8>) -> Result<()> {
+    // name, resource kind, and the tree of that kind if it has one
+    type Root<'a> = (&'a str, u32, Option<(&'a [TreeNode], &'a HashMap<&'a str, i32>)>);
+    let roots: [Root; 12] = [
+        ("Sprites", 2, Some((&project.sprites.tree, &ctx.sprites))),
+        ("Sounds", 3, Some((&project.sounds.tree, &ctx.sounds))),
+        ("Backgrounds", 6, Some((&project.backgrounds.tree, &ctx.backgrounds))),
+        ("Paths", 8, Some((&project.paths.tree, &ctx.paths))),
+        ("Scripts", 7, Some((&project.scripts.tree, &ctx.scripts))),
+        ("Fonts", 9, Some((&project.fonts.tree, &ctx.fonts))),
+        ("Time Lines", 12, Some((&project.timelines.tree, &ctx.timelines))),
+        ("Objects", 1, Some((&project.objects.tree, &ctx.objects))),
+        ("Rooms", 4, Some((&project.rooms.tree, &ctx.rooms))),
+        ("Game Information", 10, None),
+        ("Global Game Settings", 11, None),
+        ("Extension Packages", 13, None),
+    ];
+    for (name, group, children) in roots {
+        out.write_u32::<LE>(1)?;
+        out.write_u32::<LE>(group)?;
+        out.write_u32::<LE>(0)?;
+        write_string(name, ctx.version, &mut *out)?;
+        match children {
+            Some((nodes, map)) => write_tree_children(nodes, group, map, ctx.version, out)?,
+            None => out.write_u32::<LE>(0)?,
+        }
+    }
+    Ok(())
+}
+
+fn room_order(nodes: &[TreeNode], map: &HashMap<&str, i32>, order: &mut Vec<i32>) {
+    for node in nodes {
+        match node {
+            TreeNode::Folder(_, children) => room_order(children, map, order),
+            TreeNode::Asset(name) => order.extend(map.get(name.as_str())),
+        }
+    }
+}
+
+/// The room order is always the order of the resource tree.
+pub(crate) fn write_room_order(project: &Project, ctx: &Indices, mut out: impl Write) -> Result<()> {
+    let mut order = Vec::with_capacity(ctx.rooms.len());
+    room_order(&project.rooms.tree, &ctx.rooms, &mut order);
+    out.write_u32::<LE>(700)?;
+    out.write_u32::<LE>(order.len() as u32)?;
+    for room in order {
+        out.write_i32::<LE>(room)?;
+    }
+    Ok(())
+}
+
+/// The ids of every instance and every tile in a room, in order.
+pub(crate) type RoomIds = (Vec<usize>, Vec<usize>);
+
+/// Instance and tile ids usually aren't kept in gm82 projects, so the ones that aren't set get handed out in order,
+/// after the highest one that is. This returns the ids for each room, and the last ids overall.
+pub(crate) fn instance_ids(rooms: &AssetList<Room>) -> (Vec<RoomIds>, (usize, usize)) {
+    const FIRST_INSTANCE_ID: usize = 100000;
+    const FIRST_TILE_ID: usize = 10000000;
+    let all_rooms = || rooms.assets.iter().flatten();
+    let mut last = (
+        all_rooms().flat_map(|r| &r.instances).map(|i| i.id).fold(FIRST_INSTANCE_ID, usize::max),
+        all_rooms().flat_map(|r| &r.tiles).map(|t| t.id).fold(FIRST_TILE_ID, usize::max),
+    );
+    fn next(id: usize, last: &mut usize) -> usize {
+        if id == 0 {
+            *last += 1;
+            *last
+        } else {
+            id
+        }
+    }
+    let room_ids = rooms
+        .assets
+        .iter()
+        .map(|room| match room {
+            Some(room) => (
+                room.instances.iter().map(|i| next(i.id, &mut last.0)).collect(),
+                room.tiles.iter().map(|t| next(t.id, &mut last.1)).collect(),
+            ),
+            None => Default::default(),
+        })
+        .collect();
+    (room_ids, last)
+}
+
+/// Every asset section from sounds to rooms, followed by the last instance and tile ids.
+pub(crate) fn write_all_assets(project: &Project, ctx: &Indices, mut out: impl Write) -> Result<()> {
+    write_assets(&project.sounds, ctx, write_sound, &mut out)?;
+    write_assets(&project.sprites, ctx, write_sprite, &mut out)?;
+    write_assets(&project.backgrounds, ctx, write_background, &mut out)?;
+    write_assets(&project.paths, ctx, write_path, &mut out)?;
+    write_assets(&project.scripts, ctx, write_script, &mut out)?;
+    write_assets(&project.fonts, ctx, write_font, &mut out)?;
+    write_assets(&project.timelines, ctx, write_timeline, &mut out)?;
+    write_assets(&project.objects, ctx, write_object, &mut out)?;
+    let rooms = &project.rooms;
+    let (room_ids, last_ids) = instance_ids(rooms);
+    let room_list = AssetList {
+        assets: rooms.assets.iter().zip(room_ids).map(|(r, ids)| r.as_ref().map(|r| (r, ids))).collect(),
+        names: rooms.names.clone(),
+        timestamps: rooms.timestamps.clone(),
+        tree: Vec::new(),
+    };
+    write_assets(&room_list, ctx, |(room, ids), out, ctx| write_room(room, out, ctx, ids), &mut out)?;
+    out.write_u32::<LE>(last_ids.0 as u32)?;
+    out.write_u32::<LE>(last_ids.1 as u32)?;
+    Ok(())
+}
+
+/// Turns a project into the contents of a .gmk or .gm81 file.
+/// Action definitions aren't part of the project, so they get looked up in `libs`.
+pub fn write_gm81(project: &Project, libs: &[ActionLibrary], version: GmkVersion) -> Result<Vec<u8>> {
+    crate::save::check_project(project)?;
+    let ctx = Indices::new(project, libs, version, false);
+    let out = &mut Vec::new();
+    out.write_u32::<LE>(GMK_MAGIC)?;
+    out.write_u32::<LE>(version.number())?;
+    out.write_u32::<LE>(project.game_id)?;
+    out.write_all(&[0; 16])?; // guid
+    write_settings(project, &ctx, &mut *out)?;
+    write_triggers(project, &ctx, &mut *out)?;
+    write_constants(project, version, &mut *out)?;
+    write_all_assets(project, &ctx, &mut *out)?;
+    write_included_files(project, &ctx, &mut *out)?;
+    out.write_u32::<LE>(700)?;
+    out.write_u32::<LE>(project.extensions.len() as u32)?;
+    for extension in &project.extensions {
+        write_string(extension, version, &mut *out)?;
+    }
+    write_game_information(&project.game_info, &ctx, &mut *out)?;
+    // library creation code, which gm doesn't actually use
+    out.write_u32::<LE>(500)?;
+    out.write_u32::<LE>(0)?;
+    write_room_order(project, &ctx, &mut *out)?;
+    write_tree(project, &ctx, out)?;
+    Ok(std::mem::take(out))
+}
+
+/// Saves a project as a .gmk or .gm81 file.
+pub fn save_gm81(
+    project: &Project,
+    libs: &[ActionLibrary],
+    path: impl AsRef<FsPath>,
+    version: GmkVersion,
+) -> Result<()> {
+    let path = path.as_ref();
+    let data = write_gm81(project, libs, version)?;
+    std::fs::write(path, data).map_err(|e| Error::FileIoError(e, path.to_path_buf()))
+}
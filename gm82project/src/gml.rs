@@ -0,0 +1,113 @@
+// Tokenizer for GML the way GameMaker 8 reads it, for anything that needs to look at code
+// without being fooled by strings and comments.
+// GML has no escapes in strings, so a string runs until the next matching quote, across lines if need be.
+
+use std::ops::Range;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    Identifier,
+    /// Decimal, or hexadecimal starting with `$`.
+    Number,
+    /// Single or double quoted, including the quotes.
+    String,
+    /// `//` up to the end of the line, or `/* */`.
+    Comment,
+    Operator,
+    /// Anything else, like non-ASCII characters outside of strings.
+    Other,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    /// Byte range in the code.
+    pub span: Range<usize>,
+    pub text: &'a str,
+}
+
+const OPERATORS: [&str; 18] =
+    [":=", "==", "!=", "<>", "<=", ">=", "&&", "||", "^^", "<<", ">>", "+=", "-=", "*=", "/=", "|=", "&=", "^="];
+
+/// Iterates over the tokens in some GML, skipping whitespace.
+pub struct Lexer<'a> {
+    code: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(code: &'a str) -> Self {
+        Self { code, pos: 0 }
+    }
+
+    fn find_from(&self, from: usize, pat: &str) -> Option<usize> {
+        Some(from + self.code.get(from..)?.find(pat)?)
+    }
+
+    fn skip_chars(&self, from: usize, f: impl Fn(u8) -> bool) -> usize {
+        let bytes = self.code.as_bytes();
+        (from..bytes.len()).find(|&i| !f(bytes[i])).unwrap_or(bytes.len())
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bytes = self.code.as_bytes();
+        let start = self.skip_chars(self.pos, |c| c.is_ascii_whitespace());
+        let c = *bytes.get(start)?;
+        let next = bytes.get(start + 1).copied();
+        let (kind, end) = match c {
+            b'/' if next == Some(b'/') => {
+                let end = self.find_from(start, "\n").unwrap_or(bytes.len());
+                // the \r of a \r\n isn't part of the comment
+                (TokenKind::Comment, if end > start && bytes[end - 1] == b'\r' { end - 1 } else { end })
+            },
+            b'/' if next == Some(b'*') => {
+                (TokenKind::Comment, self.find_from(start + 2, "*/").map_or(bytes.len(), |end| end + 2))
+            },
+            b'"' | b'\'' => {
+                let quote = if c == b'"' { "\"" } else { "'" };
+                (TokenKind::String, self.find_from(start + 1, quote).map_or(bytes.len(), |end| end + 1))
+            },
+            b'$' => (TokenKind::Number, self.skip_chars(start + 1, |c| c.is_ascii_hexdigit())),
+            b'0'..=b'9' => (TokenKind::Number, self.skip_chars(start, |c| c.is_ascii_digit() || c == b'.')),
+            b'.' if next.is_some_and(|c| c.is_ascii_digit()) => {
+                (TokenKind::Number, self.skip_chars(start, |c| c.is_ascii_digit() || c == b'.'))
+            },
+            b'A'..=b'Z' | b'a'..=b'z' | b'_' => {
+                (TokenKind::Identifier, self.skip_chars(start, |c| c.is_ascii_alphanumeric() || c == b'_'))
+            },
+            _ if c.is_ascii_punctuation() => {
+                let two = self.code.get(start..start + 2).filter(|op| OPERATORS.contains(op));
+                (TokenKind::Operator, start + two.map_or(1, str::len))
+            },
+            // skip a whole character so the span stays on char boundaries
+            _ => (TokenKind::Other, start + self.code[start..].chars().next().map_or(1, char::len_utf8)),
+        };
+        self.pos = end;
+        Some(Token { kind, span: start..end, text: &self.code[start..end] })
+    }
+}
+
+/// The identifiers in some GML that could refer to an asset, instance or constant.
+/// Identifiers right after a `.` are left out, since those are variables of something else.
+pub fn identifiers(code: &str) -> impl Iterator<Item = Token<'_>> {
+    let mut after_dot = false;
+    Lexer::new(code).filter(|t| t.kind != TokenKind::Comment).filter(move |t| {
+        let dot = std::mem::replace(&mut after_dot, t.kind == TokenKind::Operator && t.text == ".");
+        t.kind == TokenKind::Identifier && !dot
+    })
+}
+
+/// Splits an instance reference like `room_0A1B2C3D` into the room name and the instance's hex name.
+pub fn instance_name(ident: &str) -> Option<(&str, u32)> {
+    let at = ident.len().checked_sub(9)?;
+    let (room, name) = (ident.get(..at)?, ident.get(at..)?);
+    let name = name.strip_prefix('_')?;
+    if room.is_empty() || !name.bytes().all(|c| c.is_ascii_digit() || (b'A'..=b'F').contains(&c)) {
+        return None
+    }
+    Some((room, u32::from_str_radix(name, 16).ok()?))
+}
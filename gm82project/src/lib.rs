@@ -0,0 +1,177 @@
+//! Headless reader and writer for .gm82 projects.
+//!
+//! gm82save loads and saves projects straight into GameMaker's memory, so it only works inside the IDE.
+//! This crate understands the same on-disk layout using plain Rust data, so projects can be read anywhere.
+
+pub mod actionlib;
+pub mod asset;
+pub mod diff;
+pub mod events;
+pub mod font_render;
+pub mod gamedata;
+pub mod gm81;
+pub mod gml;
+pub mod lint;
+pub mod load;
+pub mod manifest;
+pub mod mask;
+pub mod merge;
+pub mod pack;
+pub mod project;
+pub mod rename;
+pub mod save;
+pub mod transaction;
+pub mod xref;
+
+pub use gm81::{load_gm81, save_gm81};
+pub use load::load_gmk;
+pub use project::*;
+pub use save::save_gmk;
+
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum Error {
+    IoError(std::io::Error),
+    FileIoError(std::io::Error, PathBuf),
+    DirIoError(std::io::Error, PathBuf),
+    PngDecodeError(PathBuf, png::DecodingError),
+    UnicodeError(String),
+    AssetNotFound(String, &'static str, String),
+    SyntaxError(PathBuf),
+    UnknownKey(PathBuf, String),
+    UnknownAction(u32, u32),
+    ParseIntError(std::num::ParseIntError),
+    ParseFloatError(std::num::ParseFloatError),
+    InvalidVersion(String),
+    DuplicateAsset(String),
+    DuplicateIncludedFile(String),
+    DuplicateTrigger(String),
+    DuplicateId(&'static str, usize, String),
+    BadAssetName(String, char),
+    BadIncludedFileName(String, char),
+    BadTriggerName(String, char),
+    PathTooLong(String, usize),
+    UnfinishedSave(PathBuf),
+    OldGM82,
+    Other(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn fmt_path(p: &std::path::Path) -> impl std::fmt::Display + '_ {
+            let mut components = p.components();
+            for _ in 0..components.clone().count().saturating_sub(3) {
+                components.next();
+            }
+            components.as_path().display()
+        }
+        fn fmt_bad_name(f: &mut std::fmt::Formatter<'_>, what: &str, n: &str, c: char) -> std::fmt::Result {
+            if c == '.' || c == ' ' {
+                write!(f, "{what} \"{n}\" is illegal")
+            } else {
+                write!(f, "{what} {n} may not contain character {}", c.escape_debug())
+            }
+        }
+        match self {
+            Self::IoError(e) => write!(f, "io error: {}", e),
+            Self::FileIoError(e, p) => write!(f, "io error in file {}: {}", fmt_path(p), e),
+            Self::DirIoError(e, p) => write!(f, "io error in directory {}: {}", fmt_path(p), e),
+            Self::PngDecodeError(p, e) => write!(f, "couldn't decode image {}: {}", fmt_path(p), e),
+            Self::UnicodeError(s) => write!(f, "couldn't encode {}", s),
+            Self::AssetNotFound(s, t, src) => write!(f, "couldn't find {} {} (from {})", t, s, src),
+            Self::SyntaxError(p) => write!(f, "syntax error in file {}", fmt_path(p)),
+            Self::UnknownKey(p, k) => write!(f, "unknown key in {}: {:?}", fmt_path(p), k),
+            Self::UnknownAction(lib_id, act_id) => write!(f, "unknown action {} in lib with id {}", act_id, lib_id),
+            Self::ParseIntError(e) => write!(f, "integer parse error: {}", e),
+            Self::ParseFloatError(e) => write!(f, "float parse error: {}", e),
+            Self::InvalidVersion(v) => write!(f, "invalid exe_version {}", v),
+            Self::DuplicateAsset(n) => write!(f, "multiple assets named {}", n),
+            Self::DuplicateIncludedFile(n) => write!(f, "multiple included files named {}", n),
+            Self::DuplicateTrigger(n) => write!(f, "multiple triggers named {}", n),
+            Self::DuplicateId(t, id, room) => {
+                write!(f, "multiple {}s with id {} (found again in room {})", t, id, room)
+            },
+            Self::BadAssetName(n, c) => fmt_bad_name(f, "asset name", n, *c),
+            Self::BadIncludedFileName(n, c) => fmt_bad_name(f, "included file name", n, *c),
+            Self::BadTriggerName(n, c) => fmt_bad_name(f, "trigger file name", n, *c),
+            Self::PathTooLong(p, len) => {
+                write!(
+                    f,
+                    "path {} would be {} characters long, windows only allows {}",
+                    p,
+                    len,
+                    transaction::MAX_PATH - 1
+                )
+            },
+            Self::UnfinishedSave(p) => {
+                write!(
+                    f,
+                    "{} has a save that didn't finish, opening or saving the project will deal with it",
+                    fmt_path(p)
+                )
+            },
+            Self::OldGM82 => write!(f, "this project was made with a newer version of gm82save, please update"),
+            Self::Other(s) => write!(f, "other error: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::IoError(err)
+    }
+}
+
+impl From<std::num::ParseIntError> for Error {
+    fn from(err: std::num::ParseIntError) -> Self {
+        Error::ParseIntError(err)
+    }
+}
+
+impl From<std::num::ParseFloatError> for Error {
+    fn from(err: std::num::ParseFloatError) -> Self {
+        Error::ParseFloatError(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+// line iterator that strips right end but only if not in a string
+// strings are found with the lexer, so quotes in comments don't count, which older versions got wrong:
+// after a comment like `// don't`, lines kept their trailing spaces until the next quote
+pub struct GMLLines<'a> {
+    code: &'a str,
+    lines: std::str::Lines<'a>,
+    tokens: gml::Lexer<'a>,
+    token: Option<gml::Token<'a>>,
+}
+
+impl<'a> GMLLines<'a> {
+    pub fn new(code: &'a str) -> Self {
+        let mut tokens = gml::Lexer::new(code);
+        Self { code, lines: code.lines(), token: tokens.next(), tokens }
+    }
+}
+
+impl<'a> Iterator for GMLLines<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // trim line only if EOL is not in a string
+        let line = self.lines.next()?;
+        let end = line.as_ptr() as usize - self.code.as_ptr() as usize + line.len();
+        while self.token.as_ref().is_some_and(|t| t.span.end <= end) {
+            self.token = self.tokens.next();
+        }
+        let in_string = self.token.as_ref().is_some_and(|t| t.kind == gml::TokenKind::String && t.span.start < end);
+        Some(if in_string { line } else { line.trim_end() })
+    }
+}
+
+pub const ACTION_TOKEN: &str = "/*\"/*'/**//* YYD ACTION";
+
+/// The newest gm82_version this crate reads, and what it writes for projects with [`Project::escaped_utf16`] set.
+pub const GM82_VERSION: u8 = 6;
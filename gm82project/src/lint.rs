@@ -0,0 +1,387 @@
+// Checks for things in a project that are likely mistakes, or that keep it from being saved or built.
+// Each rule has a severity, and some know how to fix what they find.
+// Errors are things that would stop the project from saving or building, or that make it behave wrong.
+
+use crate::{
+    AssetList, Project, Result, TreeNode,
+    actionlib::{self, ActionLibrary},
+    asset::*,
+    events::{self, EVENT_NAMES},
+    rename::rename_asset,
+    save::{filename_invalid, make_unicase},
+};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Warning => f.pad("warning"),
+            Self::Error => f.pad("error"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Problem {
+    pub rule: &'static str,
+    pub severity: Severity,
+    /// What the problem is in, such as "objects/obj_player" or "objects/obj_player Step_0 action 2".
+    pub location: String,
+    pub message: String,
+}
+
+type Check = fn(&Project, Option<&[ActionLibrary]>, &mut Vec<(String, String)>);
+type Fix = fn(&mut Project, Option<&[ActionLibrary]>) -> Result<usize>;
+
+pub struct Rule {
+    pub name: &'static str,
+    pub severity: Severity,
+    pub description: &'static str,
+    check: Check,
+    fix: Option<Fix>,
+}
+
+impl Rule {
+    pub fn can_fix(&self) -> bool {
+        self.fix.is_some()
+    }
+}
+
+pub const RULES: [Rule; 7] = [
+    Rule {
+        name: "bad-names",
+        severity: Severity::Error,
+        description: "asset names that can't be used as file names",
+        check: check_bad_names,
+        fix: Some(fix_bad_names),
+    },
+    Rule {
+        name: "duplicate-names",
+        severity: Severity::Error,
+        description: "assets of the same type whose names only differ by case, or not at all",
+        check: check_duplicate_names,
+        fix: Some(fix_duplicate_names),
+    },
+    Rule {
+        name: "name-collisions",
+        severity: Severity::Warning,
+        description: "assets, constants and triggers that share a name, so code can only refer to one of them",
+        check: check_name_collisions,
+        fix: None,
+    },
+    Rule {
+        name: "missing-applies-to",
+        severity: Severity::Warning,
+        description: "actions that apply to an object that doesn't exist, so they do nothing",
+        check: check_missing_applies_to,
+        fix: None,
+    },
+    Rule {
+        name: "missing-collision-object",
+        severity: Severity::Error,
+        description: "collision events with an object that doesn't exist",
+        check: check_missing_collision_objects,
+        fix: Some(fix_missing_collision_objects),
+    },
+    Rule {
+        name: "unknown-action",
+        severity: Severity::Error,
+        description: "actions that aren't in any action library, only checked if the libraries are given",
+        check: check_unknown_actions,
+        fix: None,
+    },
+    Rule {
+        name: "empty-rooms",
+        severity: Severity::Warning,
+        description: "rooms with no instances, tiles, backgrounds or creation code",
+        check: check_empty_rooms,
+        fix: None,
+    },
+];
+
+/// Runs every rule over the project, returning what was found, errors first.
+pub fn lint(project: &Project, libs: Option<&[ActionLibrary]>) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    for rule in &RULES {
+        let mut found = Vec::new();
+        (rule.check)(project, libs, &mut found);
+        problems.extend(found.into_iter().map(|(location, message)| Problem {
+            rule: rule.name,
+            severity: rule.severity,
+            location,
+            message,
+        }));
+    }
+    problems.sort_by_key(|p| std::cmp::Reverse(p.severity));
+    problems
+}
+
+/// Fixes everything the rules know how to fix, returning how many problems that was.
+pub fn fix(project: &mut Project, libs: Option<&[ActionLibrary]>) -> Result<usize> {
+    let mut count = 0;
+    for fix in RULES.iter().filter_map(|rule| rule.fix) {
+        count += fix(project, libs)?;
+    }
+    Ok(count)
+}
+
+/// Every asset type's name and the names of its assets, along with their slots.
+fn asset_names(project: &Project) -> [(&'static str, Vec<(usize, &str)>); 9] {
+    fn names<T>(list: &AssetList<T>) -> Vec<(usize, &str)> {
+        list.names
+            .iter()
+            .zip(&list.assets)
+            .enumerate()
+            .filter(|(_, (_, a))| a.is_some())
+            .map(|(i, (n, _))| (i, n.as_str()))
+            .collect()
+    }
+    [
+        ("sprites", names(&project.sprites)),
+        ("sounds", names(&project.sounds)),
+        ("backgrounds", names(&project.backgrounds)),
+        ("paths", names(&project.paths)),
+        ("scripts", names(&project.scripts)),
+        ("fonts", names(&project.fonts)),
+        ("timelines", names(&project.timelines)),
+        ("objects", names(&project.objects)),
+        ("rooms", names(&project.rooms)),
+    ]
+}
+
+fn kind_names<'a>(project: &'a Project, kind: &str) -> Vec<(usize, &'a str)> {
+    asset_names(project).into_iter().find(|(k, _)| *k == kind).map(|(_, names)| names).unwrap_or_default()
+}
+
+fn names_and_tree<'a>(project: &'a mut Project, kind: &str) -> (&'a mut Vec<String>, &'a mut Vec<TreeNode>) {
+    fn split<T>(list: &mut AssetList<T>) -> (&mut Vec<String>, &mut Vec<TreeNode>) {
+        (&mut list.names, &mut list.tree)
+    }
+    match kind {
+        "sprites" => split(&mut project.sprites),
+        "sounds" => split(&mut project.sounds),
+        "backgrounds" => split(&mut project.backgrounds),
+        "paths" => split(&mut project.paths),
+        "scripts" => split(&mut project.scripts),
+        "fonts" => split(&mut project.fonts),
+        "timelines" => split(&mut project.timelines),
+        "objects" => split(&mut project.objects),
+        _ => split(&mut project.rooms),
+    }
+}
+
+/// Adds a number to the end of a name until no asset of that type has it, ignoring case.
+fn unused_name(names: &[(usize, &str)], base: &str) -> String {
+    let taken = names.iter().map(|(_, n)| make_unicase(n.to_string())).collect::<HashSet<_>>();
+    (2..).map(|i| format!("{}_{}", base, i)).find(|n| !taken.contains(&make_unicase(n.clone()))).unwrap()
+}
+
+/// Calls `f` on every event in the project, with where it is.
+fn for_each_event(project: &Project, mut f: impl FnMut(String, &Event)) {
+    for (name, timeline) in project.timelines.iter() {
+        for (time, event) in &timeline.moments {
+            f(format!("timelines/{} moment {}", name, time), event);
+        }
+    }
+    for (name, object) in project.objects.iter() {
+        for (ev_type, events) in object.events.iter().enumerate() {
+            for (ev_numb, event) in events {
+                f(format!("objects/{} {}_{}", name, EVENT_NAMES[ev_type], ev_numb), event);
+            }
+        }
+    }
+}
+
+fn check_bad_names(project: &Project, _: Option<&[ActionLibrary]>, out: &mut Vec<(String, String)>) {
+    for (kind, names) in asset_names(project) {
+        for (_, name) in names {
+            let message = match filename_invalid(name).map(char::from) {
+                Some('.' | ' ') => "name can't be used as a file name on Windows".to_string(),
+                Some(c) => format!("name can't contain {:?}", c),
+                None => continue,
+            };
+            out.push((format!("{}/{}", kind, name), message));
+        }
+    }
+}
+
+fn fix_bad_names(project: &mut Project, libs: Option<&[ActionLibrary]>) -> Result<usize> {
+    let mut count = 0;
+    for kind in asset_names(project).map(|(k, _)| k) {
+        loop {
+            let names = kind_names(project, kind);
+            let Some(&(_, name)) = names.iter().find(|(_, n)| filename_invalid(n).is_some()) else { break };
+            let mut new = name
+                .chars()
+                .map(|c| if "<>:\"/\\|?*".contains(c) || c.is_ascii_control() { '_' } else { c })
+                .collect::<String>();
+            new = new.trim().trim_end_matches(['.', ' ']).to_string();
+            if new.is_empty() {
+                new = "unnamed".into();
+            } else if filename_invalid(&new).is_some() {
+                // what's left is a device name like CON
+                new.insert(0, '_');
+            }
+            let others = names.iter().filter(|(_, n)| *n != name).copied().collect::<Vec<_>>();
+            if others.iter().any(|(_, n)| make_unicase(n.to_string()) == make_unicase(new.clone())) {
+                new = unused_name(&others, &new);
+            }
+            let name = name.to_string();
+            rename_asset(project, kind, &name, &new, libs)?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+fn check_duplicate_names(project: &Project, _: Option<&[ActionLibrary]>, out: &mut Vec<(String, String)>) {
+    for (kind, names) in asset_names(project) {
+        let mut seen = HashMap::new();
+        for (index, name) in names {
+            let &mut (first_index, first) = seen.entry(make_unicase(name.to_string())).or_insert((index, name));
+            if first_index != index {
+                out.push((format!("{}/{}", kind, name), format!("same name as {}/{}", kind, first)));
+            }
+        }
+    }
+}
+
+fn fix_duplicate_names(project: &mut Project, libs: Option<&[ActionLibrary]>) -> Result<usize> {
+    fn rename_last(nodes: &mut [TreeNode], old: &str, new: &str) -> bool {
+        nodes.iter_mut().rev().any(|node| match node {
+            TreeNode::Folder(_, children) => rename_last(children, old, new),
+            TreeNode::Asset(name) if name == old => {
+                *name = new.to_string();
+                true
+            },
+            TreeNode::Asset(_) => false,
+        })
+    }
+    let mut count = 0;
+    for kind in asset_names(project).map(|(k, _)| k) {
+        loop {
+            let names = kind_names(project, kind);
+            let mut seen = HashMap::new();
+            let Some((index, first, name)) = names.iter().find_map(|&(i, name)| {
+                seen.insert(make_unicase(name.to_string()), name).map(|first| (i, first.to_string(), name.to_string()))
+            }) else {
+                break
+            };
+            let new = unused_name(&names, &name);
+            if first == name {
+                // references by name already meant the first one, so only this one's name changes
+                let (names, tree) = names_and_tree(project, kind);
+                names[index] = new.clone();
+                rename_last(tree, &name, &new);
+            } else {
+                rename_asset(project, kind, &name, &new, libs)?;
+            }
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+fn check_name_collisions(project: &Project, _: Option<&[ActionLibrary]>, out: &mut Vec<(String, String)>) {
+    let mut seen: HashMap<String, String> = HashMap::new();
+    let mut check = |location: String, name: &str, out: &mut Vec<(String, String)>| {
+        if name.is_empty() {
+            return
+        }
+        match seen.get(name) {
+            Some(first) => out.push((location, format!("{} is also {}", name, first))),
+            None => {
+                seen.insert(name.to_string(), location);
+            },
+        }
+    };
+    for (kind, names) in asset_names(project) {
+        // same-type duplicates are their own rule
+        let mut own = HashSet::new();
+        for (_, name) in names {
+            if own.insert(name) {
+                check(format!("{}/{}", kind, name), name, out);
+            }
+        }
+    }
+    for trigger in project.triggers.iter().flatten() {
+        check(format!("triggers/{} constant", trigger.name), &trigger.constant_name, out);
+    }
+    for (name, _) in &project.constants {
+        check(format!("constants/{}", name), name, out);
+    }
+}
+
+fn check_missing_applies_to(project: &Project, _: Option<&[ActionLibrary]>, out: &mut Vec<(String, String)>) {
+    for_each_event(project, |location, event| {
+        for (i, action) in event.actions.iter().enumerate() {
+            match action.applies_to.as_deref() {
+                None | Some("self" | "other") => (),
+                Some(object) if project.objects.index_of(object).is_some() => (),
+                Some(object) => out.push((
+                    format!("{} action {}", location, i),
+                    if object.is_empty() {
+                        "applies to a deleted object".to_string()
+                    } else {
+                        format!("applies to missing object {}", object)
+                    },
+                )),
+            }
+        }
+    });
+}
+
+fn check_missing_collision_objects(project: &Project, _: Option<&[ActionLibrary]>, out: &mut Vec<(String, String)>) {
+    for (name, object) in project.objects.iter() {
+        for (ev_numb, _) in &object.events[events::EV_COLLISION] {
+            if !matches!(ev_numb, EventNumber::Name(other) if project.objects.index_of(other).is_some()) {
+                out.push((
+                    format!("objects/{} Collision_{}", name, ev_numb),
+                    format!("collision with missing object {}", ev_numb),
+                ));
+            }
+        }
+    }
+}
+
+fn fix_missing_collision_objects(project: &mut Project, _: Option<&[ActionLibrary]>) -> Result<usize> {
+    // the event can never run, so it goes
+    let objects = project.objects.iter().map(|(name, _)| name.to_string()).collect::<HashSet<_>>();
+    let mut count = 0;
+    for (_, object) in project.objects.iter_mut() {
+        let events = &mut object.events[events::EV_COLLISION];
+        let before = events.len();
+        events.retain(|(ev_numb, _)| matches!(ev_numb, EventNumber::Name(other) if objects.contains(other)));
+        count += before - events.len();
+    }
+    Ok(count)
+}
+
+fn check_unknown_actions(project: &Project, libs: Option<&[ActionLibrary]>, out: &mut Vec<(String, String)>) {
+    let Some(libs) = libs else { return };
+    for_each_event(project, |location, event| {
+        for (i, action) in event.actions.iter().enumerate() {
+            if let Err(e) = actionlib::find_action(libs, action.lib_id, action.id) {
+                out.push((format!("{} action {}", location, i), e.to_string()));
+            }
+        }
+    });
+}
+
+fn check_empty_rooms(project: &Project, _: Option<&[ActionLibrary]>, out: &mut Vec<(String, String)>) {
+    for (name, room) in project.rooms.iter() {
+        if room.instances.is_empty()
+            && room.tiles.is_empty()
+            && room.creation_code.trim().is_empty()
+            && room.backgrounds.iter().all(|b| !b.visible_on_start || b.source_bg.is_empty())
+        {
+            out.push((format!("rooms/{}", name), "room is empty".to_string()));
+        }
+    }
+}
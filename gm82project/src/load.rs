@@ -0,0 +1,1232 @@
+use crate::{
+    ACTION_TOKEN, AssetList, Error, GM82_VERSION, GMLLines, GameInformation, Project, Result, TreeNode, asset::*,
+    events, transaction,
+};
+use rayon::prelude::*;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    ffi::OsStr,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::PathBuf,
+};
+
+pub fn undelimit(s: &str) -> String {
+    s.replace("*\\/", "*/").replace("\\n", "\n").replace("\\r", "\r").replace("\\\\", "\\")
+}
+
+/// Splits a comma-separated line written with [`crate::save::quote_field`], or returns None if a quoted field
+/// isn't closed properly. Lines without quotes split the same way they always have.
+pub fn split_fields(line: &str) -> Option<Vec<Cow<'_, str>>> {
+    let mut fields = Vec::new();
+    let mut rest = line;
+    loop {
+        if let Some(mut quoted) = rest.strip_prefix('"') {
+            let mut field = String::new();
+            loop {
+                let end = quoted.find('"')?;
+                field.push_str(&quoted[..end]);
+                quoted = &quoted[end + 1..];
+                // a doubled quote is one that's part of the field
+                match quoted.strip_prefix('"') {
+                    Some(after) => {
+                        field.push('"');
+                        quoted = after;
+                    },
+                    None => break,
+                }
+            }
+            fields.push(Cow::Owned(field));
+            match quoted.strip_prefix(',') {
+                Some(after) => rest = after,
+                None if quoted.is_empty() => return Some(fields),
+                None => return None,
+            }
+        } else {
+            match rest.split_once(',') {
+                Some((field, after)) => {
+                    fields.push(Cow::Borrowed(field));
+                    rest = after;
+                },
+                None => {
+                    fields.push(Cow::Borrowed(rest));
+                    return Some(fields)
+                },
+            }
+        }
+    }
+}
+
+/// Turns `##define` lines in an event's code back into the `#define` lines they were saved from,
+/// undoing [`crate::save::escape_defines`].
+pub fn unescape_defines(code: &str) -> String {
+    let mut out = String::with_capacity(code.len());
+    for (i, line) in code.split('\n').enumerate() {
+        if i != 0 {
+            out.push('\n');
+        }
+        match line.strip_prefix('#') {
+            Some(rest) if rest.starts_with('#') && rest.trim_start_matches('#').starts_with("define") => {
+                out.push_str(rest)
+            },
+            _ => out.push_str(line),
+        }
+    }
+    out
+}
+
+/// Turns text saved with [`crate::save::escape_utf16`] back into the UTF-16 it came from.
+pub fn unescape_utf16(s: &str) -> Vec<u16> {
+    let mut out = Vec::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(at) = rest.find("\\u{") {
+        out.extend(rest[..at].encode_utf16());
+        rest = &rest[at..];
+        let escape = rest[3..]
+            .split_once('}')
+            .map(|(hex, _)| hex)
+            .filter(|hex| (1..=4).contains(&hex.len()) && hex.bytes().all(|c| c.is_ascii_hexdigit()))
+            .and_then(|hex| Some((u16::from_str_radix(hex, 16).ok()?, hex.len())));
+        if let Some((unit, len)) = escape {
+            out.push(unit);
+            rest = &rest[len + 4..];
+        } else {
+            // not an escape after all, so it's written as is
+            out.push(u16::from(b'\\'));
+            rest = &rest[1..];
+        }
+    }
+    out.extend(rest.encode_utf16());
+    out
+}
+
+/// Unescapes text from a project that escapes it, where anything that isn't valid UTF-16 becomes U+FFFD.
+/// [`crate::save::escape_text`] undoes it, apart from that.
+pub fn unescape_text(s: &str) -> String {
+    String::from_utf16_lossy(&unescape_utf16(s))
+}
+
+pub fn load_gml(code: &str) -> String {
+    let mut buf = String::with_capacity(code.len());
+    // don't use string.replace() in case your gml is \r\n for some reason
+    for line in GMLLines::new(code) {
+        buf += line;
+        buf += "\r\n";
+    }
+    buf
+}
+
+pub struct Assets {
+    pub index: Vec<String>,
+    pub map: HashMap<String, usize>,
+}
+
+pub struct AssetMaps {
+    pub triggers: Assets,
+    pub sprites: Assets,
+    pub sounds: Assets,
+    pub backgrounds: Assets,
+    pub paths: Assets,
+    pub scripts: Assets,
+    pub objects: Assets,
+    pub rooms: Assets,
+    pub fonts: Assets,
+    pub timelines: Assets,
+}
+
+fn open_file(path: &std::path::Path) -> Result<BufReader<File>> {
+    Ok(BufReader::new(File::open(path).map_err(|e| Error::FileIoError(e, path.to_path_buf()))?))
+}
+
+fn read_file<P: AsRef<std::path::Path>>(path: P) -> Result<String> {
+    std::fs::read_to_string(path.as_ref()).map_err(|e| Error::FileIoError(e, path.as_ref().to_path_buf()))
+}
+
+fn read_binary<P: AsRef<std::path::Path>>(path: P) -> Result<Vec<u8>> {
+    std::fs::read(path.as_ref()).map_err(|e| Error::FileIoError(e, path.as_ref().to_path_buf()))
+}
+
+fn decode_line<'a, F: FnMut(&'a str, &'a str) -> Result<()>>(
+    path: &std::path::Path,
+    line: &'a str,
+    func: &mut F,
+) -> Result<()> {
+    if !line.is_empty() {
+        let (key, value) = line.split_once('=').ok_or_else(|| Error::SyntaxError(path.to_path_buf()))?;
+        func(key, value)?;
+    }
+    Ok(())
+}
+
+pub fn read_txt<F: FnMut(&str, &str) -> Result<()>>(path: &std::path::Path, mut func: F) -> Result<()> {
+    let f = open_file(path)?;
+    for line in f.lines() {
+        decode_line(path, &line?, &mut func)?;
+    }
+    Ok(())
+}
+
+/// Parses a `tree.yyd`, where each line is a folder (`+`) or an asset (`|`) indented with a tab per level.
+/// Returns None if the syntax is wrong.
+pub fn parse_tree(text: &str) -> Option<Vec<TreeNode>> {
+    // stack of folders that are still open, the root being at the bottom
+    let mut stack: Vec<(String, Vec<TreeNode>)> = vec![(String::new(), Vec::new())];
+    for line in text.lines() {
+        if line.is_empty() {
+            continue
+        }
+        let trimmed = line.trim_start();
+        let level = line.len() - trimmed.len();
+        while stack.len() > level + 1 {
+            let (name, children) = stack.pop().unwrap();
+            stack.last_mut().unwrap().1.push(TreeNode::Folder(name, children));
+        }
+        let name = &trimmed[1..];
+        match trimmed.chars().next() {
+            Some('+') => stack.push((name.to_string(), Vec::new())),
+            Some('|') => stack.last_mut().unwrap().1.push(TreeNode::Asset(name.to_string())),
+            _ => return None,
+        }
+    }
+    while stack.len() > 1 {
+        let (name, children) = stack.pop().unwrap();
+        stack.last_mut().unwrap().1.push(TreeNode::Folder(name, children));
+    }
+    stack.pop().map(|(_, nodes)| nodes)
+}
+
+pub fn read_resource_tree(
+    type_name: &'static str,
+    names: &HashMap<String, usize>,
+    path: &mut PathBuf,
+) -> Result<Vec<TreeNode>> {
+    fn check_names(nodes: &[TreeNode], type_name: &'static str, names: &HashMap<String, usize>) -> Result<()> {
+        for node in nodes {
+            match node {
+                TreeNode::Folder(_, children) => check_names(children, type_name, names)?,
+                TreeNode::Asset(name) if !names.contains_key(name) => {
+                    return Err(Error::AssetNotFound(
+                        name.to_string(),
+                        &type_name[..type_name.len() - 1],
+                        "resource tree".to_string(),
+                    ))
+                },
+                TreeNode::Asset(_) => (),
+            }
+        }
+        Ok(())
+    }
+    if names.is_empty() {
+        return Ok(Vec::new())
+    }
+    path.push(type_name);
+    path.push("tree.yyd");
+    let tree = parse_tree(&read_file(&path)?).ok_or_else(|| Error::SyntaxError(path.to_path_buf()))?;
+    check_names(&tree, type_name, names)?;
+    path.pop();
+    path.pop();
+    Ok(tree)
+}
+
+fn load_triggers(maps: &AssetMaps, path: &mut PathBuf) -> Result<Vec<Option<Trigger>>> {
+    path.push("triggers");
+    let mut triggers = Vec::with_capacity(maps.triggers.index.len());
+    for name in &maps.triggers.index {
+        if name.is_empty() {
+            triggers.push(None);
+            continue
+        }
+        let mut trig = Trigger { name: name.clone(), ..Default::default() };
+        path.push(name);
+        path.set_extension("txt");
+        read_txt(path, |k, v| {
+            match k {
+                "constant" => trig.constant_name = v.to_string(),
+                "kind" => trig.kind = v.parse()?,
+                _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+            }
+            Ok(())
+        })?;
+        path.set_extension("gml");
+        trig.condition = load_gml(&read_file(&path)?);
+        path.pop();
+        triggers.push(Some(trig));
+    }
+    path.pop();
+    Ok(triggers)
+}
+
+fn verify_path(path: &std::path::Path) -> Result<()> {
+    if path.exists() {
+        Ok(())
+    } else {
+        Err(Error::FileIoError(std::io::Error::new(std::io::ErrorKind::NotFound, "file not found"), path.to_path_buf()))
+    }
+}
+
+fn load_sound(path: &mut PathBuf, _asset_maps: &AssetMaps) -> Result<Sound> {
+    let mut snd = Sound::default();
+    path.set_extension("txt");
+    let mut exists = false;
+    read_txt(path, |k, v| {
+        match k {
+            "extension" => snd.extension = v.to_string(),
+            "source" => snd.source = v.to_string(),
+            "exists" => exists = v.parse::<u8>()? != 0,
+            "kind" => snd.kind = v.parse()?,
+            "effects" => snd.effects = v.parse()?,
+            "volume" => snd.volume = v.parse()?,
+            "pan" => snd.pan = v.parse()?,
+            "preload" => snd.preload = v.parse::<u8>()? != 0,
+            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    if exists {
+        path.set_extension(snd.extension.trim_matches('.'));
+        verify_path(path)?;
+        snd.data = Some(read_binary(&path)?);
+    }
+    Ok(snd)
+}
+
+pub fn load_frame(path: &std::path::Path) -> Result<Frame> {
+    use png::{BitDepth, ColorType, Decoder, Transformations};
+    let err = |e| Error::PngDecodeError(path.to_path_buf(), e);
+    // no open_file because png uses BufReader internally
+    let mut decoder = Decoder::new(File::open(path).map_err(|e| Error::FileIoError(e, path.to_path_buf()))?);
+    decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(err)?;
+    let info = reader.info();
+    let (width, height) = (info.width, info.height);
+    // do this calculation myself in case the png is a weird format
+    let line_size = width as usize * 4;
+    let mut data = vec![0u8; line_size * height as usize];
+    let too_soon = || Error::Other(format!("decoding ended too soon for image {}", path.to_string_lossy()));
+    match (info.bit_depth, info.color_type) {
+        (BitDepth::Eight, ColorType::Rgba) => {
+            // this should be the only one that actually gets used
+            // but i'll allow other formats too just to be nice
+            reader.next_frame(&mut data).map_err(err)?;
+            // RGBA8 -> BGRA8
+            data.par_chunks_exact_mut(4).for_each(|px| px.swap(0, 2));
+        },
+        (BitDepth::Eight, ColorType::Rgb) => {
+            for dst_row in data.chunks_exact_mut(line_size) {
+                let src_row = reader.next_row().map_err(err)?.ok_or_else(too_soon)?;
+                // RGB8 -> BGR8
+                for (dst, src) in dst_row.chunks_exact_mut(4).zip(src_row.data().chunks_exact(3)) {
+                    dst[0] = src[2];
+                    dst[1] = src[1];
+                    dst[2] = src[0];
+                    dst[3] = 255;
+                }
+            }
+        },
+        (BitDepth::Eight, ColorType::Grayscale) => {
+            for dst_row in data.chunks_exact_mut(line_size) {
+                let src_row = reader.next_row().map_err(err)?.ok_or_else(too_soon)?;
+                for (dst, &src) in dst_row.chunks_exact_mut(4).zip(src_row.data()) {
+                    dst[0..3].fill(src);
+                    dst[3] = 255;
+                }
+            }
+        },
+        (BitDepth::Eight, ColorType::GrayscaleAlpha) => {
+            for dst_row in data.chunks_exact_mut(line_size) {
+                let src_row = reader.next_row().map_err(err)?.ok_or_else(too_soon)?;
+                for (dst, src) in dst_row.chunks_exact_mut(4).zip(src_row.data().chunks_exact(2)) {
+                    dst[0..3].fill(src[0]);
+                    dst[3] = src[1];
+                }
+            }
+        },
+        (depth, coltype) => {
+            // the above should cover all valid formats
+            // see https://www.w3.org/TR/PNG-Chunks.html
+            return Err(Error::Other(format!(
+                "couldn't understand format {:?}/{:?} for image {}",
+                depth,
+                coltype,
+                path.to_string_lossy()
+            )))
+        },
+    }
+    Ok(Frame { width, height, data })
+}
+
+fn load_background(path: &mut PathBuf, _asset_maps: &AssetMaps) -> Result<Background> {
+    let mut bg = Background::default();
+    path.set_extension("txt");
+    let mut bg_exists = false;
+    read_txt(path, |k, v| {
+        match k {
+            "exists" => bg_exists = v.parse::<u8>()? != 0,
+            "tileset" => bg.is_tileset = v.parse::<u8>()? != 0,
+            "tile_width" => bg.tile_width = v.parse()?,
+            "tile_height" => bg.tile_height = v.parse()?,
+            "tile_hoffset" => bg.h_offset = v.parse()?,
+            "tile_voffset" => bg.v_offset = v.parse()?,
+            "tile_hsep" => bg.h_sep = v.parse()?,
+            "tile_vsep" => bg.v_sep = v.parse()?,
+            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    if bg_exists {
+        path.set_extension("png");
+        bg.frame = load_frame(path)?;
+    }
+    Ok(bg)
+}
+
+fn load_sprite(path: &mut PathBuf, _asset_maps: &AssetMaps) -> Result<Sprite> {
+    let mut sp = Sprite::default();
+    let mut frame_count = 0usize;
+    path.push("sprite.txt");
+    read_txt(path, |k, v| {
+        match k {
+            "frames" => frame_count = v.parse()?,
+            "origin_x" => sp.origin_x = v.parse()?,
+            "origin_y" => sp.origin_y = v.parse()?,
+            "collision_shape" => sp.collision_shape = v.parse()?,
+            "alpha_tolerance" => sp.alpha_tolerance = v.parse()?,
+            "per_frame_colliders" => sp.per_frame_colliders = v.parse::<u8>()? != 0,
+            "bbox_type" => sp.bbox_type = v.parse()?,
+            "bbox_left" => sp.bbox_left = v.parse()?,
+            "bbox_bottom" => sp.bbox_bottom = v.parse()?,
+            "bbox_right" => sp.bbox_right = v.parse()?,
+            "bbox_top" => sp.bbox_top = v.parse()?,
+            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    path.pop();
+    sp.frames = (0..frame_count).map(|i| load_frame(&path.join(format!("{}.png", i)))).collect::<Result<_>>()?;
+    Ok(sp)
+}
+
+fn load_script(path: &mut PathBuf, _asset_maps: &AssetMaps) -> Result<Script> {
+    path.set_extension("gml");
+    Ok(Script { source: load_gml(&read_file(path)?) })
+}
+
+fn load_font(path: &mut PathBuf, _asset_maps: &AssetMaps) -> Result<Font> {
+    let mut f = Font::default();
+    path.set_extension("txt");
+    read_txt(path, |k, v| {
+        match k {
+            "name" => f.sys_name = v.to_string(),
+            "size" => f.size = v.parse()?,
+            "bold" => f.bold = v.parse::<u8>()? != 0,
+            "italic" => f.italic = v.parse::<u8>()? != 0,
+            "charset" => f.charset = v.parse()?,
+            "aa_level" => f.aa_level = v.parse()?, // DOES NOT CORRESPOND TO .GMK OR .EXE
+            "range_start" => f.range_start = v.parse()?,
+            "range_end" => f.range_end = v.parse()?,
+            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    // the ide doesn't know about these, so they're found by name rather than listed in the .txt
+    for extension in ["ttf", "otf"] {
+        path.set_extension(extension);
+        if path.is_file() {
+            f.file = Some(FontFile { extension: extension.to_string(), data: read_binary(path)? });
+            break
+        }
+    }
+    Ok(f)
+}
+
+// action kinds aren't known without the action libraries, so this only checks what can be checked from the file
+fn load_event(path: &std::path::Path, event_code: &str, asset_maps: &AssetMaps) -> Result<Event> {
+    let object_name = path.file_stem().map(OsStr::to_string_lossy).unwrap_or_default();
+    let mut event = Event::default();
+    for action_code in event_code.split(ACTION_TOKEN) {
+        if action_code.trim().is_empty() {
+            continue
+        }
+        let (params, code) = action_code.split_once("*/").ok_or_else(|| Error::SyntaxError(path.to_path_buf()))?;
+        let mut action = Action::default();
+        let mut lib_id_set = false;
+        let mut act_id_set = false;
+        for line in params.lines() {
+            decode_line(path, line, &mut |k, v| {
+                match k {
+                    "lib_id" => {
+                        action.lib_id = v.parse()?;
+                        if lib_id_set {
+                            return Err(Error::SyntaxError(path.to_path_buf()))
+                        }
+                        lib_id_set = true;
+                    },
+                    "action_id" => {
+                        action.id = v.parse()?;
+                        if !lib_id_set || act_id_set {
+                            return Err(Error::SyntaxError(path.to_path_buf()))
+                        }
+                        act_id_set = true;
+                    },
+                    "relative" => action.is_relative = Some(v.parse::<u8>()? != 0),
+                    "applies_to" => {
+                        if !matches!(v, "other" | "self" | "") && !asset_maps.objects.map.contains_key(v) {
+                            return Err(Error::AssetNotFound(
+                                v.to_string(),
+                                "object",
+                                format!("object {object_name} code action applies_to"),
+                            ))
+                        }
+                        action.applies_to = Some(v.to_string());
+                    },
+                    "invert" => match &mut action.params {
+                        ActionParams::Normal { invert, .. } => *invert = v.parse::<u8>()? != 0,
+                        params => *params = ActionParams::Normal { invert: v.parse::<u8>()? != 0, args: Vec::new() },
+                    },
+                    "repeats" => action.params = ActionParams::Repeat(v.to_string()),
+                    "var_name" => match &mut action.params {
+                        ActionParams::Variable { name, .. } => *name = v.to_string(),
+                        params => *params = ActionParams::Variable { name: v.to_string(), value: String::new() },
+                    },
+                    "var_value" => match &mut action.params {
+                        ActionParams::Variable { value, .. } => *value = v.to_string(),
+                        params => *params = ActionParams::Variable { name: String::new(), value: v.to_string() },
+                    },
+                    "arg0" | "arg1" | "arg2" | "arg3" | "arg4" | "arg5" | "arg6" | "arg7" => {
+                        if !act_id_set {
+                            return Err(Error::SyntaxError(path.to_path_buf()))
+                        }
+                        let i = k.chars().last().unwrap().to_digit(8).unwrap() as usize;
+                        if !matches!(action.params, ActionParams::Normal { .. }) {
+                            action.params = ActionParams::Normal { invert: false, args: Vec::new() };
+                        }
+                        if let ActionParams::Normal { args, .. } = &mut action.params {
+                            if args.len() <= i {
+                                args.resize(i + 1, String::new());
+                            }
+                            // resource names never contain anything that would get undelimited
+                            args[i] = undelimit(v);
+                        }
+                    },
+                    _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+                }
+                Ok(())
+            })?;
+        }
+        // first character will always be a newline because it doesn't cut the newline when searching for */
+        // so skip it
+        let code = if let Some(code) = code.strip_prefix('\n') {
+            code
+        } else if let Some(code) = code.strip_prefix("\r\n") {
+            code
+        } else {
+            code
+        };
+        action.code = load_gml(&unescape_defines(code));
+        event.actions.push(action);
+    }
+    Ok(event)
+}
+
+fn load_object(path: &mut PathBuf, asset_maps: &AssetMaps) -> Result<Object> {
+    path.set_extension("txt");
+    let object_name = path.file_stem().map(OsStr::to_string_lossy).unwrap_or_default().into_owned();
+    let mut obj = Object::default();
+    let sprite_map = &asset_maps.sprites.map;
+    let object_map = &asset_maps.objects.map;
+    let trigger_map = &asset_maps.triggers.map;
+    read_txt(path, |k, v| {
+        match k {
+            "sprite" => {
+                if !v.is_empty() && !sprite_map.contains_key(v) {
+                    return Err(Error::AssetNotFound(v.to_string(), "sprite", format!("object {object_name} sprite")))
+                }
+                obj.sprite = v.to_string();
+            },
+            "visible" => obj.visible = v.parse::<u8>()? != 0,
+            "solid" => obj.solid = v.parse::<u8>()? != 0,
+            "persistent" => obj.persistent = v.parse::<u8>()? != 0,
+            "depth" => obj.depth = v.parse()?,
+            "parent" => {
+                if !v.is_empty() && !object_map.contains_key(v) {
+                    return Err(Error::AssetNotFound(v.to_string(), "object", format!("object {object_name} parent")))
+                }
+                obj.parent = v.to_string();
+            },
+            "mask" => {
+                if !v.is_empty() && !sprite_map.contains_key(v) {
+                    return Err(Error::AssetNotFound(v.to_string(), "sprite", format!("object {object_name} mask")))
+                }
+                obj.mask = v.to_string();
+            },
+            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    path.set_extension("gml");
+    let code = read_file(&path)?;
+    for event in code.trim_start_matches("#define ").split("\n#define ") {
+        if event.trim().is_empty() {
+            continue
+        }
+        let err = || Error::SyntaxError(path.to_path_buf());
+        let (name, actions) = event.split_once('\n').ok_or_else(err)?;
+        let (ev_type_s, ev_numb_s) = name.trim().split_once('_').ok_or_else(err)?;
+        let ev_type = events::EVENT_NAMES.iter().position(|&s| s == ev_type_s).ok_or_else(err)?;
+        let ev_numb = match ev_type {
+            events::EV_COLLISION if object_map.contains_key(ev_numb_s) => EventNumber::Name(ev_numb_s.to_string()),
+            events::EV_TRIGGER if trigger_map.contains_key(ev_numb_s) => EventNumber::Name(ev_numb_s.to_string()),
+            events::EV_COLLISION | events::EV_TRIGGER => return Err(err()),
+            _ => EventNumber::Number(ev_numb_s.parse()?),
+        };
+        let event = load_event(path, actions, asset_maps)?;
+        // the ide merges repeated events, so do the same here
+        match obj.events[ev_type].iter_mut().find(|(n, _)| *n == ev_numb) {
+            Some((_, existing)) => existing.actions.extend(event.actions),
+            None => obj.events[ev_type].push((ev_numb, event)),
+        }
+    }
+    Ok(obj)
+}
+
+fn load_timeline(path: &mut PathBuf, asset_maps: &AssetMaps) -> Result<Timeline> {
+    let mut tl = Timeline::default();
+    path.set_extension("gml");
+    let code = read_file(&path)?;
+    for code in code.trim_start_matches("#define ").split("\n#define ") {
+        if code.trim().is_empty() {
+            continue
+        }
+        let (name, actions) = match code.split_once('\n') {
+            Some(tuple) => tuple,
+            None if code.as_bytes().iter().all(u8::is_ascii_digit) => (code, ""), // #define 1\n#define 2
+            None => return Err(Error::SyntaxError(path.to_path_buf())),
+        };
+        let time = name.trim().parse()?;
+        tl.moments.push((time, load_event(path, actions, asset_maps)?));
+    }
+    Ok(tl)
+}
+
+pub fn load_path(file_path: &mut PathBuf, asset_maps: &AssetMaps) -> Result<Path> {
+    let mut path = Path::default();
+    file_path.push("path.txt");
+    let path_name =
+        file_path.parent().and_then(std::path::Path::file_name).map(OsStr::to_string_lossy).unwrap_or_default();
+    read_txt(file_path, |k, v| {
+        match k {
+            "connection" => path.connection = v.parse()?,
+            "closed" => path.closed = v.parse::<u8>()? != 0,
+            "precision" => path.precision = v.parse()?,
+            "background" => {
+                if !v.is_empty() && !asset_maps.rooms.map.contains_key(v) {
+                    return Err(Error::AssetNotFound(v.to_string(), "room", format!("path {path_name} room background")))
+                }
+                path.background = v.to_string();
+            },
+            "snap_x" => path.snap_x = v.parse()?,
+            "snap_y" => path.snap_y = v.parse()?,
+            _ => return Err(Error::UnknownKey(file_path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    file_path.pop();
+    file_path.push("points.txt");
+    let points_txt = read_file(&file_path)?;
+    path.points = points_txt
+        .par_lines()
+        .map(|line| {
+            let err = || Error::SyntaxError(file_path.to_path_buf());
+            let mut iter = split_fields(line).ok_or_else(err)?.into_iter();
+            let point = PathPoint {
+                x: iter.next().ok_or_else(err)?.parse()?,
+                y: iter.next().ok_or_else(err)?.parse()?,
+                speed: iter.next().ok_or_else(err)?.parse()?,
+            };
+            if iter.next().is_some() {
+                return Err(err())
+            }
+            Ok(point)
+        })
+        .collect::<Result<_>>()?;
+    file_path.pop();
+    Ok(path)
+}
+
+fn load_instances(path: &mut PathBuf, objs: &HashMap<String, usize>) -> Result<Vec<Instance>> {
+    path.push("instances.txt");
+    let instances_txt = read_file(&path)?;
+    let inst_path = path.to_path_buf(); // save instances.txt path for errors
+    path.pop();
+    let room_name = path.file_name().map(OsStr::to_string_lossy).unwrap_or_default();
+    let err = || Error::SyntaxError(inst_path.to_path_buf());
+    let mut names = HashSet::new();
+    let mut instances = Vec::new();
+    for line in instances_txt.lines().filter(|s| !s.is_empty()) {
+        let mut instance = Instance::default();
+        let mut iter = split_fields(line).ok_or_else(err)?.into_iter();
+        instance.object = match &*iter.next().ok_or_else(err)? {
+            "" => String::new(),
+            obj if objs.contains_key(obj) => obj.to_string(),
+            obj => return Err(Error::AssetNotFound(obj.to_string(), "object", format!("room {room_name} instances"))),
+        };
+        instance.x = iter.next().ok_or_else(err)?.parse()?;
+        instance.y = iter.next().ok_or_else(err)?.parse()?;
+        let code_hash = iter.next().ok_or_else(err)?;
+        if !code_hash.is_empty() {
+            // if the name is taken, leave it unassigned so a new one gets generated on save
+            let name = u32::from_str_radix(&code_hash, 16)?;
+            if names.insert(name) {
+                instance.name = name;
+            }
+        }
+        instance.locked = iter.next().ok_or_else(err)?.parse::<u8>()? != 0;
+        if let Some(s) = iter.next() {
+            instance.xscale = s.parse()?;
+        }
+        if let Some(s) = iter.next() {
+            instance.yscale = s.parse()?;
+        }
+        if let Some(s) = iter.next() {
+            instance.blend = s.parse()?;
+        }
+        if let Some(s) = iter.next() {
+            instance.angle = s.parse()?;
+        }
+        // use the name from the file, not the generated one if relevant
+        let has_code = if let Some(s) = iter.next() { s.parse::<u8>()? != 0 } else { !code_hash.is_empty() };
+        if has_code {
+            let mut path = path.join(&*code_hash);
+            path.set_extension("gml");
+            instance.creation_code = load_gml(&read_file(&path)?);
+        }
+        // only there if the project preserves ids
+        if let Some(s) = iter.next() {
+            instance.id = s.parse()?;
+        }
+        instances.push(instance);
+    }
+    Ok(instances)
+}
+
+fn load_tiles(path: &mut PathBuf, bgs: &HashMap<String, usize>) -> Result<Vec<Tile>> {
+    let mut tiles = Vec::new();
+    path.push("layers.txt");
+    let f = open_file(path)?;
+    path.pop();
+    for line in f.lines() {
+        let line = line?;
+        if line.is_empty() {
+            continue
+        }
+        let depth = line.parse()?;
+        path.push(line);
+        path.set_extension("txt");
+        let room_name =
+            path.parent().and_then(std::path::Path::file_name).map(OsStr::to_string_lossy).unwrap_or_default();
+        let layer_txt = read_file(&path)?;
+        let err = || Error::SyntaxError(path.to_path_buf());
+        let layer_tiles = layer_txt
+            .par_lines()
+            .filter(|s| !s.is_empty())
+            .map(|tile| {
+                let mut iter = split_fields(tile).ok_or_else(err)?.into_iter();
+                let mut t = Tile {
+                    source_bg: match &*iter.next().ok_or_else(err)? {
+                        "" => String::new(),
+                        bg if bgs.contains_key(bg) => bg.to_string(),
+                        bg => {
+                            return Err(Error::AssetNotFound(
+                                bg.to_string(),
+                                "background",
+                                format!("room {room_name} tiles"),
+                            ))
+                        },
+                    },
+                    x: iter.next().ok_or_else(err)?.parse()?,
+                    y: iter.next().ok_or_else(err)?.parse()?,
+                    u: iter.next().ok_or_else(err)?.parse()?,
+                    v: iter.next().ok_or_else(err)?.parse()?,
+                    width: iter.next().ok_or_else(err)?.parse()?,
+                    height: iter.next().ok_or_else(err)?.parse()?,
+                    locked: iter.next().ok_or_else(err)?.parse::<u8>()? != 0,
+                    depth,
+                    ..Default::default()
+                };
+                if let Some(s) = iter.next() {
+                    t.xscale = s.parse()?;
+                }
+                if let Some(s) = iter.next() {
+                    t.yscale = s.parse()?;
+                }
+                if let Some(s) = iter.next() {
+                    t.blend = s.parse()?;
+                }
+                if let Some(s) = iter.next() {
+                    t.id = s.parse()?;
+                }
+                if iter.next().is_some() {
+                    return Err(err())
+                }
+                Ok(t)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        tiles.extend(layer_tiles);
+        path.pop();
+    }
+    Ok(tiles)
+}
+
+pub fn load_room(path: &mut PathBuf, asset_maps: &AssetMaps) -> Result<Room> {
+    let mut room = Room::default();
+    path.push("room.txt");
+    let room_name = path.parent().and_then(std::path::Path::file_name).map(OsStr::to_string_lossy).unwrap_or_default();
+    read_txt(path, |k, v| {
+        match k {
+            "caption" => room.caption = v.to_string(),
+            "width" => room.width = v.parse()?,
+            "height" => room.height = v.parse()?,
+            "snap_x" => room.snap_x = v.parse()?,
+            "snap_y" => room.snap_y = v.parse()?,
+            "isometric" => room.isometric = v.parse::<u8>()? != 0,
+            "roomspeed" => room.speed = v.parse()?,
+            "roompersistent" => room.persistent = v.parse::<u8>()? != 0,
+            "bg_color" => room.bg_colour = v.parse()?,
+            "clear_screen" => room.clear_screen = v.parse::<u8>()? != 0,
+            "clear_view" => room.clear_view = v.parse::<u8>()? != 0,
+            // 8 backgrounds/views
+            k if k.chars().last().map(|c| c.is_digit(8)) == Some(true) => {
+                let i = k.chars().last().and_then(|c| c.to_digit(8)).unwrap() as usize;
+                match &k[..k.len() - 1] {
+                    "bg_visible" => room.backgrounds[i].visible_on_start = v.parse::<u8>()? != 0,
+                    "bg_is_foreground" => room.backgrounds[i].is_foreground = v.parse::<u8>()? != 0,
+                    "bg_source" => {
+                        if !v.is_empty() && !asset_maps.backgrounds.map.contains_key(v) {
+                            return Err(Error::AssetNotFound(
+                                v.to_string(),
+                                "background",
+                                format!("room {room_name} backgrounds"),
+                            ))
+                        }
+                        room.backgrounds[i].source_bg = v.to_string();
+                    },
+                    "bg_xoffset" => room.backgrounds[i].xoffset = v.parse()?,
+                    "bg_yoffset" => room.backgrounds[i].yoffset = v.parse()?,
+                    "bg_tile_h" => room.backgrounds[i].tile_horz = v.parse::<u8>()? != 0,
+                    "bg_tile_v" => room.backgrounds[i].tile_vert = v.parse::<u8>()? != 0,
+                    "bg_hspeed" => room.backgrounds[i].hspeed = v.parse()?,
+                    "bg_vspeed" => room.backgrounds[i].vspeed = v.parse()?,
+                    "bg_stretch" => room.backgrounds[i].stretch = v.parse::<u8>()? != 0,
+                    "view_visible" => room.views[i].visible = v.parse::<u8>()? != 0,
+                    "view_xview" => room.views[i].source_x = v.parse()?,
+                    "view_yview" => room.views[i].source_y = v.parse()?,
+                    "view_wview" => room.views[i].source_w = v.parse()?,
+                    "view_hview" => room.views[i].source_h = v.parse()?,
+                    "view_xport" => room.views[i].port_x = v.parse()?,
+                    "view_yport" => room.views[i].port_y = v.parse()?,
+                    "view_wport" => room.views[i].port_w = v.parse()?,
+                    "view_hport" => room.views[i].port_h = v.parse()?,
+                    "view_fol_hbord" => room.views[i].following_hborder = v.parse()?,
+                    "view_fol_vbord" => room.views[i].following_vborder = v.parse()?,
+                    "view_fol_hspeed" => {
+                        room.views[i].following_hspeed =
+                            if let Ok(s) = v.parse() { s } else { v.parse::<u32>()? as i32 };
+                    },
+                    "view_fol_vspeed" => {
+                        room.views[i].following_vspeed =
+                            if let Ok(s) = v.parse() { s } else { v.parse::<u32>()? as i32 };
+                    },
+                    "view_fol_target" => {
+                        if !v.is_empty() && !asset_maps.objects.map.contains_key(v) {
+                            return Err(Error::AssetNotFound(
+                                v.to_string(),
+                                "object",
+                                format!("room {room_name} view targets"),
+                            ))
+                        }
+                        room.views[i].following_target = v.to_string();
+                    },
+                    _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+                }
+            },
+            "views_enabled" => room.views_enabled = v.parse::<u8>()? != 0,
+            // views
+            "remember" => room.remember_room_editor_info = v.parse::<u8>()? != 0,
+            "editor_width" => room.editor_width = v.parse()?,
+            "editor_height" => room.editor_height = v.parse()?,
+            "show_grid" => room.show_grid = v.parse::<u8>()? != 0,
+            "show_objects" => room.show_objects = v.parse::<u8>()? != 0,
+            "show_tiles" => room.show_tiles = v.parse::<u8>()? != 0,
+            "show_backgrounds" => room.show_backgrounds = v.parse::<u8>()? != 0,
+            "show_foregrounds" => room.show_foregrounds = v.parse::<u8>()? != 0,
+            "show_views" => room.show_views = v.parse::<u8>()? != 0,
+            "delete_underlying_objects" => room.delete_underlying_objects = v.parse::<u8>()? != 0,
+            "delete_underlying_tiles" => room.delete_underlying_tiles = v.parse::<u8>()? != 0,
+            "tab" => room.tab = v.parse()?, // i still don't know wtf this is
+            "editor_x" => room.x_position_scroll = v.parse()?,
+            "editor_y" => room.y_position_scroll = v.parse()?,
+            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    path.pop();
+    path.push("code.gml");
+    room.creation_code = load_gml(&read_file(&path)?);
+    path.pop();
+    room.instances = load_instances(path, &asset_maps.objects.map)?;
+    room.tiles = load_tiles(path, &asset_maps.backgrounds.map)?;
+    Ok(room)
+}
+
+fn load_constants(path: &mut PathBuf) -> Result<Vec<(String, String)>> {
+    path.push("constants.txt");
+    let s = read_file(&path)?;
+    let mut constants = Vec::new();
+    for line in s.lines() {
+        decode_line(path, line, &mut |name, value| {
+            constants.push((name.to_string(), value.to_string()));
+            Ok(())
+        })?;
+    }
+    path.pop();
+    Ok(constants)
+}
+
+fn load_included_files(path: &mut PathBuf) -> Result<Vec<IncludedFile>> {
+    path.push("datafiles");
+    path.push("index.yyd");
+    let index = read_file(&path)?;
+    path.pop();
+    let files = index
+        .lines()
+        .map(|fname| {
+            let mut file = IncludedFile { file_name: fname.to_string(), ..Default::default() };
+            let mut path = path.join(fname.to_string() + ".txt");
+            read_txt(&path, |k, v| {
+                match k {
+                    "store" => file.stored_in_gmk = v.parse::<u8>()? != 0,
+                    "free" => file.free_memory = v.parse::<u8>()? != 0,
+                    "overwrite" => file.overwrite_file = v.parse::<u8>()? != 0,
+                    "remove" => file.remove_at_end = v.parse::<u8>()? != 0,
+                    "export" => file.export_setting = v.parse()?,
+                    "export_folder" => file.export_custom_folder = v.to_string(),
+                    _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+                }
+                Ok(())
+            })?;
+            path.pop();
+            path.push("include");
+            path.push(fname);
+            std::fs::metadata(&path).map_err(|e| Error::FileIoError(e, path.to_path_buf()))?;
+            if file.stored_in_gmk {
+                file.data = Some(read_binary(&path)?);
+            }
+            file.source_path = path;
+            Ok(file)
+        })
+        .collect::<Result<_>>()?;
+    path.pop();
+    Ok(files)
+}
+
+fn load_game_information(path: &mut PathBuf) -> Result<GameInformation> {
+    let mut info = GameInformation::default();
+    path.push("game_information.txt");
+    read_txt(path, |k, v| {
+        match k {
+            "color" => info.colour = v.parse()?,
+            "new_window" => info.new_window = v.parse::<u8>()? != 0,
+            "caption" => info.caption = v.to_string(),
+            "left" => info.left = v.parse()?,
+            "top" => info.top = v.parse()?,
+            "width" => info.width = v.parse()?,
+            "height" => info.height = v.parse()?,
+            "border" => info.border = v.parse::<u8>()? != 0,
+            "resizable" => info.resizable = v.parse::<u8>()? != 0,
+            "window_on_top" => info.window_on_top = v.parse::<u8>()? != 0,
+            "freeze_game" => info.freeze_game = v.parse::<u8>()? != 0,
+            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    path.set_extension("rtf");
+    verify_path(path)?;
+    info.rtf = read_binary(&path)?;
+    path.pop();
+    Ok(info)
+}
+
+fn load_settings(project: &mut Project, path: &mut PathBuf) -> Result<()> {
+    path.push("settings");
+    project.constants = load_constants(path)?;
+    let settings = &mut project.settings;
+    let mut custom_load_bar = false;
+    let mut bar_bg = false;
+    let mut bar_fg = false;
+    let mut custom_load_bg = false;
+    path.push("settings.txt");
+    read_txt(path, |k, v| {
+        match k {
+            "fullscreen" => settings.fullscreen = v.parse::<u8>()? != 0,
+            "interpolate_pixels" => settings.interpolate_pixels = v.parse::<u8>()? != 0,
+            "dont_draw_border" => settings.dont_draw_border = v.parse::<u8>()? != 0,
+            "display_cursor" => settings.display_cursor = v.parse::<u8>()? != 0,
+            "scaling" => settings.scaling = if let Ok(s) = v.parse() { s } else { v.parse::<u32>()? as i32 },
+            "allow_resize" => settings.allow_resize = v.parse::<u8>()? != 0,
+            "window_on_top" => settings.window_on_top = v.parse::<u8>()? != 0,
+            "clear_color" => settings.clear_colour = v.parse()?,
+            "set_resolution" => settings.set_resolution = v.parse::<u8>()? != 0,
+            "color_depth" => settings.colour_depth = v.parse()?,
+            "resolution" => settings.resolution = v.parse()?,
+            "frequency" => settings.frequency = v.parse()?,
+            "dont_show_buttons" => settings.dont_show_buttons = v.parse::<u8>()? != 0,
+            "vsync" => settings.vsync = v.parse::<u8>()? != 0,
+            "force_cpu_render" => (), // legacy, and saving didn't even work so
+            "swap_creation_events" => settings.swap_creation_events = v.parse::<u8>()? != 0,
+            "disable_screensaver" => settings.disable_screensaver = v.parse::<u8>()? != 0,
+            "f4_fullscreen_toggle" => settings.f4_fullscreen = v.parse::<u8>()? != 0,
+            "f1_help_menu" => settings.f1_help = v.parse::<u8>()? != 0,
+            "esc_close_game" => settings.esc_close = v.parse::<u8>()? != 0,
+            "f5_save_f6_load" => settings.f5_save_f6_load = v.parse::<u8>()? != 0,
+            "f9_screenshot" => settings.f9_screenshot = v.parse::<u8>()? != 0,
+            "treat_close_as_esc" => settings.treat_close_as_esc = v.parse::<u8>()? != 0,
+            "priority" => settings.priority = v.parse()?,
+            "freeze_on_lose_focus" => settings.freeze_on_lose_focus = v.parse::<u8>()? != 0,
+            "custom_loader" => custom_load_bg = v.parse::<u8>()? != 0,
+            "custom_bar" => {
+                settings.loading_bar = v.parse()?;
+                custom_load_bar = settings.loading_bar == 2;
+            },
+            "bar_has_bg" => bar_bg = v.parse::<u8>()? != 0,
+            "bar_has_fg" => bar_fg = v.parse::<u8>()? != 0,
+            "transparent" => settings.loading_transparent = v.parse::<u8>()? != 0,
+            "translucency" => settings.loading_translucency = v.parse()?,
+            "scale_progress_bar" => settings.loading_progress_bar_scale = v.parse::<u8>()? != 0,
+            "show_error_messages" => settings.show_error_messages = v.parse::<u8>()? != 0,
+            "log_errors" => settings.log_errors = v.parse::<u8>()? != 0,
+            "always_abort" => settings.always_abort = v.parse::<u8>()? != 0,
+            "zero_uninitialized_vars" => settings.zero_uninitialized_vars = v.parse::<u8>()? != 0,
+            "error_on_uninitialized_args" => settings.error_on_uninitialized_args = v.parse::<u8>()? != 0,
+            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    path.pop();
+    if custom_load_bar {
+        if bar_bg {
+            path.push("back.bmp");
+            verify_path(path)?;
+            settings.loading_background = Some(read_binary(&path)?);
+            path.pop();
+        }
+        if bar_fg {
+            path.push("front.bmp");
+            verify_path(path)?;
+            settings.loading_foreground = Some(read_binary(&path)?);
+            path.pop();
+        }
+    }
+    if custom_load_bg {
+        path.push("loader.bmp");
+        settings.custom_load_image = Some(read_binary(&path)?);
+        path.pop();
+    }
+    path.push("icon.ico");
+    verify_path(path)?;
+    settings.icon = read_binary(&path)?;
+    path.pop();
+    {
+        path.push("extensions.txt");
+        let f = open_file(path)?;
+        for line in f.lines() {
+            let name = line?;
+            if !name.is_empty() {
+                project.extensions.push(name);
+            }
+        }
+        path.pop();
+    }
+    project.game_info = load_game_information(path)?;
+    path.pop();
+    Ok(())
+}
+
+fn load_index(name: &str, has_any: bool, path: &mut PathBuf) -> Result<Assets> {
+    if !has_any {
+        return Ok(Assets { index: Vec::new(), map: HashMap::new() })
+    }
+    path.push(name);
+    path.push("index.yyd");
+    let text = read_file(&path)?;
+    let index: Vec<_> = text.par_lines().map(String::from).collect();
+    let map = index.par_iter().enumerate().filter_map(|(i, s)| (!s.is_empty()).then(|| (s.to_string(), i))).collect();
+    path.pop();
+    path.pop();
+    Ok(Assets { index, map })
+}
+
+/// Reads an asset type's `timestamps.yyd`. Projects saved before it existed don't have one.
+pub fn read_timestamps(path: &std::path::Path) -> Result<HashMap<String, f64>> {
+    if !path.exists() {
+        return Ok(HashMap::new())
+    }
+    read_file(path)?
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            // names can have = in them, but timestamps can't
+            let (name, timestamp) = line.rsplit_once('=').ok_or_else(|| Error::SyntaxError(path.to_path_buf()))?;
+            Ok((name.to_string(), timestamp.parse()?))
+        })
+        .collect()
+}
+
+fn load_assets<T: Send>(
+    name: &'static str,
+    load_asset: fn(&mut PathBuf, &AssetMaps) -> Result<T>,
+    assets: &Assets,
+    path: &mut PathBuf,
+    asset_maps: &AssetMaps,
+) -> Result<AssetList<T>> {
+    path.push(name);
+    let loaded = assets
+        .index
+        .par_iter()
+        .map(|name| if name.is_empty() { Ok(None) } else { load_asset(&mut path.join(name), asset_maps).map(Some) })
+        .collect::<Result<Vec<_>>>()?;
+    let timestamps = read_timestamps(&path.join("timestamps.yyd"))?;
+    path.pop();
+    let timestamps = assets.index.iter().map(|name| timestamps.get(name).copied().unwrap_or(0.0)).collect();
+    let tree = read_resource_tree(name, &assets.map, path)?;
+    Ok(AssetList { assets: loaded, names: assets.index.clone(), timestamps, tree })
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn load_asset_maps(
+    path: &mut PathBuf,
+    has_triggers: bool,
+    has_sprites: bool,
+    has_sounds: bool,
+    has_backgrounds: bool,
+    has_paths: bool,
+    has_scripts: bool,
+    has_objects: bool,
+    has_fonts: bool,
+    has_timelines: bool,
+) -> Result<AssetMaps> {
+    Ok(AssetMaps {
+        triggers: load_index("triggers", has_triggers, path)?,
+        sprites: load_index("sprites", has_sprites, path)?,
+        sounds: load_index("sounds", has_sounds, path)?,
+        backgrounds: load_index("backgrounds", has_backgrounds, path)?,
+        paths: load_index("paths", has_paths, path)?,
+        scripts: load_index("scripts", has_scripts, path)?,
+        objects: load_index("objects", has_objects, path)?,
+        rooms: load_index("rooms", true, path)?,
+        fonts: load_index("fonts", has_fonts, path)?,
+        timelines: load_index("timelines", has_timelines, path)?,
+    })
+}
+
+/// Loads a project from the path of its .gm82 file.
+pub fn load_gmk(path: impl Into<PathBuf>) -> Result<Project> {
+    let mut path = path.into();
+    if let Some(dir) = path.parent() {
+        // a save that got interrupted after it was committed still needs to be finished
+        transaction::recover(dir)?;
+    }
+    let mut project = Project::default();
+    let mut has_backgrounds = true;
+    let mut has_datafiles = true;
+    let mut has_fonts = true;
+    let mut has_objects = true;
+    let mut has_paths = true;
+    let mut has_scripts = true;
+    let mut has_sounds = true;
+    let mut has_sprites = true;
+    let mut has_timelines = true;
+    let mut has_triggers = true;
+    let settings = &mut project.settings;
+    read_txt(&path, |k, v| {
+        match k {
+            "gm82_version" => {
+                let version = v.parse::<u8>()?;
+                if version > GM82_VERSION {
+                    return Err(Error::OldGM82)
+                }
+                project.escaped_utf16 = version >= 6;
+            },
+            "gameid" => project.game_id = v.parse()?,
+            "info_author" => settings.info_author = v.to_string(),
+            "info_version" => settings.info_version = v.to_string(),
+            "info_timestamp" => (), // legacy
+            "info_information" => settings.info_information = undelimit(v),
+            "exe_company" => settings.exe_company = v.to_string(),
+            "exe_product" => settings.exe_product = v.to_string(),
+            "exe_copyright" => settings.exe_copyright = v.to_string(),
+            "exe_description" => settings.exe_description = v.to_string(),
+            "exe_version" => {
+                let err = || Error::InvalidVersion(v.to_string());
+                let mut iter = v.split('.');
+                settings.version_major = iter.next().ok_or_else(err)?.parse()?;
+                settings.version_minor = iter.next().ok_or_else(err)?.parse()?;
+                settings.version_release = iter.next().ok_or_else(err)?.parse()?;
+                settings.version_build = iter.next().ok_or_else(err)?.parse()?;
+                if iter.next().is_some() {
+                    return Err(Error::InvalidVersion(v.to_string()))
+                }
+            },
+            "has_backgrounds" => has_backgrounds = v.parse::<u8>()? != 0,
+            "has_datafiles" => has_datafiles = v.parse::<u8>()? != 0,
+            "has_fonts" => has_fonts = v.parse::<u8>()? != 0,
+            "has_objects" => has_objects = v.parse::<u8>()? != 0,
+            "has_paths" => has_paths = v.parse::<u8>()? != 0,
+            "has_scripts" => has_scripts = v.parse::<u8>()? != 0,
+            "has_sounds" => has_sounds = v.parse::<u8>()? != 0,
+            "has_sprites" => has_sprites = v.parse::<u8>()? != 0,
+            "has_timelines" => has_timelines = v.parse::<u8>()? != 0,
+            "has_triggers" => has_triggers = v.parse::<u8>()? != 0,
+            "preserve_ids" => project.preserve_ids = v.parse::<u8>()? != 0,
+            "last_instance_id" | "last_tile_id" => (), // worked out from the ids when they're needed
+            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    path.pop();
+    load_settings(&mut project, &mut path)?;
+    let asset_maps = load_asset_maps(
+        &mut path,
+        has_triggers,
+        has_sprites,
+        has_sounds,
+        has_backgrounds,
+        has_paths,
+        has_scripts,
+        has_objects,
+        has_fonts,
+        has_timelines,
+    )?;
+    project.triggers = load_triggers(&asset_maps, &mut path)?;
+    project.sounds = load_assets("sounds", load_sound, &asset_maps.sounds, &mut path, &asset_maps)?;
+    project.sprites = load_assets("sprites", load_sprite, &asset_maps.sprites, &mut path, &asset_maps)?;
+    project.backgrounds = load_assets("backgrounds", load_background, &asset_maps.backgrounds, &mut path, &asset_maps)?;
+    project.paths = load_assets("paths", load_path, &asset_maps.paths, &mut path, &asset_maps)?;
+    project.scripts = load_assets("scripts", load_script, &asset_maps.scripts, &mut path, &asset_maps)?;
+    project.fonts = load_assets("fonts", load_font, &asset_maps.fonts, &mut path, &asset_maps)?;
+    project.timelines = load_assets("timelines", load_timeline, &asset_maps.timelines, &mut path, &asset_maps)?;
+    project.objects = load_assets("objects", load_object, &asset_maps.objects, &mut path, &asset_maps)?;
+    project.rooms = load_assets("rooms", load_room, &asset_maps.rooms, &mut path, &asset_maps)?;
+    crate::save::check_ids(&project.rooms)?;
+    if has_datafiles {
+        project.included_files = load_included_files(&mut path)?;
+    }
+    if project.escaped_utf16 {
+        project.for_each_text(|text| {
+            if text.contains("\\u{") {
+                *text = unescape_text(text)
+            }
+        });
+    }
+    Ok(project)
+}
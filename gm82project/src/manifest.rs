@@ -0,0 +1,73 @@
+// A record of every file the last save wrote, so the next one only rewrites what actually changed.
+// Files are compared by the hash of their contents, and are only trusted to still have those contents
+// if their size and modification time haven't changed since, so editing them outside the IDE is noticed.
+
+use crate::{Error, Result};
+use std::{collections::HashMap, fs::Metadata, io::Write, path::Path, time::UNIX_EPOCH};
+use xxhash_rust::xxh3::xxh3_128;
+
+/// Where the manifest goes, relative to the project directory.
+pub const MANIFEST_PATH: &str = "cache/manifest.txt";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileState {
+    pub hash: u128,
+    pub len: u64,
+    pub modified: u128,
+}
+
+impl FileState {
+    fn new(hash: u128, meta: &Metadata) -> Self {
+        let modified = meta.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).map_or(0, |d| d.as_nanos());
+        Self { hash, len: meta.len(), modified }
+    }
+}
+
+/// Keyed by path relative to the project directory, with / as the separator.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Manifest(pub HashMap<String, FileState>);
+
+impl Manifest {
+    /// Reads the manifest from a project directory. A missing or damaged one just means everything gets written.
+    pub fn load(dir: &Path) -> Self {
+        let Ok(contents) = std::fs::read_to_string(dir.join(MANIFEST_PATH)) else { return Self::default() };
+        Self(
+            contents
+                .lines()
+                .filter_map(|line| {
+                    let mut parts = line.splitn(4, ' ');
+                    let hash = u128::from_str_radix(parts.next()?, 16).ok()?;
+                    let len = parts.next()?.parse().ok()?;
+                    let modified = parts.next()?.parse().ok()?;
+                    Some((parts.next()?.to_string(), FileState { hash, len, modified }))
+                })
+                .collect(),
+        )
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut entries = self.0.iter().collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        let mut out = Vec::new();
+        for (name, state) in entries {
+            writeln!(out, "{:032x} {} {} {}", state.hash, state.len, state.modified, name)?;
+        }
+        std::fs::write(path, out).map_err(|e| Error::FileIoError(e, path.to_path_buf()))
+    }
+
+    /// Compares a newly written file with what `target` held after the last save, and records it if it's different.
+    /// Returns whether it needs to replace `target`.
+    pub fn update(&mut self, name: &str, staged: &Path, target: &Path) -> Result<bool> {
+        let data = std::fs::read(staged).map_err(|e| Error::FileIoError(e, staged.to_path_buf()))?;
+        let hash = xxh3_128(&data);
+        if let (Some(old), Ok(meta)) = (self.0.get(name), target.metadata()) {
+            if *old == FileState::new(hash, &meta) {
+                return Ok(false)
+            }
+        }
+        // renaming keeps the modification time, so this is what the target will have too
+        let meta = staged.metadata().map_err(|e| Error::FileIoError(e, staged.to_path_buf()))?;
+        self.0.insert(name.to_string(), FileState::new(hash, &meta));
+        Ok(true)
+    }
+}
@@ -0,0 +1,309 @@
+// Collision masks, which the runner expects to find precomputed in the exe.
+
+use crate::asset::{Frame, Sprite};
+use byteorder::{LE, ReadBytesExt, WriteBytesExt};
+use rayon::prelude::*;
+use std::io::{self, Read, Write};
+
+pub const SHAPE_PRECISE: u32 = 0;
+pub const SHAPE_RECTANGLE: u32 = 1;
+pub const SHAPE_DISK: u32 = 2;
+pub const SHAPE_DIAMOND: u32 = 3;
+
+pub const BBOX_AUTOMATIC: u32 = 0;
+pub const BBOX_FULL_IMAGE: u32 = 1;
+pub const BBOX_MANUAL: u32 = 2;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Mask {
+    pub width: u32,
+    pub height: u32,
+    pub bbox_left: u32,
+    pub bbox_top: u32,
+    pub bbox_right: u32,
+    pub bbox_bottom: u32,
+    /// One entry per pixel, row by row.
+    pub data: Vec<bool>,
+}
+
+/// A sprite's collision settings, which is everything besides the frames that goes into its masks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Collision {
+    pub shape: u32,
+    pub alpha_tolerance: u32,
+    pub per_frame: bool,
+    pub bbox_type: u32,
+    pub bbox_left: i32,
+    pub bbox_top: i32,
+    pub bbox_right: i32,
+    pub bbox_bottom: i32,
+}
+
+impl From<&Sprite> for Collision {
+    fn from(sprite: &Sprite) -> Self {
+        Self {
+            shape: sprite.collision_shape,
+            alpha_tolerance: sprite.alpha_tolerance,
+            per_frame: sprite.per_frame_colliders,
+            bbox_type: sprite.bbox_type,
+            bbox_left: sprite.bbox_left,
+            bbox_top: sprite.bbox_top,
+            bbox_right: sprite.bbox_right,
+            bbox_bottom: sprite.bbox_bottom,
+        }
+    }
+}
+
+/// A frame's pixels, four bytes each with alpha last, so both RGBA and GameMaker's BGRA work.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameData<'a> {
+    pub width: u32,
+    pub height: u32,
+    pub data: &'a [u8],
+}
+
+impl<'a> From<&'a Frame> for FrameData<'a> {
+    fn from(frame: &'a Frame) -> Self {
+        Self { width: frame.width, height: frame.height, data: &frame.data }
+    }
+}
+
+impl FrameData<'_> {
+    fn is_solid(&self, x: u32, y: u32, tolerance: u32) -> bool {
+        let (x, y, width) = (x as usize, y as usize, self.width as usize);
+        x < width && y < self.height as usize && u32::from(self.data[(y * width + x) * 4 + 3]) > tolerance
+    }
+}
+
+/// Builds a mask covering all of `frames` at once. They should all be the same size, like they are in the IDE.
+pub fn make_mask(collision: &Collision, frames: &[FrameData]) -> Mask {
+    let (width, height) = frames.first().map_or((0, 0), |f| (f.width, f.height));
+    let tolerance = collision.alpha_tolerance;
+    let solid = (0..height)
+        .into_par_iter()
+        .flat_map_iter(|y| (0..width).map(move |x| frames.iter().any(|f| f.is_solid(x, y, tolerance))))
+        .collect::<Vec<_>>();
+    let full = [0, 0, width.saturating_sub(1), height.saturating_sub(1)];
+    let [left, top, right, bottom] = match collision.bbox_type {
+        BBOX_AUTOMATIC => solid
+            .par_chunks(width.max(1) as usize)
+            .enumerate()
+            .filter_map(|(y, row)| {
+                let first = row.iter().position(|&s| s)? as u32;
+                let last = row.iter().rposition(|&s| s)? as u32;
+                Some([first, y as u32, last, y as u32])
+            })
+            .reduce_with(|[l1, t1, r1, b1], [l2, t2, r2, b2]| [l1.min(l2), t1.min(t2), r1.max(r2), b1.max(b2)])
+            .unwrap_or(full),
+        BBOX_MANUAL => {
+            let clamp = |v: i32, max: u32| v.clamp(0, max as i32) as u32;
+            [
+                clamp(collision.bbox_left, full[2]),
+                clamp(collision.bbox_top, full[3]),
+                clamp(collision.bbox_right, full[2]),
+                clamp(collision.bbox_bottom, full[3]),
+            ]
+        },
+        _ => full,
+    };
+    // shapes are fit inside the bounding box, measuring from pixel centres
+    let rx = (f64::from(right) - f64::from(left) + 1.0) / 2.0;
+    let ry = (f64::from(bottom) - f64::from(top) + 1.0) / 2.0;
+    let (cx, cy) = (f64::from(left) + rx, f64::from(top) + ry);
+    let data = (0..height)
+        .into_par_iter()
+        .flat_map_iter(|y| {
+            let solid = &solid;
+            (0..width).map(move |x| {
+                let in_bbox = (left..=right).contains(&x) && (top..=bottom).contains(&y);
+                let (dx, dy) = (((f64::from(x) + 0.5 - cx) / rx).abs(), ((f64::from(y) + 0.5 - cy) / ry).abs());
+                in_bbox
+                    && match collision.shape {
+                        SHAPE_PRECISE => solid[y as usize * width as usize + x as usize],
+                        SHAPE_DISK => dx * dx + dy * dy <= 1.0,
+                        SHAPE_DIAMOND => dx + dy <= 1.0,
+                        _ => true,
+                    }
+            })
+        })
+        .collect();
+    Mask { width, height, bbox_left: left, bbox_top: top, bbox_right: right, bbox_bottom: bottom, data }
+}
+
+/// The masks that get saved for a sprite: one per frame if it has separate masks, otherwise one for all of them.
+pub fn make_masks(collision: &Collision, frames: &[FrameData]) -> Vec<Mask> {
+    if frames.is_empty() {
+        Vec::new()
+    } else if collision.per_frame {
+        frames.par_iter().map(|f| make_mask(collision, std::slice::from_ref(f))).collect()
+    } else {
+        vec![make_mask(collision, frames)]
+    }
+}
+
+/// Same as `make_masks`, using a sprite's own frames and settings.
+pub fn sprite_masks(sprite: &Sprite) -> Vec<Mask> {
+    let frames = sprite.frames.iter().map(FrameData::from).collect::<Vec<_>>();
+    make_masks(&sprite.into(), &frames)
+}
+
+pub fn write_mask(mask: &Mask, mut out: impl Write) -> io::Result<()> {
+    out.write_u32::<LE>(800)?;
+    out.write_u32::<LE>(mask.width)?;
+    out.write_u32::<LE>(mask.height)?;
+    out.write_u32::<LE>(mask.bbox_left)?;
+    out.write_u32::<LE>(mask.bbox_right)?;
+    out.write_u32::<LE>(mask.bbox_bottom)?;
+    out.write_u32::<LE>(mask.bbox_top)?;
+    for &px in &mask.data {
+        out.write_u32::<LE>(px.into())?;
+    }
+    Ok(())
+}
+
+pub fn read_mask(mut r: impl Read) -> io::Result<Mask> {
+    let version = r.read_u32::<LE>()?;
+    if version != 800 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unsupported mask version {}", version)))
+    }
+    let width = r.read_u32::<LE>()?;
+    let height = r.read_u32::<LE>()?;
+    let bbox_left = r.read_u32::<LE>()?;
+    let bbox_right = r.read_u32::<LE>()?;
+    let bbox_bottom = r.read_u32::<LE>()?;
+    let bbox_top = r.read_u32::<LE>()?;
+    let data =
+        (0..u64::from(width) * u64::from(height)).map(|_| Ok(r.read_u32::<LE>()? != 0)).collect::<io::Result<_>>()?;
+    Ok(Mask { width, height, bbox_left, bbox_top, bbox_right, bbox_bottom, data })
+}
+
+/// Exes only have the finished masks, so this picks collision settings that would produce them again.
+/// If nothing simple fits, the sprite ends up with a precise mask and a manual bounding box.
+pub fn guess_collision(sprite: &mut Sprite, masks: &[Mask]) {
+    let Some(first) = masks.first() else { return };
+    let bbox =
+        masks.iter().fold((first.bbox_left, first.bbox_top, first.bbox_right, first.bbox_bottom), |(l, t, r, b), m| {
+            (l.min(m.bbox_left), t.min(m.bbox_top), r.max(m.bbox_right), b.max(m.bbox_bottom))
+        });
+    sprite.alpha_tolerance = 0;
+    sprite.bbox_left = bbox.0 as i32;
+    sprite.bbox_top = bbox.1 as i32;
+    sprite.bbox_right = bbox.2 as i32;
+    sprite.bbox_bottom = bbox.3 as i32;
+    let candidates = [
+        (SHAPE_PRECISE, BBOX_AUTOMATIC),
+        (SHAPE_RECTANGLE, BBOX_AUTOMATIC),
+        (SHAPE_RECTANGLE, BBOX_FULL_IMAGE),
+        (SHAPE_RECTANGLE, BBOX_MANUAL),
+        (SHAPE_DISK, BBOX_MANUAL),
+        (SHAPE_DIAMOND, BBOX_MANUAL),
+    ];
+    for (shape, bbox_type) in candidates {
+        sprite.collision_shape = shape;
+        sprite.bbox_type = bbox_type;
+        if sprite_masks(sprite) == masks {
+            return
+        }
+    }
+    sprite.collision_shape = SHAPE_PRECISE;
+    sprite.bbox_type = BBOX_MANUAL;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A frame from a picture where each character is a pixel's alpha: '#' is 255, '.' is 0 and digits are tens.
+    fn frame(rows: &[&str]) -> (u32, u32, Vec<u8>) {
+        let data = rows
+            .iter()
+            .flat_map(|row| row.chars())
+            .flat_map(|c| {
+                let alpha = match c {
+                    '#' => 255,
+                    '.' => 0,
+                    d => d.to_digit(10).unwrap() as u8 * 10,
+                };
+                [0, 0, 0, alpha]
+            })
+            .collect();
+        (rows[0].len() as u32, rows.len() as u32, data)
+    }
+
+    fn mask(collision: &Collision, rows: &[&str]) -> Mask {
+        let (width, height, data) = frame(rows);
+        make_mask(collision, &[FrameData { width, height, data: &data }])
+    }
+
+    fn pixels(mask: &Mask) -> Vec<String> {
+        mask.data
+            .chunks(mask.width as usize)
+            .map(|row| row.iter().map(|&s| if s { '#' } else { '.' }).collect())
+            .collect()
+    }
+
+    #[test]
+    fn precise() {
+        let m = mask(&Collision::default(), &["....", ".#..", "..#.", "...."]);
+        assert_eq!(pixels(&m), ["....", ".#..", "..#.", "...."]);
+        assert_eq!([m.bbox_left, m.bbox_top, m.bbox_right, m.bbox_bottom], [1, 1, 2, 2]);
+    }
+
+    #[test]
+    fn precise_combines_frames() {
+        let ((width, height, a), (_, _, b)) = (frame(&["#.", ".."]), frame(&["..", ".#"]));
+        let frames = [FrameData { width, height, data: &a }, FrameData { width, height, data: &b }];
+        assert_eq!(pixels(&make_mask(&Collision::default(), &frames)), ["#.", ".#"]);
+        let masks = make_masks(&Collision { per_frame: true, ..Default::default() }, &frames);
+        assert_eq!(masks.iter().map(pixels).collect::<Vec<_>>(), [["#.", ".."], ["..", ".#"]]);
+    }
+
+    #[test]
+    fn tolerance() {
+        // a pixel only counts if its alpha is above the tolerance, not equal to it
+        let m = mask(&Collision { alpha_tolerance: 30, ..Default::default() }, &["234", "#.."]);
+        assert_eq!(pixels(&m), ["..#", "#.."]);
+        let m = mask(&Collision { alpha_tolerance: 254, ..Default::default() }, &["9#", ".."]);
+        assert_eq!(pixels(&m), [".#", ".."]);
+        // nothing is solid at all, so the bounding box is the whole image
+        let m = mask(&Collision { alpha_tolerance: 255, ..Default::default() }, &["##", "##"]);
+        assert_eq!(pixels(&m), ["..", ".."]);
+        assert_eq!([m.bbox_left, m.bbox_top, m.bbox_right, m.bbox_bottom], [0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn rectangle() {
+        let rect = Collision { shape: SHAPE_RECTANGLE, ..Default::default() };
+        assert_eq!(pixels(&mask(&rect, &["....", ".#..", "...#", "...."])), ["....", ".###", ".###", "...."]);
+        let full = Collision { bbox_type: BBOX_FULL_IMAGE, ..rect };
+        assert_eq!(pixels(&mask(&full, &["...", "..."])), ["###", "###"]);
+        // manual boxes get clamped to the image
+        let manual =
+            Collision { bbox_type: BBOX_MANUAL, bbox_left: -5, bbox_top: 1, bbox_right: 1, bbox_bottom: 9, ..rect };
+        let m = mask(&manual, &["...", "...", "..."]);
+        assert_eq!(pixels(&m), ["...", "##.", "##."]);
+        assert_eq!([m.bbox_left, m.bbox_top, m.bbox_right, m.bbox_bottom], [0, 1, 1, 2]);
+    }
+
+    #[test]
+    fn ellipse() {
+        let disk = Collision { shape: SHAPE_DISK, bbox_type: BBOX_FULL_IMAGE, ..Default::default() };
+        assert_eq!(pixels(&mask(&disk, &["....", "....", "....", "...."])), [".##.", "####", "####", ".##."]);
+        assert_eq!(pixels(&mask(&disk, &["........", "........"])), [".######.", ".######."]);
+        let diamond = Collision { shape: SHAPE_DIAMOND, ..disk };
+        assert_eq!(pixels(&mask(&diamond, &["...", "...", "..."])), [".#.", "###", ".#."]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let disk = Collision { shape: SHAPE_DISK, bbox_type: BBOX_FULL_IMAGE, ..Default::default() };
+        let m = mask(&disk, &["....", "....", "....", "...."]);
+        let mut data = Vec::new();
+        write_mask(&m, &mut data).unwrap();
+        assert_eq!(read_mask(data.as_slice()).unwrap(), m);
+        let (width, height, data) = frame(&["....", "....", "....", "...."]);
+        let mut sprite = Sprite { frames: vec![Frame { width, height, data }], ..Default::default() };
+        guess_collision(&mut sprite, std::slice::from_ref(&m));
+        assert_eq!((sprite.collision_shape, sprite.bbox_type), (SHAPE_DISK, BBOX_MANUAL));
+    }
+}
@@ -0,0 +1,358 @@
+// Three-way merging for the project files that branches most often both change: asset indexes, resource trees,
+// timestamps, and the instance and tile lists of rooms. Line-based merging conflicts on these whenever both sides
+// add something, even though the formats say exactly how to combine them.
+// Anything that's genuinely edited both ways still conflicts.
+
+use crate::{
+    TreeNode,
+    load::{parse_tree, split_fields},
+    save::write_tree_children,
+};
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap, HashSet},
+    hash::Hash,
+    path::Path,
+};
+
+/// An entry of a merged file.
+enum Merged<T> {
+    Clean(T),
+    /// What ours, the common ancestor and theirs had, None meaning that side doesn't have it.
+    Conflict(Option<T>, Option<T>, Option<T>),
+}
+
+impl<T> Merged<T> {
+    fn map<U>(self, f: impl Fn(T) -> U) -> Merged<U> {
+        match self {
+            Merged::Clean(x) => Merged::Clean(f(x)),
+            Merged::Conflict(ours, base, theirs) => Merged::Conflict(ours.map(&f), base.map(&f), theirs.map(&f)),
+        }
+    }
+}
+
+/// Merges a file the way git would, given its path in the repository, the common ancestor, ours and theirs.
+/// Returns the merged text and how many conflicts were left in it, or None if it isn't a file this knows about.
+pub fn merge_file(path: &str, base: &str, ours: &str, theirs: &str) -> Option<(String, usize)> {
+    let path = Path::new(path);
+    let file_name = path.file_name()?.to_str()?;
+    // room files live in rooms/<room>/
+    let in_room = path.parent().and_then(Path::parent).and_then(Path::file_name) == Some("rooms".as_ref());
+    let merged = match file_name {
+        "index.yyd" => merge_index(base, ours, theirs),
+        "tree.yyd" => merge_tree(base, ours, theirs)?,
+        "timestamps.yyd" => {
+            merge_timestamps(&parse_timestamps(base)?, &parse_timestamps(ours)?, &parse_timestamps(theirs)?)
+        },
+        "layers.txt" if in_room => merge_layers(base, ours, theirs),
+        // instances are named by their creation code file, which is what tells them apart
+        "instances.txt" if in_room => {
+            merge_lines(base, ours, theirs, |l| split_fields(l).and_then(|f| f.into_iter().nth(3)).unwrap_or_default())
+        },
+        // tiles only have something to tell them apart if the project preserves ids,
+        // otherwise moving one counts as removing it and adding another
+        _ if in_room && file_name.strip_suffix(".txt").is_some_and(|depth| depth.parse::<i32>().is_ok()) => {
+            merge_lines(base, ours, theirs, |l| match split_fields(l) {
+                Some(f) if f.len() == 12 => f.into_iter().last().unwrap_or_default(),
+                _ => Cow::Borrowed(l),
+            })
+        },
+        _ => return None,
+    };
+    Some(render(merged))
+}
+
+/// Merges a list whose items are told apart by `key`. Items only one side touched come out as that side has them,
+/// and items one side added go in after the item they followed there, behind anything the other side added too.
+/// If only one side reordered what was already there, its order is kept, otherwise ours is.
+fn merge_list<T: Clone + PartialEq, K: Clone + Eq + Hash>(
+    base: &[T],
+    ours: &[T],
+    theirs: &[T],
+    key: impl Fn(&T) -> K,
+) -> Vec<Merged<T>> {
+    // the same key can come up more than once, so each one is numbered by how many times it came up before
+    let numbered = |items: &[T]| {
+        let mut seen = HashMap::new();
+        items
+            .iter()
+            .map(|item| {
+                let k = key(item);
+                let n = seen.entry(k.clone()).or_insert(0usize);
+                *n += 1;
+                (k, *n)
+            })
+            .collect::<Vec<_>>()
+    };
+    let index = |keys: &[(K, usize)]| keys.iter().cloned().enumerate().map(|(i, k)| (k, i)).collect::<HashMap<_, _>>();
+    let (base_keys, ours_keys, theirs_keys) = (numbered(base), numbered(ours), numbered(theirs));
+    let (base_map, ours_map, theirs_map) = (index(&base_keys), index(&ours_keys), index(&theirs_keys));
+    let kept = |keys: &[(K, usize)]| {
+        keys.iter()
+            .filter(|k| base_map.contains_key(k) && ours_map.contains_key(k) && theirs_map.contains_key(k))
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    // the side whose order is kept goes first, and the other side's additions are slotted into it
+    let swap = kept(&ours_keys) == kept(&base_keys) && kept(&theirs_keys) != kept(&base_keys);
+    let (first, first_keys, first_map, second, second_keys, second_map) = if swap {
+        (theirs, &theirs_keys, &theirs_map, ours, &ours_keys, &ours_map)
+    } else {
+        (ours, &ours_keys, &ours_map, theirs, &theirs_keys, &theirs_map)
+    };
+    let conflict = |first: Option<&T>, base: Option<&T>, second: Option<&T>| {
+        let (first, base, second) = (first.cloned(), base.cloned(), second.cloned());
+        if swap { Merged::Conflict(second, base, first) } else { Merged::Conflict(first, base, second) }
+    };
+    // each item keeps its key, and whether only the first side added it, as the second side's additions go behind those
+    let mut out = Vec::with_capacity(first.len().max(second.len()));
+    for (item, k) in first.iter().zip(first_keys) {
+        let o = base_map.get(k).map(|&i| &base[i]);
+        let s = second_map.get(k).map(|&i| &second[i]);
+        let merged = match (o, s) {
+            (_, Some(s)) if s == item => Merged::Clean(item.clone()),
+            (Some(o), Some(s)) if o == item => Merged::Clean(s.clone()),
+            (Some(o), Some(s)) if o == s => Merged::Clean(item.clone()),
+            // the second side removed it and the first didn't change it
+            (Some(o), None) if o == item => continue,
+            (None, None) => Merged::Clean(item.clone()),
+            (o, s) => conflict(Some(item), o, s),
+        };
+        out.push((k, o.is_none() && s.is_none(), merged));
+    }
+    let mut anchor = None;
+    for (item, k) in second.iter().zip(second_keys) {
+        if first_map.contains_key(k) {
+            anchor = out.iter().position(|(key, ..)| *key == k);
+            continue
+        }
+        let merged = match base_map.get(k).map(|&i| &base[i]) {
+            // the first side removed it and the second didn't change it
+            Some(o) if o == item => continue,
+            Some(o) => conflict(None, Some(o), Some(item)),
+            None => Merged::Clean(item.clone()),
+        };
+        let mut at = anchor.map_or(0, |i| i + 1);
+        while out.get(at).is_some_and(|(_, first_only, _)| *first_only) {
+            at += 1;
+        }
+        out.insert(at, (k, false, merged));
+        anchor = Some(at);
+    }
+    out.into_iter().map(|(_, _, merged)| merged).collect()
+}
+
+fn merge_lines<'a, K: Clone + Eq + Hash>(
+    base: &'a str,
+    ours: &'a str,
+    theirs: &'a str,
+    key: impl Fn(&'a str) -> K,
+) -> Vec<Merged<String>> {
+    let lines = |text: &'a str| text.lines().filter(|l| !l.is_empty()).collect::<Vec<_>>();
+    merge_list(&lines(base), &lines(ours), &lines(theirs), |&l| key(l))
+        .into_iter()
+        .map(|merged| merged.map(|l| format!("{}\n", l)))
+        .collect()
+}
+
+/// Asset indexes are merged slot by slot, since an asset's position is its id. If both sides filled the same free slot,
+/// theirs moves to the end, but a slot that was in use and got changed both ways is a conflict.
+fn merge_index(base: &str, ours: &str, theirs: &str) -> Vec<Merged<String>> {
+    let (base, ours, theirs) =
+        (base.lines().collect::<Vec<_>>(), ours.lines().collect::<Vec<_>>(), theirs.lines().collect::<Vec<_>>());
+    fn slot<'a>(lines: &[&'a str], i: usize) -> &'a str {
+        lines.get(i).copied().unwrap_or_default()
+    }
+    // so an asset added on both sides only goes in once
+    let mut taken = ours.iter().copied().filter(|s| !s.is_empty()).collect::<HashSet<_>>();
+    let mut merged = Vec::with_capacity(ours.len().max(theirs.len()));
+    let mut moved = Vec::new();
+    for i in 0..ours.len().max(theirs.len()) {
+        let (o, a, b) = (slot(&base, i), slot(&ours, i), slot(&theirs, i));
+        merged.push(if a == b || b == o {
+            Merged::Clean(a)
+        } else if a == o {
+            if b.is_empty() || taken.insert(b) {
+                Merged::Clean(b)
+            } else if o.is_empty() {
+                // ours added the same asset in another slot
+                Merged::Clean(a)
+            } else {
+                Merged::Conflict(Some(a), Some(o), Some(b))
+            }
+        } else if o.is_empty() {
+            if taken.insert(b) {
+                moved.push(b);
+            }
+            Merged::Clean(a)
+        } else {
+            Merged::Conflict(Some(a), Some(o), Some(b))
+        });
+    }
+    merged.extend(moved.into_iter().map(Merged::Clean));
+    merged.into_iter().map(|merged| merged.map(|s| format!("{}\n", s))).collect()
+}
+
+/// Layers are just the depths that have tiles on them, so each one is its own key and the order is left as it was.
+fn merge_layers(base: &str, ours: &str, theirs: &str) -> Vec<Merged<String>> {
+    merge_lines(base, ours, theirs, |l| l)
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum TreeKey {
+    Folder(String),
+    Asset(String),
+}
+
+/// Reads a timestamps file into each asset's time, or returns None if a line isn't `name=time`.
+fn parse_timestamps(text: &str) -> Option<HashMap<&str, f64>> {
+    text.lines()
+        .filter(|l| !l.is_empty())
+        .map(|l| l.rsplit_once('=').and_then(|(name, time)| Some((name, time.parse::<f64>().ok()?))))
+        .collect()
+}
+
+/// If both sides changed an asset's timestamp, the asset was changed on both, so the later time wins.
+/// One that was removed on one side stays removed as long as the other side didn't change it.
+fn merge_timestamps(
+    base: &HashMap<&str, f64>,
+    ours: &HashMap<&str, f64>,
+    theirs: &HashMap<&str, f64>,
+) -> Vec<Merged<String>> {
+    let names = ours.keys().chain(theirs.keys()).collect::<BTreeSet<_>>();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let time = match (ours.get(name), theirs.get(name)) {
+                (Some(a), Some(b)) => a.max(*b),
+                (Some(t), None) | (None, Some(t)) if base.get(name) != Some(t) => *t,
+                _ => return None,
+            };
+            Some(Merged::Clean(format!("{}={}\n", name, time)))
+        })
+        .collect()
+}
+
+/// Resource trees are merged one folder at a time, with folders found by the path of folder names leading to them.
+/// Moving an asset to a different folder on both sides, or removing a folder that the other side added to,
+/// conflicts on the whole file, as the tree isn't something that can be fixed up line by line.
+fn merge_tree(base: &str, ours: &str, theirs: &str) -> Option<Vec<Merged<String>>> {
+    type Folders = HashMap<Vec<String>, Vec<TreeKey>>;
+    fn folders(nodes: &[TreeNode], path: &mut Vec<String>, out: &mut Folders) -> Option<()> {
+        let mut children = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            match node {
+                TreeNode::Folder(name, nodes) => {
+                    path.push(name.clone());
+                    folders(nodes, path, out)?;
+                    path.pop();
+                    children.push(TreeKey::Folder(name.clone()));
+                },
+                TreeNode::Asset(name) => children.push(TreeKey::Asset(name.clone())),
+            }
+        }
+        // two folders with the same name in the same place can't be told apart
+        out.insert(path.clone(), children).is_none().then_some(())
+    }
+    fn build(path: &mut Vec<String>, folders: &Folders, assets: &mut Vec<String>) -> Vec<TreeNode> {
+        let mut nodes = Vec::new();
+        for child in folders.get(path).into_iter().flatten() {
+            match child {
+                TreeKey::Folder(name) => {
+                    path.push(name.clone());
+                    let children = build(path, folders, assets);
+                    path.pop();
+                    nodes.push(TreeNode::Folder(name.clone(), children));
+                },
+                TreeKey::Asset(name) => {
+                    assets.push(name.clone());
+                    nodes.push(TreeNode::Asset(name.clone()));
+                },
+            }
+        }
+        nodes
+    }
+    let assets = |folders: &Folders| {
+        folders
+            .values()
+            .flatten()
+            .filter_map(|k| match k {
+                TreeKey::Asset(name) => Some(name.clone()),
+                TreeKey::Folder(_) => None,
+            })
+            .collect::<HashSet<_>>()
+    };
+    let mut trees = [base, ours, theirs].into_iter().map(|text| {
+        let mut out = Folders::new();
+        folders(&parse_tree(text)?, &mut Vec::new(), &mut out)?;
+        Some(out)
+    });
+    let (base_folders, ours_folders, theirs_folders) = (trees.next()??, trees.next()??, trees.next()??);
+    let empty = Vec::new();
+    let merged = base_folders
+        .keys()
+        .chain(ours_folders.keys())
+        .chain(theirs_folders.keys())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .map(|path| {
+            let list = |folders: &Folders| folders.get(path).unwrap_or(&empty).clone();
+            let children =
+                merge_list(&list(&base_folders), &list(&ours_folders), &list(&theirs_folders), TreeKey::clone)
+                    .into_iter()
+                    // items are their own keys, so there's never anything to conflict on
+                    .filter_map(|merged| if let Merged::Clean(k) = merged { Some(k) } else { None })
+                    .collect();
+            (path.clone(), children)
+        })
+        .collect::<Folders>();
+    let mut placed = Vec::new();
+    let tree = build(&mut Vec::new(), &merged, &mut placed);
+    let (base_assets, ours_assets, theirs_assets) =
+        (assets(&base_folders), assets(&ours_folders), assets(&theirs_folders));
+    let expected = ours_assets
+        .union(&theirs_assets)
+        .filter(|name| !base_assets.contains(*name) || (ours_assets.contains(*name) && theirs_assets.contains(*name)))
+        .collect::<HashSet<_>>();
+    let unique = placed.iter().collect::<HashSet<_>>();
+    if unique.len() != placed.len() || unique != expected {
+        let text = |s: &str| if s.is_empty() || s.ends_with('\n') { s.to_string() } else { format!("{}\n", s) };
+        return Some(vec![Merged::Conflict(Some(text(ours)), Some(text(base)), Some(text(theirs)))])
+    }
+    let mut out = Vec::new();
+    write_tree_children(&tree, &mut String::new(), &mut out).ok()?;
+    Some(vec![Merged::Clean(String::from_utf8(out).ok()?)])
+}
+
+/// Writes out merged lines, with each run of conflicts between one set of conflict markers.
+/// Returns how many runs there were.
+fn render(merged: Vec<Merged<String>>) -> (String, usize) {
+    let mut out = String::new();
+    let mut conflicts = 0;
+    let mut iter = merged.into_iter().peekable();
+    while let Some(merged) = iter.next() {
+        let (mut ours, mut base, mut theirs) = match merged {
+            Merged::Clean(text) => {
+                out += &text;
+                continue
+            },
+            Merged::Conflict(ours, base, theirs) => {
+                (ours.unwrap_or_default(), base.unwrap_or_default(), theirs.unwrap_or_default())
+            },
+        };
+        while let Some(Merged::Conflict(o, b, t)) = iter.next_if(|m| matches!(m, Merged::Conflict(..))) {
+            ours += &o.unwrap_or_default();
+            base += &b.unwrap_or_default();
+            theirs += &t.unwrap_or_default();
+        }
+        out += "<<<<<<< ours\n";
+        out += &ours;
+        out += "||||||| base\n";
+        out += &base;
+        out += "=======\n";
+        out += &theirs;
+        out += ">>>>>>> theirs\n";
+        conflicts += 1;
+    }
+    (out, conflicts)
+}
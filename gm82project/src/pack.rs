@@ -0,0 +1,100 @@
+// Rectangle packing for font atlases.
+// This is a skyline packer: it keeps track of the top edge of everything placed so far,
+// and puts each rectangle wherever its bottom edge ends up lowest.
+
+/// Where each rectangle went, in the same order they were given, and how big the atlas needs to be.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Packing {
+    pub width: u32,
+    pub height: u32,
+    pub positions: Vec<(u32, u32)>,
+}
+
+#[derive(Clone, Copy)]
+struct Segment {
+    x: u32,
+    y: u32,
+    width: u32,
+}
+
+/// Packs rectangles into a strip `width` wide, returning positions and the height used,
+/// or None if one of them is wider than the strip.
+fn pack_strip(sizes: &[(u32, u32)], order: &[usize], width: u32) -> Option<(Vec<(u32, u32)>, u32)> {
+    let mut skyline = vec![Segment { x: 0, y: 0, width }];
+    let mut positions = vec![(0, 0); sizes.len()];
+    let mut height = 0;
+    for &i in order {
+        let (w, h) = sizes[i];
+        if w == 0 || h == 0 {
+            continue
+        }
+        // find the spot where the bottom of the rectangle is lowest, then leftmost
+        let mut best: Option<(u32, u32, usize)> = None;
+        for start in 0..skyline.len() {
+            let x = skyline[start].x;
+            if x + w > width {
+                break
+            }
+            let mut y = 0;
+            let mut covered = 0;
+            for seg in &skyline[start..] {
+                if covered >= w {
+                    break
+                }
+                y = y.max(seg.y);
+                covered += seg.width;
+            }
+            if best.is_none_or(|(best_y, best_x, _)| (y, x) < (best_y, best_x)) {
+                best = Some((y, x, start));
+            }
+        }
+        let (y, x, start) = best?;
+        positions[i] = (x, y);
+        height = height.max(y + h);
+        // raise the skyline under the rectangle, trimming whatever it partly covers
+        let mut end = start;
+        while end < skyline.len() && skyline[end].x + skyline[end].width <= x + w {
+            end += 1;
+        }
+        if let Some(seg) = skyline.get_mut(end).filter(|s| s.x < x + w) {
+            seg.width -= x + w - seg.x;
+            seg.x = x + w;
+        }
+        skyline.splice(start..end, [Segment { x, y: y + h, width: w }]);
+        skyline.dedup_by(|b, a| {
+            let merge = a.y == b.y;
+            if merge {
+                a.width += b.width;
+            }
+            merge
+        });
+    }
+    Some((positions, height))
+}
+
+/// Packs rectangles into the smallest atlas no bigger than `max_size` on either side, leaving `spacing` pixels
+/// to the right of and below each of them. Both sides are powers of two, since the runner uploads it as a texture.
+/// Empty rectangles all go at 0,0.
+pub fn pack(sizes: &[(u32, u32)], spacing: u32, max_size: u32) -> Option<Packing> {
+    let padded = sizes
+        .iter()
+        .map(|&(w, h)| if w == 0 || h == 0 { (0, 0) } else { (w + spacing, h + spacing) })
+        .collect::<Vec<_>>();
+    // tallest first, which is what skyline packing likes best
+    let mut order = (0..sizes.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| (std::cmp::Reverse(padded[i].1), std::cmp::Reverse(padded[i].0)));
+    let widest = padded.iter().map(|&(w, _)| w).max().unwrap_or(0).max(1);
+    let mut best: Option<Packing> = None;
+    let mut width = widest.next_power_of_two();
+    while width <= max_size {
+        if let Some((positions, height)) = pack_strip(&padded, &order, width) {
+            let height = height.max(1).next_power_of_two();
+            let area = u64::from(width) * u64::from(height);
+            if height <= max_size && best.as_ref().is_none_or(|b| area < u64::from(b.width) * u64::from(b.height)) {
+                best = Some(Packing { width, height, positions });
+            }
+        }
+        width *= 2;
+    }
+    best
+}
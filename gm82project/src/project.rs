@@ -0,0 +1,243 @@
+use crate::asset::*;
+
+/// An entry in a resource tree, as stored in `tree.yyd`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TreeNode {
+    Folder(String, Vec<TreeNode>),
+    Asset(String),
+}
+
+/// One asset type. Like in the IDE, names, assets and timestamps are parallel lists,
+/// and deleted assets leave an empty slot so the indices of the others don't change.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssetList<T> {
+    pub assets: Vec<Option<T>>,
+    pub names: Vec<String>,
+    /// When each asset was last changed, as a Delphi TDateTime. 0 if it isn't known.
+    pub timestamps: Vec<f64>,
+    pub tree: Vec<TreeNode>,
+}
+
+impl<T> Default for AssetList<T> {
+    fn default() -> Self {
+        Self { assets: Vec::new(), names: Vec::new(), timestamps: Vec::new(), tree: Vec::new() }
+    }
+}
+
+impl<T> AssetList<T> {
+    /// Whether there are any assets in the list, ignoring empty slots.
+    pub fn has_any(&self) -> bool {
+        self.assets.iter().any(Option::is_some)
+    }
+
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        if name.is_empty() {
+            return None
+        }
+        self.names.iter().zip(&self.assets).position(|(n, a)| a.is_some() && n == name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&T> {
+        self.assets.get(self.index_of(name)?)?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut T> {
+        let index = self.index_of(name)?;
+        self.assets.get_mut(index)?.as_mut()
+    }
+
+    /// Iterates over every existing asset along with its name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.names.iter().zip(&self.assets).filter_map(|(n, a)| Some((n.as_str(), a.as_ref()?)))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&str, &mut T)> {
+        self.names.iter().zip(&mut self.assets).filter_map(|(n, a)| Some((n.as_str(), a.as_mut()?)))
+    }
+
+    /// Adds an asset to the end of the list and to the root of the resource tree, returning its index.
+    pub fn push(&mut self, name: impl Into<String>, asset: T) -> usize {
+        let name = name.into();
+        self.tree.push(TreeNode::Asset(name.clone()));
+        self.names.push(name);
+        self.assets.push(Some(asset));
+        self.timestamps.push(0.0);
+        self.assets.len() - 1
+    }
+}
+
+/// Global game settings, including the bits that live in the .gm82 file itself.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Settings {
+    pub fullscreen: bool,
+    pub interpolate_pixels: bool,
+    pub dont_draw_border: bool,
+    pub display_cursor: bool,
+    pub scaling: i32,
+    pub allow_resize: bool,
+    pub window_on_top: bool,
+    pub clear_colour: u32,
+    pub set_resolution: bool,
+    pub colour_depth: u32,
+    pub resolution: u32,
+    pub frequency: u32,
+    pub dont_show_buttons: bool,
+    pub vsync: bool,
+    pub swap_creation_events: bool,
+    pub disable_screensaver: bool,
+    pub f4_fullscreen: bool,
+    pub f1_help: bool,
+    pub esc_close: bool,
+    pub f5_save_f6_load: bool,
+    pub f9_screenshot: bool,
+    pub treat_close_as_esc: bool,
+    pub priority: u32,
+    pub freeze_on_lose_focus: bool,
+    pub loading_bar: u32,
+    /// back.bmp, only used with a custom loading bar.
+    pub loading_background: Option<Vec<u8>>,
+    /// front.bmp, only used with a custom loading bar.
+    pub loading_foreground: Option<Vec<u8>>,
+    /// loader.bmp
+    pub custom_load_image: Option<Vec<u8>>,
+    pub loading_transparent: bool,
+    pub loading_translucency: u32,
+    pub loading_progress_bar_scale: bool,
+    /// icon.ico
+    pub icon: Vec<u8>,
+    pub show_error_messages: bool,
+    pub log_errors: bool,
+    pub always_abort: bool,
+    pub zero_uninitialized_vars: bool,
+    pub error_on_uninitialized_args: bool,
+    pub info_author: String,
+    pub info_version: String,
+    pub info_information: String,
+    pub version_major: u32,
+    pub version_minor: u32,
+    pub version_release: u32,
+    pub version_build: u32,
+    pub exe_company: String,
+    pub exe_product: String,
+    pub exe_copyright: String,
+    pub exe_description: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GameInformation {
+    pub colour: u32,
+    pub new_window: bool,
+    pub caption: String,
+    pub left: i32,
+    pub top: i32,
+    pub width: i32,
+    pub height: i32,
+    pub border: bool,
+    pub resizable: bool,
+    pub window_on_top: bool,
+    pub freeze_game: bool,
+    /// Contents of game_information.rtf.
+    pub rtf: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Project {
+    pub game_id: u32,
+    pub settings: Settings,
+    pub game_info: GameInformation,
+    pub constants: Vec<(String, String)>,
+    /// Names of the extension packages the game uses.
+    pub extensions: Vec<String>,
+    pub triggers: Vec<Option<Trigger>>,
+    pub sounds: AssetList<Sound>,
+    pub sprites: AssetList<Sprite>,
+    pub backgrounds: AssetList<Background>,
+    pub paths: AssetList<Path>,
+    pub scripts: AssetList<Script>,
+    pub fonts: AssetList<Font>,
+    pub timelines: AssetList<Timeline>,
+    pub objects: AssetList<Object>,
+    pub rooms: AssetList<Room>,
+    pub included_files: Vec<IncludedFile>,
+    /// Whether instance and tile ids get saved, rather than handed out again every time the project is loaded.
+    pub preserve_ids: bool,
+    /// Whether text gets saved with invalid UTF-16 escaped like `\u{D800}`, as projects from gm82_version 6 on are.
+    /// Text here is always unescaped, with anything invalid turned into U+FFFD like it is in a built game.
+    pub escaped_utf16: bool,
+}
+
+impl Project {
+    pub fn trigger_index(&self, name: &str) -> Option<usize> {
+        self.triggers.iter().position(|t| t.as_ref().map(|t| t.name == name) == Some(true))
+    }
+
+    /// Calls `f` on every piece of text in the project that isn't a name, which is what gets escaped when it's saved.
+    pub(crate) fn for_each_text(&mut self, mut f: impl FnMut(&mut String)) {
+        fn event(event: &mut Event, f: &mut impl FnMut(&mut String)) {
+            for action in &mut event.actions {
+                f(&mut action.code);
+                match &mut action.params {
+                    ActionParams::Normal { args, .. } => args.iter_mut().for_each(&mut *f),
+                    ActionParams::Repeat(repeats) => f(repeats),
+                    ActionParams::Variable { name, value } => {
+                        f(name);
+                        f(value);
+                    },
+                    ActionParams::None => (),
+                }
+            }
+        }
+        let settings = &mut self.settings;
+        for text in [
+            &mut settings.info_author,
+            &mut settings.info_version,
+            &mut settings.info_information,
+            &mut settings.exe_company,
+            &mut settings.exe_product,
+            &mut settings.exe_copyright,
+            &mut settings.exe_description,
+            &mut self.game_info.caption,
+        ] {
+            f(text);
+        }
+        for (name, value) in &mut self.constants {
+            f(name);
+            f(value);
+        }
+        self.extensions.iter_mut().for_each(&mut f);
+        for trigger in self.triggers.iter_mut().flatten() {
+            f(&mut trigger.condition);
+            f(&mut trigger.constant_name);
+        }
+        for (_, sound) in self.sounds.iter_mut() {
+            f(&mut sound.extension);
+            f(&mut sound.source);
+        }
+        for (_, script) in self.scripts.iter_mut() {
+            f(&mut script.source);
+        }
+        for (_, font) in self.fonts.iter_mut() {
+            f(&mut font.sys_name);
+        }
+        for (_, timeline) in self.timelines.iter_mut() {
+            for (_, ev) in &mut timeline.moments {
+                event(ev, &mut f);
+            }
+        }
+        for (_, object) in self.objects.iter_mut() {
+            for (_, ev) in object.events.iter_mut().flatten() {
+                event(ev, &mut f);
+            }
+        }
+        for (_, room) in self.rooms.iter_mut() {
+            f(&mut room.caption);
+            f(&mut room.creation_code);
+            for instance in &mut room.instances {
+                f(&mut instance.creation_code);
+            }
+        }
+        for file in &mut self.included_files {
+            f(&mut file.export_custom_folder);
+        }
+    }
+}
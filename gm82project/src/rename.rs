@@ -0,0 +1,276 @@
+// Renaming an asset along with everything that refers to it.
+// Other assets refer to it by name, so those fields are updated directly. GML is searched for the name as an
+// identifier, skipping strings and comments, so text that merely contains the name is left alone.
+
+use crate::{
+    AssetList, Error, Project, Result, TreeNode,
+    actionlib::{self, ActionLibrary},
+    asset::*,
+    events,
+    gml::{identifiers, instance_name},
+    save::{filename_invalid, make_unicase},
+};
+
+/// A rename to apply to GML.
+pub struct Rename<'a> {
+    old: &'a str,
+    new: &'a str,
+    name: bool,
+    instances: bool,
+}
+
+impl<'a> Rename<'a> {
+    /// Renames references to an asset.
+    pub fn asset(old: &'a str, new: &'a str) -> Self {
+        Self { old, new, name: true, instances: false }
+    }
+
+    /// Renames references to a room, and to its instances with `<room>_XXXXXXXX`.
+    pub fn room(old: &'a str, new: &'a str) -> Self {
+        Self { old, new, name: true, instances: true }
+    }
+
+    /// Only renames `<room>_XXXXXXXX` instance references, for when a room is duplicated.
+    pub fn instances(old: &'a str, new: &'a str) -> Self {
+        Self { old, new, name: false, instances: true }
+    }
+
+    fn identifier(&self, ident: &str) -> Option<String> {
+        if self.name && ident == self.old {
+            return Some(self.new.to_string())
+        }
+        match instance_name(ident) {
+            Some((room, name)) if self.instances && room == self.old => Some(format!("{}_{:08X}", self.new, name)),
+            _ => None,
+        }
+    }
+
+    /// Renames every reference in a piece of GML, returning how many there were.
+    pub fn gml(&self, code: &mut String) -> usize {
+        let mut count = 0;
+        let mut out = String::with_capacity(code.len());
+        let mut last = 0;
+        for token in identifiers(code) {
+            if let Some(new) = self.identifier(token.text) {
+                out.push_str(&code[last..token.span.start]);
+                out.push_str(&new);
+                last = token.span.end;
+                count += 1;
+            }
+        }
+        if count != 0 {
+            out.push_str(&code[last..]);
+            *code = out;
+        }
+        count
+    }
+}
+
+fn rename_in_list<T>(list: &mut AssetList<T>, kind: &str, old: &str, new: &str) -> Result<()> {
+    fn rename_node(nodes: &mut [TreeNode], old: &str, new: &str) -> bool {
+        nodes.iter_mut().any(|node| match node {
+            TreeNode::Folder(_, children) => rename_node(children, old, new),
+            TreeNode::Asset(name) if name == old => {
+                *name = new.to_string();
+                true
+            },
+            TreeNode::Asset(_) => false,
+        })
+    }
+    let index = list.index_of(old).ok_or_else(|| Error::Other(format!("there are no {} named {}", kind, old)))?;
+    if let Some(c) = filename_invalid(new) {
+        return Err(Error::BadAssetName(new.to_string(), char::from(c)))
+    }
+    let new_unicase = make_unicase(new.to_string());
+    if list.iter().any(|(name, _)| name != old && make_unicase(name.to_string()) == new_unicase) {
+        return Err(Error::DuplicateAsset(new.to_string()))
+    }
+    list.names[index] = new.to_string();
+    rename_node(&mut list.tree, old, new);
+    Ok(())
+}
+
+/// Renames the field if it refers to the asset, returning how many references that was.
+fn rename_field(field: &mut String, old: &str, new: &str) -> usize {
+    if field == old {
+        *field = new.to_string();
+        1
+    } else {
+        0
+    }
+}
+
+struct Renamer<'a> {
+    rename: Rename<'a>,
+    /// Action argument type for the asset type being renamed.
+    arg_type: u32,
+    /// Whether it's an object, which actions can apply to.
+    object: bool,
+    libs: Option<&'a [ActionLibrary]>,
+}
+
+impl Renamer<'_> {
+    /// Renames references in an event's actions, returning how many there were and how many arguments might have been
+    /// references but were left alone, as their type isn't known without the action libraries.
+    fn event(&self, event: &mut Event) -> Result<(usize, usize)> {
+        let (old, new) = (self.rename.old, self.rename.new);
+        let mut count = 0;
+        let mut unknown = 0;
+        for action in &mut event.actions {
+            count += self.rename.gml(&mut action.code);
+            if self.object {
+                if let Some(applies_to) = &mut action.applies_to {
+                    count += rename_field(applies_to, old, new);
+                }
+            }
+            match &mut action.params {
+                ActionParams::Normal { args, .. } => {
+                    if let Some(libs) = self.libs {
+                        let def = actionlib::find_action(libs, action.lib_id, action.id)?;
+                        for (arg, ty) in args.iter_mut().zip(def.param_types) {
+                            count += match ty {
+                                // expression, or string or expression
+                                0 | 2 => self.rename.gml(arg),
+                                ty if ty == self.arg_type => rename_field(arg, old, new),
+                                _ => 0,
+                            };
+                        }
+                    } else {
+                        // without the libraries there's no telling what the arguments are,
+                        // so one that's exactly the name could be a string or a different type of asset
+                        unknown += args.iter().filter(|arg| *arg == old).count();
+                    }
+                },
+                ActionParams::Repeat(repeats) => count += self.rename.gml(repeats),
+                ActionParams::Variable { name, value } => count += self.rename.gml(name) + self.rename.gml(value),
+                ActionParams::None => (),
+            }
+        }
+        Ok((count, unknown))
+    }
+}
+
+/// What [`rename_asset`] did.
+#[derive(Debug, Default)]
+pub struct Renamed {
+    /// How many references were updated.
+    pub count: usize,
+    /// Where action arguments were left alone because, without the action libraries, it's not known whether they
+    /// refer to the asset, like "object obj_player". They're exactly the old name.
+    pub unknown: Vec<String>,
+}
+
+/// Renames an asset and every reference to it in the project.
+/// `kind` is the asset type as it's named in the project folder, such as "sprites".
+/// Action libraries are optional, but without them action arguments aren't renamed, as it's not known what they are.
+/// Ones that are exactly the old name are reported instead.
+pub fn rename_asset(
+    project: &mut Project,
+    kind: &str,
+    old: &str,
+    new: &str,
+    libs: Option<&[ActionLibrary]>,
+) -> Result<Renamed> {
+    let arg_type = match kind {
+        "sprites" => 5,
+        "sounds" => 6,
+        "backgrounds" => 7,
+        "paths" => 8,
+        "scripts" => 9,
+        "objects" => 10,
+        "rooms" => 11,
+        "fonts" => 12,
+        "timelines" => 14,
+        _ => return Err(Error::Other(format!("unknown asset type {}", kind))),
+    };
+    match kind {
+        "sprites" => rename_in_list(&mut project.sprites, kind, old, new)?,
+        "sounds" => rename_in_list(&mut project.sounds, kind, old, new)?,
+        "backgrounds" => rename_in_list(&mut project.backgrounds, kind, old, new)?,
+        "paths" => rename_in_list(&mut project.paths, kind, old, new)?,
+        "scripts" => rename_in_list(&mut project.scripts, kind, old, new)?,
+        "objects" => rename_in_list(&mut project.objects, kind, old, new)?,
+        "rooms" => rename_in_list(&mut project.rooms, kind, old, new)?,
+        "fonts" => rename_in_list(&mut project.fonts, kind, old, new)?,
+        _ => rename_in_list(&mut project.timelines, kind, old, new)?,
+    }
+    let renamer = Renamer {
+        rename: if kind == "rooms" { Rename::room(old, new) } else { Rename::asset(old, new) },
+        arg_type,
+        object: kind == "objects",
+        libs,
+    };
+    let rename = &renamer.rename;
+    let mut count = 0;
+    let mut unknown = Vec::new();
+    let mut event = |event: &mut Event, owner: &str| -> Result<usize> {
+        let (renamed, unsure) = renamer.event(event)?;
+        if unsure != 0 && unknown.last().map(String::as_str) != Some(owner) {
+            unknown.push(owner.to_string());
+        }
+        Ok(renamed)
+    };
+
+    for (_, value) in &mut project.constants {
+        count += rename.gml(value);
+    }
+    for trigger in project.triggers.iter_mut().flatten() {
+        count += rename.gml(&mut trigger.condition);
+    }
+    for (_, script) in project.scripts.iter_mut() {
+        count += rename.gml(&mut script.source);
+    }
+    for (name, timeline) in project.timelines.iter_mut() {
+        let owner = format!("timeline {}", name);
+        for (_, ev) in &mut timeline.moments {
+            count += event(ev, &owner)?;
+        }
+    }
+    for (name, object) in project.objects.iter_mut() {
+        let owner = format!("object {}", name);
+        match kind {
+            "sprites" => count += rename_field(&mut object.sprite, old, new) + rename_field(&mut object.mask, old, new),
+            "objects" => count += rename_field(&mut object.parent, old, new),
+            _ => (),
+        }
+        for (ev_type, events) in object.events.iter_mut().enumerate() {
+            for (ev_numb, ev) in events {
+                if let (true, events::EV_COLLISION, EventNumber::Name(name)) = (renamer.object, ev_type, ev_numb) {
+                    count += rename_field(name, old, new);
+                }
+                count += event(ev, &owner)?;
+            }
+        }
+    }
+    for (_, room) in project.rooms.iter_mut() {
+        count += rename.gml(&mut room.creation_code);
+        for instance in &mut room.instances {
+            if renamer.object {
+                count += rename_field(&mut instance.object, old, new);
+            }
+            count += rename.gml(&mut instance.creation_code);
+        }
+        match kind {
+            "backgrounds" => {
+                for background in &mut room.backgrounds {
+                    count += rename_field(&mut background.source_bg, old, new);
+                }
+                for tile in &mut room.tiles {
+                    count += rename_field(&mut tile.source_bg, old, new);
+                }
+            },
+            "objects" => {
+                for view in &mut room.views {
+                    count += rename_field(&mut view.following_target, old, new);
+                }
+            },
+            _ => (),
+        }
+    }
+    if kind == "rooms" {
+        for (_, path) in project.paths.iter_mut() {
+            count += rename_field(&mut path.background, old, new);
+        }
+    }
+    Ok(Renamed { count, unknown })
+}
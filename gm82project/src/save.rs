@@ -0,0 +1,1258 @@
+use crate::{
+    ACTION_TOKEN, AssetList, Error, GM82_VERSION, GMLLines, GameInformation, Project, Result, Settings, TreeNode,
+    asset::*,
+    events,
+    load::unescape_text,
+    transaction::{self, Transaction},
+};
+use itertools::Itertools;
+use png::Compression;
+use rayon::prelude::*;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    ffi::OsString,
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+use xxhash_rust::xxh3::xxh3_64;
+
+pub fn delimit(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\r', "\\r").replace('\n', "\\n").replace("*/", "*\\/")
+}
+
+/// Writes a field of a comma-separated line like those in instances.txt, quoting it if it has a comma or a quote,
+/// with any quotes doubled. Names are the only fields that can need it. [`crate::load::split_fields`] reads it back.
+pub fn quote_field(s: &str) -> Cow<'_, str> {
+    if s.contains([',', '"']) { Cow::Owned(format!("\"{}\"", s.replace('"', "\"\""))) } else { Cow::Borrowed(s) }
+}
+
+/// Events in a .gml file are separated by `#define` lines, so any line in an event's code that starts like one gets
+/// an extra `#`, making it `##define`. So that it all comes back the same, lines that already start with `##define`
+/// get one more too. [`crate::load::unescape_defines`] undoes it.
+pub fn escape_defines(code: &str) -> String {
+    let mut out = String::with_capacity(code.len());
+    for (i, line) in code.split('\n').enumerate() {
+        if i != 0 {
+            out.push('\n');
+        }
+        if line.starts_with('#') && line.trim_start_matches('#').starts_with("define") {
+            out.push('#');
+        }
+        out.push_str(line);
+    }
+    out
+}
+
+/// Turns UTF-16 text from GameMaker into a string, writing unpaired surrogates as escapes like `\u{D800}`.
+/// So that it all comes back the same, a backslash that would be read as the start of an escape becomes `\u{5C}`.
+/// Text without either is unchanged. [`crate::load::unescape_utf16`] undoes it.
+pub fn escape_utf16(s: &[u16]) -> String {
+    let chars = char::decode_utf16(s.iter().copied()).collect::<Vec<_>>();
+    let mut out = String::with_capacity(s.len());
+    for (i, c) in chars.iter().enumerate() {
+        match c {
+            Ok('\\') if chars.get(i + 1..i + 3) == Some(&[Ok('u'), Ok('{')]) => out.push_str("\\u{5C}"),
+            Ok(c) => out.push(*c),
+            Err(e) => out.push_str(&format!("\\u{{{:X}}}", e.unpaired_surrogate())),
+        }
+    }
+    out
+}
+
+/// Escapes text the way [`escape_utf16`] would, for a project that's saved with it escaped.
+/// As it's already valid, that only means backslashes that would be read as the start of an escape.
+pub fn escape_text(s: &str) -> String {
+    s.replace("\\u{", "\\u{5C}u{")
+}
+
+/// Names that Windows keeps for devices, whatever the extension. "aux.txt" can't be created any more than "AUX" can.
+const RESERVED_NAMES: [&str; 4] = ["CON", "PRN", "AUX", "NUL"];
+const RESERVED_PORTS: [&str; 2] = ["COM", "LPT"];
+
+fn is_reserved(s: &str) -> bool {
+    // windows ignores spaces before the extension too
+    let stem = s.split('.').next().unwrap_or_default().trim_end_matches(' ');
+    if RESERVED_NAMES.iter().any(|n| n.eq_ignore_ascii_case(stem)) {
+        return true
+    }
+    match (stem.get(..3), stem.get(3..)) {
+        (Some(port), Some(number)) => {
+            RESERVED_PORTS.iter().any(|p| p.eq_ignore_ascii_case(port))
+                && matches!(number, "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" | "¹" | "²" | "³")
+        },
+        _ => false,
+    }
+}
+
+/// Checks that a name can be a file on Windows as well as anywhere else, returning the character that's the problem.
+/// Names that are only a problem as a whole, like "CON" or ones ending in a space, give a space.
+pub fn filename_invalid(s: &str) -> Option<u8> {
+    if s == "." || s == ".." || s.as_bytes().last().copied() == Some(b'.') {
+        return Some(b'.')
+    }
+    if s.trim().is_empty() || s.ends_with(' ') || is_reserved(s) {
+        return Some(b' ')
+    }
+    if let Some(c) = s.bytes().find(|c| c.is_ascii_control() && *c != 0x7f) {
+        return Some(c)
+    }
+    for c in b"<>:\"/\\|?*" {
+        if s.as_bytes().contains(c) {
+            return Some(*c)
+        }
+    }
+    None
+}
+
+pub fn make_unicase(s: String) -> unicase::UniCase<String> {
+    if s.is_ascii() { unicase::UniCase::ascii(s) } else { unicase::UniCase::unicode(s) }
+}
+
+fn create_dirs(path: &std::path::Path) -> Result<()> {
+    std::fs::create_dir_all(path).map_err(|e| Error::DirIoError(e, path.to_path_buf()))
+}
+
+fn open_file(path: &std::path::Path) -> Result<BufWriter<File>> {
+    Ok(BufWriter::new(File::create(path).map_err(|e| Error::FileIoError(e, path.to_path_buf()))?))
+}
+
+fn write_file(path: &std::path::Path, content: impl AsRef<[u8]>) -> Result<()> {
+    std::fs::write(path, content).map_err(|e| Error::FileIoError(e, path.to_path_buf()))
+}
+
+fn write_gml<F: Write>(f: &mut F, code: &str) -> Result<()> {
+    for line in GMLLines::new(code.trim_end()) {
+        writeln!(f, "{}", line)?;
+    }
+    Ok(())
+}
+
+fn save_gml(path: &std::path::Path, code: &str) -> Result<()> {
+    let mut f = open_file(path)?;
+    write_gml(&mut f, code)?;
+    f.flush()?;
+    Ok(())
+}
+
+pub fn save_frame(frame: &Frame, path: &std::path::Path) -> Result<()> {
+    let err = |_| Error::Other(format!("failed to save frame {}", path.to_string_lossy()));
+    // set up encoder
+    let mut f = open_file(path)?;
+    let mut encoder = png::Encoder::new(&mut f, frame.width, frame.height);
+    encoder.set_compression(Compression::Default);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_filter(png::FilterType::NoFilter);
+    let mut writer = encoder.write_header().map_err(err)?;
+    // BGRA8 -> RGBA8
+    let mut pixels = frame.data.clone();
+    pixels.par_chunks_exact_mut(4).for_each(|px| px.swap(0, 2));
+    // save
+    writer.write_image_data(&pixels).map_err(err)?;
+    drop(writer);
+    f.flush()?;
+    Ok(())
+}
+
+fn save_sound(sound: &Sound, path: &mut PathBuf, _project: &Project) -> Result<()> {
+    path.set_extension(sound.extension.trim_matches('.'));
+    if let Some(data) = sound.data.as_ref() {
+        write_file(path, data)?;
+    }
+    path.set_extension("txt");
+    let mut f = open_file(path)?;
+    write_sound_txt(sound, &mut f)?;
+    f.flush()?;
+    Ok(())
+}
+
+pub(crate) fn write_sound_txt<F: Write>(sound: &Sound, f: &mut F) -> Result<()> {
+    writeln!(f, "extension={}", sound.extension)?;
+    writeln!(f, "exists={}", u8::from(sound.data.is_some()))?;
+    writeln!(f, "source={}", sound.source)?;
+    writeln!(f, "kind={}", sound.kind)?;
+    writeln!(f, "effects={}", sound.effects)?;
+    writeln!(f, "volume={}", sound.volume)?;
+    writeln!(f, "pan={}", sound.pan)?;
+    writeln!(f, "preload={}", u8::from(sound.preload))?;
+    Ok(())
+}
+
+fn save_sprite(sprite: &Sprite, path: &mut PathBuf, _project: &Project) -> Result<()> {
+    create_dirs(path)?;
+    for (i, frame) in sprite.frames.iter().enumerate() {
+        path.push(format!("{}.png", i));
+        save_frame(frame, path)?;
+        path.pop();
+    }
+    path.push("sprite.txt");
+    let mut f = open_file(path)?;
+    write_sprite_txt(sprite, &mut f)?;
+    f.flush()?;
+    path.pop();
+    Ok(())
+}
+
+pub(crate) fn write_sprite_txt<F: Write>(sprite: &Sprite, f: &mut F) -> Result<()> {
+    writeln!(f, "frames={}", sprite.frames.len())?;
+    writeln!(f, "origin_x={}", sprite.origin_x)?;
+    writeln!(f, "origin_y={}", sprite.origin_y)?;
+    writeln!(f, "collision_shape={}", sprite.collision_shape)?;
+    writeln!(f, "alpha_tolerance={}", sprite.alpha_tolerance)?;
+    writeln!(f, "per_frame_colliders={}", sprite.per_frame_colliders as u8)?;
+    writeln!(f, "bbox_type={}", sprite.bbox_type)?;
+    writeln!(f, "bbox_left={}", sprite.bbox_left)?;
+    writeln!(f, "bbox_top={}", sprite.bbox_top)?;
+    writeln!(f, "bbox_right={}", sprite.bbox_right)?;
+    writeln!(f, "bbox_bottom={}", sprite.bbox_bottom)?;
+    Ok(())
+}
+
+fn save_background(back: &Background, path: &mut PathBuf, _project: &Project) -> Result<()> {
+    path.set_extension("png");
+    let frame = &back.frame;
+    if !frame.is_empty() {
+        save_frame(frame, path)?;
+    }
+    path.set_extension("txt");
+    let mut f = open_file(path)?;
+    write_background_txt(back, &mut f)?;
+    f.flush()?;
+    Ok(())
+}
+
+pub(crate) fn write_background_txt<F: Write>(back: &Background, f: &mut F) -> Result<()> {
+    writeln!(f, "exists={}", u8::from(!back.frame.is_empty()))?;
+    writeln!(f, "tileset={}", back.is_tileset as u8)?;
+    writeln!(f, "tile_width={}", back.tile_width)?;
+    writeln!(f, "tile_height={}", back.tile_height)?;
+    writeln!(f, "tile_hoffset={}", back.h_offset)?;
+    writeln!(f, "tile_voffset={}", back.v_offset)?;
+    writeln!(f, "tile_hsep={}", back.h_sep)?;
+    writeln!(f, "tile_vsep={}", back.v_sep)?;
+    Ok(())
+}
+
+fn save_path(path: &Path, file_path: &mut PathBuf, _project: &Project) -> Result<()> {
+    create_dirs(file_path)?;
+    file_path.push("path.txt");
+    let mut f = open_file(file_path)?;
+    write_path_txt(path, &mut f)?;
+    f.flush()?;
+    file_path.pop();
+    file_path.push("points.txt");
+    let mut f = open_file(file_path)?;
+    for p in &path.points {
+        writeln!(f, "{},{},{}", p.x, p.y, p.speed)?;
+    }
+    f.flush()?;
+    file_path.pop();
+    Ok(())
+}
+
+pub(crate) fn write_path_txt<F: Write>(path: &Path, f: &mut F) -> Result<()> {
+    writeln!(f, "connection={}", path.connection)?;
+    writeln!(f, "closed={}", path.closed as u8)?;
+    writeln!(f, "precision={}", path.precision)?;
+    writeln!(f, "background={}", path.background)?;
+    writeln!(f, "snap_x={}", path.snap_x)?;
+    writeln!(f, "snap_y={}", path.snap_y)?;
+    Ok(())
+}
+
+fn save_script(script: &Script, path: &mut PathBuf, _project: &Project) -> Result<()> {
+    path.set_extension("gml");
+    save_gml(path, &script.source)?;
+    Ok(())
+}
+
+fn save_font(font: &Font, path: &mut PathBuf, _project: &Project) -> Result<()> {
+    if let Some(file) = &font.file {
+        path.set_extension(&file.extension);
+        write_file(path, &file.data)?;
+    }
+    path.set_extension("txt");
+    let mut f = open_file(path)?;
+    write_font_txt(font, &mut f)?;
+    f.flush()?;
+    Ok(())
+}
+
+pub(crate) fn write_font_txt<F: Write>(font: &Font, f: &mut F) -> Result<()> {
+    writeln!(f, "name={}", font.sys_name)?;
+    writeln!(f, "size={}", font.size)?;
+    writeln!(f, "bold={}", font.bold as u8)?;
+    writeln!(f, "italic={}", font.italic as u8)?;
+    writeln!(f, "charset={}", font.charset)?;
+    writeln!(f, "aa_level={}", font.aa_level)?; // DOES NOT CORRESPOND TO .GMK OR .EXE
+    writeln!(f, "range_start={}", font.range_start)?;
+    writeln!(f, "range_end={}", font.range_end)?;
+    Ok(())
+}
+
+pub(crate) fn save_event<F: Write>(ev: &Event, name: &str, file: &mut F) -> Result<()> {
+    writeln!(file, "#define {}", name)?;
+    for action in &ev.actions {
+        writeln!(file, "{}", ACTION_TOKEN)?;
+        writeln!(file, "lib_id={}", action.lib_id)?;
+        writeln!(file, "action_id={}", action.id)?;
+        if let Some(relative) = action.is_relative {
+            writeln!(file, "relative={}", u8::from(relative))?;
+        }
+        if let Some(applies_to) = &action.applies_to {
+            writeln!(file, "applies_to={}", applies_to)?;
+        }
+        match &action.params {
+            ActionParams::None => (),
+            ActionParams::Normal { invert, args } => {
+                writeln!(file, "invert={}", u8::from(*invert))?;
+                for (i, arg) in args.iter().enumerate() {
+                    // params can have newlines so delimit
+                    writeln!(file, "arg{}={}", i, delimit(arg))?;
+                }
+            },
+            ActionParams::Repeat(repeats) => writeln!(file, "repeats={}", repeats)?,
+            ActionParams::Variable { name, value } => {
+                writeln!(file, "var_name={}", name)?;
+                writeln!(file, "var_value={}", value)?;
+            },
+        }
+        writeln!(file, "*/")?;
+        if !action.code.is_empty() {
+            write_gml(file, &escape_defines(&action.code))?;
+        }
+    }
+    Ok(())
+}
+
+fn save_timeline(tl: &Timeline, path: &mut PathBuf, _project: &Project) -> Result<()> {
+    path.set_extension("gml");
+    let mut f = open_file(path)?;
+    for (time, event) in &tl.moments {
+        if !event.actions.is_empty() {
+            save_event(event, &time.to_string(), &mut f)?;
+        }
+    }
+    f.flush()?;
+    Ok(())
+}
+
+/// Where an event goes in the IDE's event list, or None if it refers to an object or trigger that doesn't exist.
+pub fn event_index(project: &Project, ev_type: usize, ev_numb: &EventNumber) -> Option<usize> {
+    match (ev_type, ev_numb) {
+        (events::EV_COLLISION, EventNumber::Name(name)) => project.objects.index_of(name),
+        (events::EV_TRIGGER, EventNumber::Name(name)) => project.trigger_index(name),
+        (_, EventNumber::Number(n)) => Some(*n),
+        _ => None,
+    }
+}
+
+fn save_object(obj: &Object, path: &mut PathBuf, project: &Project) -> Result<()> {
+    path.set_extension("txt");
+    {
+        let mut f = open_file(path)?;
+        write_object_txt(obj, &mut f)?;
+        f.flush()?;
+    }
+    path.set_extension("gml");
+    {
+        let mut f = open_file(path)?;
+        for (ev_type, event_group) in obj.events.iter().enumerate() {
+            // same order as the ide would save them in
+            let sorted = event_group
+                .iter()
+                .filter(|(_, ev)| !ev.actions.is_empty())
+                .filter_map(|(numb, ev)| Some((event_index(project, ev_type, numb)?, numb, ev)))
+                .sorted_by_key(|(i, _, _)| *i);
+            for (_, ev_numb, ev) in sorted {
+                let name = format!("{}_{}", events::EVENT_NAMES[ev_type], ev_numb);
+                save_event(ev, &name, &mut f)?;
+            }
+        }
+        f.flush()?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_object_txt<F: Write>(obj: &Object, f: &mut F) -> Result<()> {
+    writeln!(f, "sprite={}", obj.sprite)?;
+    writeln!(f, "visible={}", u8::from(obj.visible))?;
+    writeln!(f, "solid={}", u8::from(obj.solid))?;
+    writeln!(f, "persistent={}", u8::from(obj.persistent))?;
+    writeln!(f, "depth={}", obj.depth)?;
+    writeln!(f, "parent={}", obj.parent)?;
+    writeln!(f, "mask={}", obj.mask)?;
+    Ok(())
+}
+
+fn save_tiles(tiles: &[Tile], path: &mut PathBuf, preserve_ids: bool) -> Result<()> {
+    let mut layers = HashMap::new();
+    for tile in tiles {
+        let f = match layers.entry(tile.depth) {
+            std::collections::hash_map::Entry::Occupied(e) => e.into_mut(),
+            std::collections::hash_map::Entry::Vacant(e) => {
+                path.push(format!("{}.txt", tile.depth));
+                let f = e.insert(open_file(path)?);
+                path.pop();
+                f
+            },
+        };
+        write!(
+            f,
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            quote_field(&tile.source_bg),
+            tile.x,
+            tile.y,
+            tile.u,
+            tile.v,
+            tile.width,
+            tile.height,
+            u8::from(tile.locked),
+            tile.xscale,
+            tile.yscale,
+            tile.blend,
+        )?;
+        if preserve_ids {
+            write!(f, ",{}", tile.id)?;
+        }
+        writeln!(f)?;
+    }
+    layers.values_mut().try_for_each(Write::flush)?;
+    path.push("layers.txt");
+    let mut f = open_file(path)?;
+    for depth in layers.keys().sorted() {
+        writeln!(f, "{}", depth)?;
+    }
+    f.flush()?;
+    path.pop();
+    Ok(())
+}
+
+fn save_instances(instances: &[Instance], path: &mut PathBuf, preserve_ids: bool) -> Result<()> {
+    path.push("instances.txt");
+    let mut f = open_file(path)?;
+    path.pop();
+    for instance in instances {
+        let mut code = Vec::with_capacity(instance.creation_code.len());
+        write_gml(&mut code, &instance.creation_code)?;
+        let fname = format!("{:08X}", instance.name);
+        if !code.is_empty() {
+            path.push(&fname);
+            path.set_extension("gml");
+            write_file(path, &code)?;
+            path.pop();
+        }
+        write!(
+            f,
+            "{},{},{},{},{},{},{},{},{},{}",
+            quote_field(&instance.object),
+            instance.x,
+            instance.y,
+            fname,
+            u8::from(instance.locked),
+            instance.xscale,
+            instance.yscale,
+            instance.blend,
+            instance.angle,
+            u8::from(!code.is_empty()),
+        )?;
+        if preserve_ids {
+            write!(f, ",{}", instance.id)?;
+        }
+        writeln!(f)?;
+    }
+    f.flush()?;
+    Ok(())
+}
+
+fn save_room(room: &Room, path: &mut PathBuf, project: &Project) -> Result<()> {
+    create_dirs(path)?;
+    path.push("room.txt");
+    {
+        let mut f = open_file(path)?;
+        write_room_txt(room, &mut f)?;
+        f.flush()?;
+    }
+    path.pop();
+    {
+        path.push("code.gml");
+        save_gml(path, &room.creation_code)?;
+        path.pop();
+    }
+
+    save_tiles(&room.tiles, path, project.preserve_ids)?;
+
+    save_instances(&room.instances, path, project.preserve_ids)?;
+    Ok(())
+}
+
+pub(crate) fn write_room_txt<F: Write>(room: &Room, f: &mut F) -> Result<()> {
+    writeln!(f, "caption={}", room.caption)?;
+    writeln!(f, "width={}", room.width)?;
+    writeln!(f, "height={}", room.height)?;
+    writeln!(f, "snap_x={}", room.snap_x)?;
+    writeln!(f, "snap_y={}", room.snap_y)?;
+    writeln!(f, "isometric={}", u8::from(room.isometric))?;
+    writeln!(f, "roomspeed={}", room.speed)?;
+    writeln!(f, "roompersistent={}", u8::from(room.persistent))?;
+    writeln!(f, "bg_color={}", room.bg_colour)?;
+    writeln!(f, "clear_screen={}", u8::from(room.clear_screen))?;
+    writeln!(f, "clear_view={}", u8::from(room.clear_view))?;
+    writeln!(f)?;
+    for (i, bg) in room.backgrounds.iter().enumerate() {
+        writeln!(f, "bg_visible{}={}", i, u8::from(bg.visible_on_start))?;
+        writeln!(f, "bg_is_foreground{}={}", i, u8::from(bg.is_foreground))?;
+        writeln!(f, "bg_source{}={}", i, bg.source_bg)?;
+        writeln!(f, "bg_xoffset{}={}", i, bg.xoffset)?;
+        writeln!(f, "bg_yoffset{}={}", i, bg.yoffset)?;
+        writeln!(f, "bg_tile_h{}={}", i, u8::from(bg.tile_horz))?;
+        writeln!(f, "bg_tile_v{}={}", i, u8::from(bg.tile_vert))?;
+        writeln!(f, "bg_hspeed{}={}", i, bg.hspeed)?;
+        writeln!(f, "bg_vspeed{}={}", i, bg.vspeed)?;
+        writeln!(f, "bg_stretch{}={}", i, u8::from(bg.stretch))?;
+    }
+    writeln!(f)?;
+    writeln!(f, "views_enabled={}", u8::from(room.views_enabled))?;
+    for (i, view) in room.views.iter().enumerate() {
+        writeln!(f, "view_visible{}={}", i, u8::from(view.visible))?;
+        writeln!(f, "view_xview{}={}", i, view.source_x)?;
+        writeln!(f, "view_yview{}={}", i, view.source_y)?;
+        writeln!(f, "view_wview{}={}", i, view.source_w)?;
+        writeln!(f, "view_hview{}={}", i, view.source_h)?;
+        writeln!(f, "view_xport{}={}", i, view.port_x)?;
+        writeln!(f, "view_yport{}={}", i, view.port_y)?;
+        writeln!(f, "view_wport{}={}", i, view.port_w)?;
+        writeln!(f, "view_hport{}={}", i, view.port_h)?;
+        writeln!(f, "view_fol_hbord{}={}", i, view.following_hborder)?;
+        writeln!(f, "view_fol_vbord{}={}", i, view.following_vborder)?;
+        writeln!(f, "view_fol_hspeed{}={}", i, view.following_hspeed)?;
+        writeln!(f, "view_fol_vspeed{}={}", i, view.following_vspeed)?;
+        writeln!(f, "view_fol_target{}={}", i, view.following_target)?;
+    }
+    writeln!(f)?;
+    writeln!(f, "remember={}", u8::from(room.remember_room_editor_info))?;
+    writeln!(f, "editor_width={}", room.editor_width)?;
+    writeln!(f, "editor_height={}", room.editor_height)?;
+    writeln!(f, "show_grid={}", u8::from(room.show_grid))?;
+    writeln!(f, "show_objects={}", u8::from(room.show_objects))?;
+    writeln!(f, "show_tiles={}", u8::from(room.show_tiles))?;
+    writeln!(f, "show_backgrounds={}", u8::from(room.show_backgrounds))?;
+    writeln!(f, "show_foregrounds={}", u8::from(room.show_foregrounds))?;
+    writeln!(f, "show_views={}", u8::from(room.show_views))?;
+    writeln!(f, "delete_underlying_objects={}", u8::from(room.delete_underlying_objects))?;
+    writeln!(f, "delete_underlying_tiles={}", u8::from(room.delete_underlying_tiles))?;
+    writeln!(f, "tab={}", room.tab)?; // wtf is this
+    writeln!(f, "editor_x={}", room.x_position_scroll)?;
+    writeln!(f, "editor_y={}", room.y_position_scroll)?;
+    Ok(())
+}
+
+fn save_constants(project: &Project, path: &mut PathBuf) -> Result<()> {
+    path.push("constants.txt");
+    let mut f = open_file(path)?;
+    path.pop();
+    for (name, value) in &project.constants {
+        writeln!(f, "{}={}", name, value)?;
+    }
+    f.flush()?;
+    Ok(())
+}
+
+fn save_settings(project: &Project, path: &mut PathBuf) -> Result<()> {
+    let settings = &project.settings;
+    path.push("settings");
+    create_dirs(path)?;
+    save_constants(project, path)?;
+    {
+        path.push("settings.txt");
+        let mut f = open_file(path)?;
+        path.pop();
+        write_settings_txt(settings, &mut f)?;
+        f.flush()?;
+    }
+    if settings.loading_bar == 2 {
+        if let Some(bg) = settings.loading_background.as_ref() {
+            path.push("back.bmp");
+            write_file(path, bg)?;
+            path.pop();
+        }
+        if let Some(fg) = settings.loading_foreground.as_ref() {
+            path.push("front.bmp");
+            write_file(path, fg)?;
+            path.pop();
+        }
+    }
+    if let Some(image) = settings.custom_load_image.as_ref() {
+        path.push("loader.bmp");
+        write_file(path, image)?;
+        path.pop();
+    }
+    path.push("icon.ico");
+    write_file(path, &settings.icon)?;
+    path.pop();
+    {
+        path.push("extensions.txt");
+        let mut f = open_file(path)?;
+        for extension in &project.extensions {
+            writeln!(f, "{}", extension)?;
+        }
+        f.flush()?;
+        path.pop();
+    }
+    save_game_information(&project.game_info, path)?;
+    path.pop();
+    Ok(())
+}
+
+pub(crate) fn write_settings_txt<F: Write>(settings: &Settings, f: &mut F) -> Result<()> {
+    writeln!(f, "fullscreen={}", u8::from(settings.fullscreen))?;
+    writeln!(f, "interpolate_pixels={}", u8::from(settings.interpolate_pixels))?;
+    writeln!(f, "dont_draw_border={}", u8::from(settings.dont_draw_border))?;
+    writeln!(f, "display_cursor={}", u8::from(settings.display_cursor))?;
+    writeln!(f, "scaling={}", settings.scaling)?;
+    writeln!(f, "allow_resize={}", u8::from(settings.allow_resize))?;
+    writeln!(f, "window_on_top={}", u8::from(settings.window_on_top))?;
+    writeln!(f, "clear_color={}", settings.clear_colour)?;
+    writeln!(f, "set_resolution={}", u8::from(settings.set_resolution))?;
+    writeln!(f, "color_depth={}", settings.colour_depth)?;
+    writeln!(f, "resolution={}", settings.resolution)?;
+    writeln!(f, "frequency={}", settings.frequency)?;
+    writeln!(f, "dont_show_buttons={}", u8::from(settings.dont_show_buttons))?;
+    writeln!(f, "vsync={}", u8::from(settings.vsync))?;
+    writeln!(f, "swap_creation_events={}", u8::from(settings.swap_creation_events))?;
+    writeln!(f, "disable_screensaver={}", u8::from(settings.disable_screensaver))?;
+    writeln!(f, "f4_fullscreen_toggle={}", u8::from(settings.f4_fullscreen))?;
+    writeln!(f, "f1_help_menu={}", u8::from(settings.f1_help))?;
+    writeln!(f, "esc_close_game={}", u8::from(settings.esc_close))?;
+    writeln!(f, "f5_save_f6_load={}", u8::from(settings.f5_save_f6_load))?;
+    writeln!(f, "f9_screenshot={}", u8::from(settings.f9_screenshot))?;
+    writeln!(f, "treat_close_as_esc={}", u8::from(settings.treat_close_as_esc))?;
+    writeln!(f, "priority={}", settings.priority)?;
+    writeln!(f, "freeze_on_lose_focus={}", u8::from(settings.freeze_on_lose_focus))?;
+    writeln!(f, "custom_loader={}", u8::from(settings.custom_load_image.is_some()))?;
+    writeln!(f, "custom_bar={}", settings.loading_bar)?;
+    writeln!(f, "bar_has_bg={}", u8::from(settings.loading_background.is_some()))?;
+    writeln!(f, "bar_has_fg={}", u8::from(settings.loading_foreground.is_some()))?;
+    writeln!(f, "transparent={}", u8::from(settings.loading_transparent))?;
+    writeln!(f, "translucency={}", settings.loading_translucency)?;
+    writeln!(f, "scale_progress_bar={}", u8::from(settings.loading_progress_bar_scale))?;
+    writeln!(f, "show_error_messages={}", u8::from(settings.show_error_messages))?;
+    writeln!(f, "log_errors={}", u8::from(settings.log_errors))?;
+    writeln!(f, "always_abort={}", u8::from(settings.always_abort))?;
+    writeln!(f, "zero_uninitialized_vars={}", u8::from(settings.zero_uninitialized_vars))?;
+    writeln!(f, "error_on_uninitialized_args={}", u8::from(settings.error_on_uninitialized_args))?;
+    Ok(())
+}
+
+fn save_triggers(project: &Project, path: &mut PathBuf) -> Result<()> {
+    path.push("triggers");
+    create_dirs(path)?;
+    let triggers = &project.triggers;
+    check_triggers(project)?;
+    {
+        let mut index = Vec::with_capacity(triggers.len());
+        for trigger in triggers {
+            writeln!(index, "{}", trigger.as_ref().map(|t| t.name.as_str()).unwrap_or_default())?;
+        }
+        path.push("index.yyd");
+        write_file(path, index)?;
+        path.pop();
+    }
+    for trigger in triggers.iter().flatten() {
+        path.push(&trigger.name);
+        path.set_extension("txt");
+        {
+            let mut f = open_file(path)?;
+            writeln!(f, "constant={}", trigger.constant_name)?;
+            writeln!(f, "kind={}", trigger.kind)?;
+            f.flush()?;
+        }
+        path.set_extension("gml");
+        save_gml(path, &trigger.condition)?;
+        path.pop();
+    }
+    path.pop();
+    Ok(())
+}
+
+/// Checks that every asset has a valid, unique name, returning how many assets there are.
+pub fn check_names<T>(assets: &AssetList<T>) -> Result<usize> {
+    let mut name_set = HashSet::with_capacity(assets.names.len());
+    for name in assets.iter().map(|(name, _)| name) {
+        if let Some(c) = filename_invalid(name) {
+            return Err(Error::BadAssetName(name.to_string(), char::from(c)))
+        }
+        if !name_set.insert(make_unicase(name.to_string())) {
+            return Err(Error::DuplicateAsset(name.to_string()))
+        }
+    }
+    Ok(name_set.len())
+}
+
+fn check_triggers(project: &Project) -> Result<()> {
+    let mut name_set = HashSet::with_capacity(project.triggers.len());
+    for trigger in project.triggers.iter().flatten() {
+        if let Some(c) = filename_invalid(&trigger.name) {
+            return Err(Error::BadTriggerName(trigger.name.clone(), char::from(c)))
+        }
+        if !name_set.insert(make_unicase(trigger.name.clone())) {
+            return Err(Error::DuplicateTrigger(trigger.name.clone()))
+        }
+    }
+    Ok(())
+}
+
+fn check_included_files(project: &Project) -> Result<()> {
+    let mut names_set = HashSet::with_capacity(project.included_files.len());
+    for file in &project.included_files {
+        let name = &file.file_name;
+        if let Some(c) = filename_invalid(name) {
+            return Err(Error::BadIncludedFileName(name.clone(), char::from(c)))
+        }
+        if !names_set.insert(name) {
+            return Err(Error::DuplicateIncludedFile(name.clone()))
+        }
+    }
+    Ok(())
+}
+
+/// Checks that no two instances or tiles share an id, ignoring any that haven't been given one.
+pub fn check_ids(rooms: &AssetList<Room>) -> Result<()> {
+    let mut instance_ids = HashSet::new();
+    let mut tile_ids = HashSet::new();
+    for (name, room) in rooms.iter() {
+        for id in room.instances.iter().map(|i| i.id).filter(|&id| id != 0) {
+            if !instance_ids.insert(id) {
+                return Err(Error::DuplicateId("instance", id, name.to_string()))
+            }
+        }
+        for id in room.tiles.iter().map(|t| t.id).filter(|&id| id != 0) {
+            if !tile_ids.insert(id) {
+                return Err(Error::DuplicateId("tile", id, name.to_string()))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs every check that saving would, without writing anything.
+pub fn check_project(project: &Project) -> Result<()> {
+    check_triggers(project)?;
+    check_names(&project.sounds)?;
+    check_names(&project.sprites)?;
+    check_names(&project.backgrounds)?;
+    check_names(&project.paths)?;
+    check_names(&project.scripts)?;
+    check_names(&project.fonts)?;
+    check_names(&project.timelines)?;
+    check_names(&project.objects)?;
+    check_names(&project.rooms)?;
+    check_ids(&project.rooms)?;
+    check_included_files(project)?;
+    Ok(())
+}
+
+/// Writes an asset type's `timestamps.yyd`. It's sorted by name so that it merges cleanly,
+/// and assets without a known timestamp are left out.
+pub fn write_timestamps<'a>(path: &std::path::Path, entries: impl IntoIterator<Item = (&'a str, f64)>) -> Result<()> {
+    let mut out = Vec::new();
+    for (name, timestamp) in entries.into_iter().filter(|(_, t)| *t != 0.0).sorted_by(|a, b| a.0.cmp(b.0)) {
+        writeln!(out, "{}={}", name, timestamp)?;
+    }
+    write_file(path, out)
+}
+
+fn save_assets<T: Sync>(
+    name: &str,
+    assets: &AssetList<T>,
+    save_func: fn(&T, &mut PathBuf, &Project) -> Result<()>,
+    project: &Project,
+    path: &mut PathBuf,
+) -> Result<()> {
+    path.push(name);
+    create_dirs(path)?;
+    check_names(assets)?;
+    {
+        let mut index = Vec::with_capacity(assets.names.len());
+        for (name, asset) in assets.names.iter().zip(&assets.assets) {
+            // empty slots are written as empty lines
+            writeln!(&mut index, "{}", if asset.is_some() { name.as_str() } else { "" })?;
+        }
+        path.push("index.yyd");
+        write_file(path, index)?;
+        path.pop();
+    }
+    path.push("timestamps.yyd");
+    write_timestamps(
+        path,
+        assets
+            .names
+            .iter()
+            .zip(&assets.assets)
+            .zip(&assets.timestamps)
+            .filter_map(|((n, a), t)| a.as_ref().map(|_| (n.as_str(), *t))),
+    )?;
+    path.pop();
+    (&assets.assets, &assets.names).into_par_iter().try_for_each(|(asset, name)| -> Result<()> {
+        if let Some(asset) = asset {
+            let mut p = path.join(name);
+            save_func(asset, &mut p, project)?;
+        }
+        Ok(())
+    })?;
+    path.push("tree.yyd");
+    {
+        let mut f = open_file(path)?;
+        write_tree_children(&assets.tree, &mut String::new(), &mut f)?;
+        f.flush()?;
+    }
+    path.pop();
+    path.pop();
+    Ok(())
+}
+
+fn save_included_files(project: &Project, path: &mut PathBuf) -> Result<()> {
+    path.push("datafiles");
+    path.push("include");
+    create_dirs(path)?;
+    path.pop();
+    let files = &project.included_files;
+    check_included_files(project)?;
+    {
+        let mut index = Vec::with_capacity(files.len());
+        for file in files {
+            writeln!(index, "{}", file.file_name)?;
+        }
+        path.push("index.yyd");
+        write_file(path, index)?;
+        path.pop();
+    }
+    for file in files {
+        let mut name = file.file_name.clone();
+        path.push("include");
+        path.push(&name);
+        if let Some(data) = file.data.as_ref() {
+            write_file(path, data)?;
+        } else if !transaction::keep(path)? && file.source_path.exists() {
+            // try to copy it to gmk dir if not already done
+            std::fs::copy(&file.source_path, &path).map_err(|e| Error::FileIoError(e, path.to_path_buf()))?;
+        }
+        path.pop();
+        path.pop();
+        name += ".txt";
+        path.push(&name);
+        let mut f = open_file(path)?;
+        writeln!(f, "store={}", u8::from(file.stored_in_gmk))?;
+        writeln!(f, "free={}", u8::from(file.free_memory))?;
+        writeln!(f, "overwrite={}", u8::from(file.overwrite_file))?;
+        writeln!(f, "remove={}", u8::from(file.remove_at_end))?;
+        writeln!(f, "export={}", file.export_setting)?;
+        if file.export_setting == 3 {
+            writeln!(f, "export_folder={}", file.export_custom_folder)?;
+        }
+        f.flush()?;
+        path.pop();
+    }
+    path.pop();
+    Ok(())
+}
+
+fn save_game_information(info: &GameInformation, path: &mut PathBuf) -> Result<()> {
+    path.push("game_information.txt");
+    {
+        let mut f = open_file(path)?;
+        write_game_information_txt(info, &mut f)?;
+        f.flush()?;
+    }
+    path.set_extension("rtf");
+    write_file(path, &info.rtf)?;
+    path.pop();
+    Ok(())
+}
+
+pub(crate) fn write_game_information_txt<F: Write>(info: &GameInformation, f: &mut F) -> Result<()> {
+    writeln!(f, "color={}", info.colour)?;
+    writeln!(f, "new_window={}", u8::from(info.new_window))?;
+    writeln!(f, "caption={}", info.caption)?;
+    writeln!(f, "left={}", info.left)?;
+    writeln!(f, "top={}", info.top)?;
+    writeln!(f, "width={}", info.width)?;
+    writeln!(f, "height={}", info.height)?;
+    writeln!(f, "border={}", u8::from(info.border))?;
+    writeln!(f, "resizable={}", u8::from(info.resizable))?;
+    writeln!(f, "window_on_top={}", u8::from(info.window_on_top))?;
+    writeln!(f, "freeze_game={}", u8::from(info.freeze_game))?;
+    Ok(())
+}
+
+pub(crate) fn write_tree_children<F: Write>(nodes: &[TreeNode], tabs: &mut String, f: &mut F) -> Result<()> {
+    for node in nodes {
+        match node {
+            TreeNode::Folder(name, children) => {
+                writeln!(f, "{}+{}", tabs, name)?;
+                tabs.push('\t');
+                write_tree_children(children, tabs, f)?;
+                tabs.pop();
+            },
+            TreeNode::Asset(name) => writeln!(f, "{}|{}", tabs, name)?,
+        }
+    }
+    Ok(())
+}
+
+fn save_icon_cache(project: &Project, path: &mut PathBuf) -> Result<()> {
+    const BMP_HEADER: &[u8] = include_bytes!("../../assets/thumb_header.dat");
+    const BMP_SIZE: usize = 16 * 16 * 4 + BMP_HEADER.len();
+    fn save_frame(frame: &Frame, name: &str, path: &std::path::Path) -> Result<()> {
+        if frame.is_empty() {
+            return Ok(())
+        }
+        let mut path = path.join(name);
+        path.set_extension("bmp");
+        let mut out = vec![0; BMP_SIZE];
+        out[..BMP_HEADER.len()].copy_from_slice(BMP_HEADER);
+        frame.thumb(&mut out[BMP_HEADER.len()..], true, [255, 255, 255]);
+        write_file(&path, out)
+    }
+    path.push("cache");
+    path.push("sprites");
+    create_dirs(path)?;
+    project.sprites.iter().par_bridge().try_for_each(|(name, sprite)| -> Result<()> {
+        if let Some(frame) = sprite.frames.first() {
+            save_frame(frame, name, path)?;
+        }
+        Ok(())
+    })?;
+    path.pop();
+    path.push("backgrounds");
+    create_dirs(path)?;
+    project.backgrounds.iter().par_bridge().try_for_each(|(name, bg)| save_frame(&bg.frame, name, path))?;
+    path.pop();
+    path.pop();
+    Ok(())
+}
+
+/// Derives a name for an instance from its room, object and position, and how many instances in the room
+/// with the same object and position come before it. If that's taken, the count is bumped until it isn't,
+/// so the same instances come out with the same names on every machine.
+pub fn derive_instance_name(
+    room: &str,
+    object: &str,
+    x: i32,
+    y: i32,
+    mut counter: u32,
+    taken: impl Fn(u32) -> bool,
+) -> u32 {
+    loop {
+        let name = xxh3_64(format!("{}\0{}\0{}\0{}\0{}", room, object, x, y, counter).as_bytes()) as u32;
+        if name != 0 && !taken(name) {
+            break name
+        }
+        counter += 1;
+    }
+}
+
+/// Gives a name to every instance that doesn't have one yet, making sure names are unique across the project.
+pub fn assign_instance_names(project: &mut Project) {
+    let mut taken: HashSet<u32> =
+        project.rooms.iter().flat_map(|(_, r)| &r.instances).map(|i| i.name).filter(|&n| n != 0).collect();
+    for (room_name, room) in project.rooms.iter_mut() {
+        let mut counters = HashMap::new();
+        for instance in room.instances.iter_mut() {
+            let counter = counters.entry((instance.object.clone(), instance.x, instance.y)).or_insert(0);
+            if instance.name == 0 {
+                instance.name =
+                    derive_instance_name(room_name, &instance.object, instance.x, instance.y, *counter, |n| {
+                        taken.contains(&n)
+                    });
+                taken.insert(instance.name);
+            }
+            *counter += 1;
+        }
+    }
+}
+
+/// Gives an id to every instance and tile that doesn't have one yet, the same way building the game would.
+pub fn assign_instance_ids(project: &mut Project) {
+    let (room_ids, _) = crate::gm81::instance_ids(&project.rooms);
+    for (room, (instance_ids, tile_ids)) in project.rooms.assets.iter_mut().zip(room_ids) {
+        if let Some(room) = room {
+            room.instances.iter_mut().zip(instance_ids).for_each(|(i, id)| i.id = id);
+            room.tiles.iter_mut().zip(tile_ids).for_each(|(t, id)| t.id = id);
+        }
+    }
+}
+
+/// Saves a project, given the path of its .gm82 file. Instances without a name get one first,
+/// and if the project preserves ids, so do instances and tiles without an id.
+/// Nothing in the project directory changes unless the whole save goes through,
+/// and then any files left over from deleted or renamed assets are removed. Returns what was removed.
+pub fn save_gmk(project: &mut Project, path: impl Into<PathBuf>) -> Result<Vec<String>> {
+    stage(project, path.into(), Transaction::begin)?.commit()
+}
+
+/// Lists the files and folders that saving the project would remove, without saving it or changing anything else.
+/// Folders end in a / and come after everything in them.
+pub fn find_orphans(project: &mut Project, path: impl Into<PathBuf>) -> Result<Vec<String>> {
+    stage(project, path.into(), Transaction::preview)?.orphans()
+}
+
+/// Checks that every file saving the project would write fits in Windows' path length limit
+/// if the project's directory was `root`, given the name of its .gm82 file. Nothing gets written.
+pub fn check_path_lengths(project: &mut Project, file_name: &str, root: &std::path::Path) -> Result<()> {
+    assign_instance_names(project);
+    check_project(project)?;
+    check_ids(&project.rooms)?;
+    transaction::check_lengths(root, &saved_files(project, file_name))
+}
+
+/// Lists the files saving the project would write, relative to its directory, the same way a staged save lists them.
+/// Nothing is read or written, so included files are listed even if there's nothing to copy for them,
+/// and instances need their names first, like `check_path_lengths` gives them.
+pub fn saved_files(project: &Project, file_name: &str) -> Vec<String> {
+    fn rel(path: &std::path::Path) -> String {
+        path.components().map(|c| c.as_os_str().to_string_lossy()).join("/")
+    }
+    fn add<T>(
+        files: &mut Vec<String>,
+        dir: &str,
+        assets: &AssetList<T>,
+        asset_files: impl Fn(&T, PathBuf) -> Vec<PathBuf>,
+    ) {
+        let dir = std::path::Path::new(dir);
+        for name in ["index.yyd", "timestamps.yyd", "tree.yyd"] {
+            files.push(rel(&dir.join(name)));
+        }
+        for (name, asset) in assets.iter() {
+            files.extend(asset_files(asset, dir.join(name)).iter().map(|p| rel(p)));
+        }
+    }
+    fn with_extension(mut path: PathBuf, extension: &str) -> PathBuf {
+        path.set_extension(extension);
+        path
+    }
+    // text that goes into file names gets escaped the same way as when it's saved
+    let escape = |s: &str| if project.escaped_utf16 && s.contains("\\u{") { escape_text(s) } else { s.to_string() };
+    let settings = &project.settings;
+    let mut files = vec![file_name.to_string()];
+    files.extend(
+        ["constants.txt", "settings.txt", "icon.ico", "extensions.txt", "game_information.txt", "game_information.rtf"]
+            .map(|f| format!("settings/{}", f)),
+    );
+    if settings.loading_bar == 2 && settings.loading_background.is_some() {
+        files.push("settings/back.bmp".into());
+    }
+    if settings.loading_bar == 2 && settings.loading_foreground.is_some() {
+        files.push("settings/front.bmp".into());
+    }
+    if settings.custom_load_image.is_some() {
+        files.push("settings/loader.bmp".into());
+    }
+    if !project.triggers.is_empty() {
+        files.push("triggers/index.yyd".into());
+        for trigger in project.triggers.iter().flatten() {
+            let path = std::path::Path::new("triggers").join(&trigger.name);
+            files.push(rel(&with_extension(path.clone(), "txt")));
+            files.push(rel(&with_extension(path, "gml")));
+        }
+    }
+    if project.sounds.has_any() {
+        add(&mut files, "sounds", &project.sounds, |sound, path| {
+            let mut out = vec![with_extension(path.clone(), "txt")];
+            if sound.data.is_some() {
+                out.push(with_extension(path, escape(&sound.extension).trim_matches('.')));
+            }
+            out
+        });
+    }
+    if project.sprites.has_any() {
+        add(&mut files, "sprites", &project.sprites, |sprite, path| {
+            (0..sprite.frames.len()).map(|i| path.join(format!("{}.png", i))).chain([path.join("sprite.txt")]).collect()
+        });
+    }
+    if project.backgrounds.has_any() {
+        add(&mut files, "backgrounds", &project.backgrounds, |back, path| {
+            let mut out = vec![with_extension(path.clone(), "txt")];
+            if !back.frame.is_empty() {
+                out.push(with_extension(path, "png"));
+            }
+            out
+        });
+    }
+    if project.paths.has_any() {
+        add(&mut files, "paths", &project.paths, |_, path| vec![path.join("path.txt"), path.join("points.txt")]);
+    }
+    if project.scripts.has_any() {
+        add(&mut files, "scripts", &project.scripts, |_, path| vec![with_extension(path, "gml")]);
+    }
+    if project.fonts.has_any() {
+        add(&mut files, "fonts", &project.fonts, |font, path| {
+            let mut out = vec![with_extension(path.clone(), "txt")];
+            if let Some(file) = &font.file {
+                out.push(with_extension(path, &file.extension));
+            }
+            out
+        });
+    }
+    if project.timelines.has_any() {
+        add(&mut files, "timelines", &project.timelines, |_, path| vec![with_extension(path, "gml")]);
+    }
+    if project.objects.has_any() {
+        add(&mut files, "objects", &project.objects, |_, path| {
+            vec![with_extension(path.clone(), "txt"), with_extension(path, "gml")]
+        });
+    }
+    add(&mut files, "rooms", &project.rooms, |room, path| {
+        let mut out = ["room.txt", "code.gml", "layers.txt", "instances.txt"].map(|f| path.join(f)).to_vec();
+        out.extend(room.tiles.iter().map(|t| t.depth).unique().map(|depth| path.join(format!("{}.txt", depth))));
+        out.extend(
+            room.instances
+                .iter()
+                .filter(|i| !i.creation_code.trim_end().is_empty())
+                .map(|i| path.join(format!("{:08X}.gml", i.name))),
+        );
+        out
+    });
+    if !project.included_files.is_empty() {
+        files.push("datafiles/index.yyd".into());
+        for file in &project.included_files {
+            files.push(format!("datafiles/include/{}", file.file_name));
+            files.push(format!("datafiles/{}.txt", file.file_name));
+        }
+    }
+    for (name, sprite) in project.sprites.iter() {
+        if sprite.frames.first().is_some_and(|f| !f.is_empty()) {
+            files.push(rel(&with_extension(std::path::Path::new("cache/sprites").join(name), "bmp")));
+        }
+    }
+    for (name, back) in project.backgrounds.iter() {
+        if !back.frame.is_empty() {
+            files.push(rel(&with_extension(std::path::Path::new("cache/backgrounds").join(name), "bmp")));
+        }
+    }
+    files.sort();
+    files
+}
+
+fn stage(project: &mut Project, mut path: PathBuf, begin: fn(PathBuf) -> Result<Transaction>) -> Result<Transaction> {
+    assign_instance_names(project);
+    if project.preserve_ids {
+        assign_instance_ids(project);
+    }
+    check_ids(&project.rooms)?;
+    let file_name = path.file_name().map(OsString::from).unwrap_or_else(|| ".gm82".into());
+    path.pop();
+    let transaction = begin(path)?;
+    // escaping in place and back again is quicker than copying everything, and leaves the text as it was
+    if project.escaped_utf16 {
+        project.for_each_text(|text| {
+            if text.contains("\\u{") {
+                *text = escape_text(text)
+            }
+        });
+    }
+    let result = save_staged(project, &mut transaction.staging().join(file_name));
+    if project.escaped_utf16 {
+        project.for_each_text(|text| {
+            if text.contains("\\u{") {
+                *text = unescape_text(text)
+            }
+        });
+    }
+    result?;
+    Ok(transaction)
+}
+
+fn save_staged(project: &Project, path: &mut PathBuf) -> Result<()> {
+    // check if we have any assets to save
+    let has_backgrounds = project.backgrounds.has_any();
+    let has_datafiles = !project.included_files.is_empty();
+    let has_fonts = project.fonts.has_any();
+    let has_objects = project.objects.has_any();
+    let has_paths = project.paths.has_any();
+    let has_scripts = project.scripts.has_any();
+    let has_sounds = project.sounds.has_any();
+    let has_sprites = project.sprites.has_any();
+    let has_timelines = project.timelines.has_any();
+    let has_triggers = !project.triggers.is_empty();
+    {
+        if let Some(parent) = path.parent() {
+            create_dirs(parent)?;
+        }
+        // some stuff to go in the main gmk
+        let settings = &project.settings;
+        let mut f = open_file(path)?;
+        // older projects stay at the version before escaping, since their text would read differently
+        writeln!(f, "gm82_version={}", if project.escaped_utf16 { GM82_VERSION } else { 5 })?;
+        writeln!(f, "gameid={}", project.game_id)?;
+        writeln!(f)?;
+        writeln!(f, "info_author={}", settings.info_author)?;
+        writeln!(f, "info_version={}", settings.info_version)?;
+        writeln!(f, "info_information={}", delimit(&settings.info_information))?;
+        writeln!(f)?;
+        writeln!(f, "exe_company={}", settings.exe_company)?;
+        writeln!(f, "exe_product={}", settings.exe_product)?;
+        writeln!(f, "exe_copyright={}", settings.exe_copyright)?;
+        writeln!(f, "exe_description={}", settings.exe_description)?;
+        writeln!(
+            f,
+            "exe_version={}.{}.{}.{}",
+            settings.version_major, settings.version_minor, settings.version_release, settings.version_build
+        )?;
+        writeln!(f)?;
+        writeln!(f, "has_backgrounds={}", u8::from(has_backgrounds))?;
+        writeln!(f, "has_datafiles={}", u8::from(has_datafiles))?;
+        writeln!(f, "has_fonts={}", u8::from(has_fonts))?;
+        writeln!(f, "has_objects={}", u8::from(has_objects))?;
+        writeln!(f, "has_paths={}", u8::from(has_paths))?;
+        writeln!(f, "has_scripts={}", u8::from(has_scripts))?;
+        writeln!(f, "has_sounds={}", u8::from(has_sounds))?;
+        writeln!(f, "has_sprites={}", u8::from(has_sprites))?;
+        writeln!(f, "has_timelines={}", u8::from(has_timelines))?;
+        writeln!(f, "has_triggers={}", u8::from(has_triggers))?;
+        if project.preserve_ids {
+            let (_, (last_instance_id, last_tile_id)) = crate::gm81::instance_ids(&project.rooms);
+            writeln!(f)?;
+            writeln!(f, "preserve_ids=1")?;
+            writeln!(f, "last_instance_id={}", last_instance_id)?;
+            writeln!(f, "last_tile_id={}", last_tile_id)?;
+        }
+        f.flush()?;
+    }
+    path.pop();
+    save_settings(project, path)?;
+    if has_triggers {
+        save_triggers(project, path)?;
+    }
+    if has_sounds {
+        save_assets("sounds", &project.sounds, save_sound, project, path)?;
+    }
+    if has_sprites {
+        save_assets("sprites", &project.sprites, save_sprite, project, path)?;
+    }
+    if has_backgrounds {
+        save_assets("backgrounds", &project.backgrounds, save_background, project, path)?;
+    }
+    if has_paths {
+        save_assets("paths", &project.paths, save_path, project, path)?;
+    }
+    if has_scripts {
+        save_assets("scripts", &project.scripts, save_script, project, path)?;
+    }
+    if has_fonts {
+        save_assets("fonts", &project.fonts, save_font, project, path)?;
+    }
+    if has_timelines {
+        save_assets("timelines", &project.timelines, save_timeline, project, path)?;
+    }
+    if has_objects {
+        save_assets("objects", &project.objects, save_object, project, path)?;
+    }
+    save_assets("rooms", &project.rooms, save_room, project, path)?;
+    if has_datafiles {
+        save_included_files(project, path)?;
+    }
+    save_icon_cache(project, path)?;
+    Ok(())
+}
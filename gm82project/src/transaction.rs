@@ -0,0 +1,321 @@
+// Saves go through here so that a failed or interrupted save never leaves a project half-written.
+// Everything gets written into a staging directory inside the project first, laid out the same way.
+// Once the whole save has gone through, the staged files are listed in a journal and then moved into place.
+// If that gets interrupted, the journal is enough to finish the job the next time the project is saved or loaded.
+// Files that come out the same as last time, going by the manifest, aren't moved at all.
+// Anything in the project's own folders that the save didn't write is left over from deleted or renamed assets,
+// so it gets removed along with the moves.
+
+use crate::{
+    Error, Result,
+    manifest::{MANIFEST_PATH, Manifest},
+};
+use std::{
+    collections::HashSet,
+    io::Write,
+    path::{Path, PathBuf},
+};
+use unicase::UniCase;
+
+pub const STAGING_DIR: &str = ".gm82_staging";
+pub const JOURNAL_FILE: &str = ".gm82_journal";
+
+/// Windows' limit on the length of a path, counting the null at the end. Plenty of tools on Windows, Git included,
+/// can't handle anything longer unless they've been set up to.
+pub const MAX_PATH: usize = 260;
+
+/// The folders a save writes everything into. The project's directory can have other things in it, these can't.
+/// Hidden files like .gitignore are left alone even in here.
+pub const PROJECT_DIRS: &[&str] = &[
+    "backgrounds",
+    "cache",
+    "datafiles",
+    "fonts",
+    "objects",
+    "paths",
+    "rooms",
+    "scripts",
+    "settings",
+    "sounds",
+    "sprites",
+    "timelines",
+    "triggers",
+];
+
+/// A save in progress. Files written under `staging()` replace the project's files when it's committed,
+/// and get thrown away if it's dropped before that.
+pub struct Transaction {
+    dir: PathBuf,
+    staging: PathBuf,
+    preview: bool,
+}
+
+impl Transaction {
+    /// Starts a save into the project directory `dir`, first cleaning up after any earlier save that didn't finish.
+    pub fn begin(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = project_dir(dir.into())?;
+        recover(&dir)?;
+        let staging = dir.join(STAGING_DIR);
+        std::fs::create_dir_all(&staging).map_err(|e| Error::DirIoError(e, staging.clone()))?;
+        Ok(Self { dir, staging, preview: false })
+    }
+
+    /// Starts a save that's only staged to see what it would do, and can't be committed. It doesn't clean up after
+    /// an earlier save, so the project is left as it was, which means it fails if there's anything to clean up.
+    pub fn preview(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = project_dir(dir.into())?;
+        if needs_recovery(&dir) {
+            return Err(Error::UnfinishedSave(dir))
+        }
+        let staging = dir.join(STAGING_DIR);
+        std::fs::create_dir_all(&staging).map_err(|e| Error::DirIoError(e, staging.clone()))?;
+        Ok(Self { dir, staging, preview: true })
+    }
+
+    pub fn staging(&self) -> &Path {
+        &self.staging
+    }
+
+    /// Lists what committing would remove from the project, because nothing staged replaces it.
+    /// Directories end in a / and come after everything in them.
+    pub fn orphans(&self) -> Result<Vec<String>> {
+        let mut entries = Vec::new();
+        list_staged(&self.staging, "", &mut entries)?;
+        check_lengths(&self.dir, &entries)?;
+        find_orphans(&self.dir, &entries)
+    }
+
+    /// Moves everything that was staged into the project, apart from files that are the same as what's already there,
+    /// and removes whatever's left over in the project's folders. Returns what was removed, like [`Self::orphans`].
+    pub fn commit(self) -> Result<Vec<String>> {
+        if self.preview {
+            return Err(Error::Other("a preview of a save can't be committed".into()))
+        }
+        let mut entries = Vec::new();
+        list_staged(&self.staging, "", &mut entries)?;
+        check_lengths(&self.dir, &entries)?;
+        let orphans = find_orphans(&self.dir, &entries)?;
+        let mut manifest = Manifest::load(&self.dir);
+        let mut changed = Vec::with_capacity(entries.len());
+        for entry in entries {
+            if !entry.ends_with('/') && entry != MANIFEST_PATH {
+                let (staged, target) = (self.staging.join(&entry), self.dir.join(&entry));
+                if !manifest.update(&entry, &staged, &target)? {
+                    std::fs::remove_file(&staged).map_err(|e| Error::FileIoError(e, staged))?;
+                    continue
+                }
+            }
+            changed.push(entry);
+        }
+        // forget about files that are gone or about to be, unless they're about to be written again
+        let written = changed.iter().collect::<HashSet<_>>();
+        let removed = orphans.iter().collect::<HashSet<_>>();
+        manifest
+            .0
+            .retain(|name, _| written.contains(name) || (!removed.contains(name) && self.dir.join(name).exists()));
+        let manifest_path = self.staging.join(MANIFEST_PATH);
+        if let Some(parent) = manifest_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| Error::DirIoError(e, parent.to_path_buf()))?;
+        }
+        manifest.save(&manifest_path)?;
+        let mut entries = changed;
+        if !entries.iter().any(|e| e == MANIFEST_PATH) {
+            entries.push(MANIFEST_PATH.to_string());
+        }
+        // once the journal is there, the save counts as done even if the moves don't all happen now
+        let journal = self.dir.join(JOURNAL_FILE);
+        let journal_tmp = journal.with_extension("tmp");
+        {
+            let mut f = std::fs::File::create(&journal_tmp).map_err(|e| Error::FileIoError(e, journal_tmp.clone()))?;
+            for entry in &entries {
+                writeln!(f, "{}", entry)?;
+            }
+            // a blank line, then what gets removed
+            writeln!(f)?;
+            for orphan in &orphans {
+                writeln!(f, "{}", orphan)?;
+            }
+            f.sync_all()?;
+        }
+        std::fs::rename(&journal_tmp, &journal).map_err(|e| Error::FileIoError(e, journal.clone()))?;
+        replay(&self.dir, &entries, &orphans)?;
+        finish(&self.dir)?;
+        Ok(orphans)
+    }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        // if this was committed there's nothing left, otherwise nothing staged is wanted anymore
+        if !self.dir.join(JOURNAL_FILE).exists() {
+            let _ = std::fs::remove_dir_all(&self.staging);
+        }
+    }
+}
+
+/// Lists everything under `dir`, relative to the staging directory with / as the separator.
+/// Directories end in a / so empty ones get created too.
+/// Makes the project directory absolute, so path lengths can be checked against it.
+/// It's empty when the project file was given without a directory, which means the current one.
+fn project_dir(dir: PathBuf) -> Result<PathBuf> {
+    let dir = if dir.as_os_str().is_empty() { PathBuf::from(".") } else { dir };
+    std::path::absolute(&dir).map_err(|e| Error::DirIoError(e, dir))
+}
+
+fn list_staged(dir: &Path, prefix: &str, entries: &mut Vec<String>) -> Result<()> {
+    let mut children = std::fs::read_dir(dir)
+        .map_err(|e| Error::DirIoError(e, dir.to_path_buf()))?
+        .map(|e| e.map_err(|e| Error::DirIoError(e, dir.to_path_buf())))
+        .collect::<Result<Vec<_>>>()?;
+    children.sort_by_key(|e| e.file_name());
+    for child in children {
+        let name = child.file_name().into_string().map_err(|s| Error::UnicodeError(s.to_string_lossy().into()))?;
+        let rel = format!("{}{}", prefix, name);
+        if child.file_type()?.is_dir() {
+            entries.push(format!("{}/", rel));
+            list_staged(&child.path(), &format!("{}/", rel), entries)?;
+        } else {
+            entries.push(rel);
+        }
+    }
+    Ok(())
+}
+
+/// Checks that every entry fits in [`MAX_PATH`] under `root`, going by UTF-16 like Windows does.
+/// `root` doesn't have to be where the project is, such as where someone else has it checked out on Windows.
+pub fn check_lengths(root: &Path, entries: &[String]) -> Result<()> {
+    let root_len = root.to_string_lossy().trim_end_matches(['/', '\\']).encode_utf16().count();
+    for entry in entries {
+        // one more for the separator after the root
+        let len = root_len + 1 + entry.trim_end_matches('/').encode_utf16().count();
+        if len + 1 > MAX_PATH {
+            return Err(Error::PathTooLong(entry.clone(), len))
+        }
+    }
+    Ok(())
+}
+
+/// Lists everything in the project's folders that isn't in `entries`, in the order it should be removed in.
+fn find_orphans(dir: &Path, entries: &[String]) -> Result<Vec<String>> {
+    fn walk(dir: &Path, prefix: &str, owned: &HashSet<UniCase<&str>>, orphans: &mut Vec<String>) -> Result<()> {
+        let mut children = std::fs::read_dir(dir)
+            .map_err(|e| Error::DirIoError(e, dir.to_path_buf()))?
+            .map(|e| e.map_err(|e| Error::DirIoError(e, dir.to_path_buf())))
+            .collect::<Result<Vec<_>>>()?;
+        children.sort_by_key(|e| e.file_name());
+        for child in children {
+            let Ok(name) = child.file_name().into_string() else { continue };
+            if name.starts_with('.') {
+                continue
+            }
+            if child.file_type()?.is_dir() {
+                let rel = format!("{}{}/", prefix, name);
+                walk(&child.path(), &rel, owned, orphans)?;
+                if !owned.contains(&UniCase::new(rel.as_str())) {
+                    orphans.push(rel);
+                }
+            } else {
+                let rel = format!("{}{}", prefix, name);
+                if !owned.contains(&UniCase::new(rel.as_str())) {
+                    orphans.push(rel);
+                }
+            }
+        }
+        Ok(())
+    }
+    // asset names can't differ only by case, and on Windows files that do are the same file anyway
+    let owned = entries.iter().map(String::as_str).chain([MANIFEST_PATH]).map(UniCase::new).collect::<HashSet<_>>();
+    let mut orphans = Vec::new();
+    for name in PROJECT_DIRS {
+        let path = dir.join(name);
+        if path.is_dir() {
+            let rel = format!("{}/", name);
+            walk(&path, &rel, &owned, &mut orphans)?;
+            if !owned.contains(&UniCase::new(rel.as_str())) {
+                orphans.push(rel);
+            }
+        }
+    }
+    Ok(orphans)
+}
+
+/// Carries a file the project already has over into a save without rewriting it, so it doesn't count as left over.
+/// Returns false if the project doesn't have the file yet.
+pub fn keep(staged: &Path) -> Result<bool> {
+    let target = target_path(staged);
+    if !target.is_file() {
+        return Ok(false)
+    }
+    // a copy rather than a hard link, so nothing written to the staged file can reach the project before it's committed
+    std::fs::copy(&target, staged).map_err(|e| Error::FileIoError(e, staged.to_path_buf()))?;
+    Ok(true)
+}
+
+fn replay(dir: &Path, entries: &[String], orphans: &[String]) -> Result<()> {
+    let staging = dir.join(STAGING_DIR);
+    for entry in entries {
+        if let Some(rel) = entry.strip_suffix('/') {
+            let target = dir.join(rel);
+            std::fs::create_dir_all(&target).map_err(|e| Error::DirIoError(e, target))?;
+        } else {
+            let (staged, target) = (staging.join(entry), dir.join(entry));
+            // anything that's gone was already moved before the last attempt got interrupted
+            if staged.exists() {
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent).map_err(|e| Error::DirIoError(e, parent.to_path_buf()))?;
+                }
+                std::fs::rename(&staged, &target).map_err(|e| Error::FileIoError(e, target))?;
+            }
+        }
+    }
+    for orphan in orphans {
+        if let Some(rel) = orphan.strip_suffix('/') {
+            // hidden files are kept, so the directory might not be empty
+            let _ = std::fs::remove_dir(dir.join(rel));
+        } else {
+            let path = dir.join(orphan);
+            if path.exists() {
+                std::fs::remove_file(&path).map_err(|e| Error::FileIoError(e, path))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn finish(dir: &Path) -> Result<()> {
+    let staging = dir.join(STAGING_DIR);
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging).map_err(|e| Error::DirIoError(e, staging))?;
+    }
+    let journal = dir.join(JOURNAL_FILE);
+    if journal.exists() {
+        std::fs::remove_file(&journal).map_err(|e| Error::FileIoError(e, journal))?;
+    }
+    Ok(())
+}
+
+/// Whether an earlier save left anything behind for [`recover`] to finish or throw away.
+pub fn needs_recovery(dir: &Path) -> bool {
+    let journal = dir.join(JOURNAL_FILE);
+    journal.exists() || journal.with_extension("tmp").exists() || dir.join(STAGING_DIR).exists()
+}
+
+/// Finishes a save that was interrupted after it was committed, or throws it away if it wasn't.
+/// Loading and saving both do this, so it's only needed when reading a project some other way.
+pub fn recover(dir: &Path) -> Result<()> {
+    let journal = dir.join(JOURNAL_FILE);
+    if journal.exists() {
+        let contents = std::fs::read_to_string(&journal).map_err(|e| Error::FileIoError(e, journal.clone()))?;
+        let mut lines = contents.lines().map(String::from);
+        let entries = lines.by_ref().take_while(|line| !line.is_empty()).collect::<Vec<_>>();
+        replay(dir, &entries, &lines.collect::<Vec<_>>())?;
+    }
+    let _ = std::fs::remove_file(journal.with_extension("tmp"));
+    finish(dir)
+}
+
+/// Where a file under the staging directory ends up once the save is committed.
+/// Files that are only written when they don't exist yet should check this path.
+pub fn target_path(staged: &Path) -> PathBuf {
+    staged.components().filter(|c| c.as_os_str() != STAGING_DIR).collect()
+}
@@ -0,0 +1,257 @@
+// An index of which assets refer to which, built from a loaded project.
+// Code counts as referring to an asset wherever one of its identifiers is the asset's name, so names that only
+// show up in strings or comments don't. Asset types are named the way their folders are, like "sprites".
+
+use crate::{
+    AssetList, Project, Result,
+    actionlib::{self, ActionLibrary},
+    asset::*,
+    events::{self, EVENT_NAMES},
+    gml::identifiers,
+};
+use std::collections::{BTreeSet, HashMap};
+
+/// One thing referring to an asset.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Reference {
+    /// Type of the asset being referred to.
+    pub kind: &'static str,
+    pub name: String,
+    /// Type of what's doing the referring. Besides asset types, this can be "triggers" or "constants".
+    pub from_kind: &'static str,
+    pub from_name: String,
+    /// Where in it the reference is, such as "sprite", "Step_0 action 2" or "instance 5B0FF47E".
+    pub how: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CrossReference {
+    /// Sorted by what's being referred to, and without duplicates.
+    pub references: Vec<Reference>,
+}
+
+/// Action argument types that refer to an asset, and the asset type they refer to.
+fn arg_kind(ty: u32) -> Option<&'static str> {
+    Some(match ty {
+        5 => "sprites",
+        6 => "sounds",
+        7 => "backgrounds",
+        8 => "paths",
+        9 => "scripts",
+        10 => "objects",
+        11 => "rooms",
+        12 => "fonts",
+        14 => "timelines",
+        _ => return None,
+    })
+}
+
+struct Builder<'a> {
+    /// Every asset name, along with which types have an asset with that name.
+    names: HashMap<&'a str, Vec<&'static str>>,
+    libs: Option<&'a [ActionLibrary]>,
+    references: BTreeSet<Reference>,
+    from_kind: &'static str,
+    from_name: &'a str,
+}
+
+impl<'a> Builder<'a> {
+    fn add(&mut self, kind: &'static str, name: &str, how: impl Into<String>) {
+        if name.is_empty() {
+            return
+        }
+        self.references.insert(Reference {
+            kind,
+            name: name.to_string(),
+            from_kind: self.from_kind,
+            from_name: self.from_name.to_string(),
+            how: how.into(),
+        });
+    }
+
+    /// Adds a reference to every asset with this name, whatever its type.
+    fn add_name(&mut self, name: &str, how: &str) {
+        for kind in self.names.get(name).cloned().unwrap_or_default() {
+            self.add(kind, name, how);
+        }
+    }
+
+    fn add_gml(&mut self, code: &str, how: &str) {
+        for token in identifiers(code) {
+            self.add_name(token.text, how);
+        }
+    }
+
+    fn add_event(&mut self, event: &Event, how: &str) -> Result<()> {
+        for (i, action) in event.actions.iter().enumerate() {
+            let how = format!("{} action {}", how, i);
+            self.add_gml(&action.code, &how);
+            if let Some(applies_to) = action.applies_to.as_deref().filter(|a| *a != "self" && *a != "other") {
+                self.add("objects", applies_to, format!("{} applies to", how));
+            }
+            match &action.params {
+                ActionParams::Normal { args, .. } => {
+                    if let Some(libs) = self.libs {
+                        let def = actionlib::find_action(libs, action.lib_id, action.id)?;
+                        for (arg, ty) in args.iter().zip(def.param_types) {
+                            match (ty, arg_kind(ty)) {
+                                // expression, or string or expression
+                                (0 | 2, _) => self.add_gml(arg, &how),
+                                (_, Some(kind)) => self.add(kind, arg, how.as_str()),
+                                _ => (),
+                            }
+                        }
+                    } else {
+                        // without the libraries there's no telling which arguments are names,
+                        // so only the ones that are exactly the name of something count
+                        for arg in args {
+                            self.add_name(arg, &how);
+                        }
+                    }
+                },
+                ActionParams::Repeat(repeats) => self.add_gml(repeats, &how),
+                ActionParams::Variable { name, value } => {
+                    self.add_gml(name, &how);
+                    self.add_gml(value, &how);
+                },
+                ActionParams::None => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CrossReference {
+    /// Indexes every reference in the project. Action libraries are optional, but without them, action arguments
+    /// only count if they're exactly the name of an asset, as it's not known which ones are expressions.
+    pub fn build(project: &Project, libs: Option<&[ActionLibrary]>) -> Result<Self> {
+        fn names<'a, T>(list: &'a AssetList<T>, kind: &'static str, out: &mut HashMap<&'a str, Vec<&'static str>>) {
+            for (name, _) in list.iter() {
+                out.entry(name).or_default().push(kind);
+            }
+        }
+        let mut b = Builder { names: HashMap::new(), libs, references: BTreeSet::new(), from_kind: "", from_name: "" };
+        names(&project.sprites, "sprites", &mut b.names);
+        names(&project.sounds, "sounds", &mut b.names);
+        names(&project.backgrounds, "backgrounds", &mut b.names);
+        names(&project.paths, "paths", &mut b.names);
+        names(&project.scripts, "scripts", &mut b.names);
+        names(&project.fonts, "fonts", &mut b.names);
+        names(&project.timelines, "timelines", &mut b.names);
+        names(&project.objects, "objects", &mut b.names);
+        names(&project.rooms, "rooms", &mut b.names);
+
+        b.from_kind = "constants";
+        for (name, value) in &project.constants {
+            b.from_name = name;
+            b.add_gml(value, "value");
+        }
+        b.from_kind = "triggers";
+        for trigger in project.triggers.iter().flatten() {
+            b.from_name = &trigger.name;
+            b.add_gml(&trigger.condition, "condition");
+        }
+        b.from_kind = "paths";
+        for (name, path) in project.paths.iter() {
+            b.from_name = name;
+            b.add("rooms", &path.background, "background room");
+        }
+        b.from_kind = "scripts";
+        for (name, script) in project.scripts.iter() {
+            b.from_name = name;
+            b.add_gml(&script.source, "code");
+        }
+        b.from_kind = "timelines";
+        for (name, timeline) in project.timelines.iter() {
+            b.from_name = name;
+            for (time, event) in &timeline.moments {
+                b.add_event(event, &format!("moment {}", time))?;
+            }
+        }
+        b.from_kind = "objects";
+        for (name, object) in project.objects.iter() {
+            b.from_name = name;
+            b.add("sprites", &object.sprite, "sprite");
+            b.add("sprites", &object.mask, "mask");
+            b.add("objects", &object.parent, "parent");
+            for (ev_type, events) in object.events.iter().enumerate() {
+                for (ev_numb, event) in events {
+                    let how = format!("{}_{}", EVENT_NAMES[ev_type], ev_numb);
+                    if let (events::EV_COLLISION, EventNumber::Name(other)) = (ev_type, ev_numb) {
+                        b.add("objects", other, how.as_str());
+                    }
+                    b.add_event(event, &how)?;
+                }
+            }
+        }
+        b.from_kind = "rooms";
+        for (name, room) in project.rooms.iter() {
+            b.from_name = name;
+            b.add_gml(&room.creation_code, "creation code");
+            for (i, background) in room.backgrounds.iter().enumerate() {
+                b.add("backgrounds", &background.source_bg, format!("background {}", i));
+            }
+            for (i, view) in room.views.iter().enumerate() {
+                b.add("objects", &view.following_target, format!("view {}", i));
+            }
+            for instance in &room.instances {
+                let how = format!("instance {:08X}", instance.name);
+                b.add("objects", &instance.object, how.as_str());
+                b.add_gml(&instance.creation_code, &how);
+            }
+            for tile in &room.tiles {
+                b.add("backgrounds", &tile.source_bg, "tiles");
+            }
+        }
+        Ok(Self { references: b.references.into_iter().collect() })
+    }
+
+    /// Everything that refers to an asset.
+    pub fn users<'a>(&'a self, kind: &'a str, name: &'a str) -> impl Iterator<Item = &'a Reference> {
+        // sorted by kind and name first, so they're all next to each other
+        let start = self.references.partition_point(|r| (r.kind, r.name.as_str()) < (kind, name));
+        self.references[start..].iter().take_while(move |r| r.kind == kind && r.name == name)
+    }
+
+    /// Everything an asset, trigger or constant refers to.
+    pub fn uses<'a>(&'a self, kind: &'a str, name: &'a str) -> impl Iterator<Item = &'a Reference> {
+        self.references.iter().filter(move |r| r.from_kind == kind && r.from_name == name)
+    }
+
+    /// Writes the index as a JSON array of references.
+    pub fn to_json(&self) -> String {
+        fn string(s: &str) -> String {
+            let mut out = String::with_capacity(s.len() + 2);
+            out.push('"');
+            for c in s.chars() {
+                match c {
+                    '"' => out.push_str("\\\""),
+                    '\\' => out.push_str("\\\\"),
+                    '\n' => out.push_str("\\n"),
+                    '\r' => out.push_str("\\r"),
+                    '\t' => out.push_str("\\t"),
+                    c if u32::from(c) < 0x20 => out.push_str(&format!("\\u{:04x}", u32::from(c))),
+                    c => out.push(c),
+                }
+            }
+            out.push('"');
+            out
+        }
+        let mut out = String::from("[");
+        for (i, r) in self.references.iter().enumerate() {
+            if i != 0 {
+                out.push(',');
+            }
+            out.push_str(&format!(
+                "\n  {{\"kind\": {}, \"name\": {}, \"from_kind\": {}, \"from_name\": {}, \"how\": {}}}",
+                string(r.kind),
+                string(&r.name),
+                string(r.from_kind),
+                string(&r.from_name),
+                string(&r.how),
+            ));
+        }
+        out.push_str("\n]\n");
+        out
+    }
+}
@@ -0,0 +1,2 @@
+rm_5B0FF47E.x = 1
+show_message("\u{5C}u{41} is A")
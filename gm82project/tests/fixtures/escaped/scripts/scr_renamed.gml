@@ -0,0 +1 @@
+new
@@ -0,0 +1,38 @@
+#define Create_0
+/*"/*'/**//* YYD ACTION
+lib_id=1
+action_id=603
+applies_to=self
+*/
+foo()
+#define Collision_obj
+/*"/*'/**//* YYD ACTION
+lib_id=1
+action_id=101
+relative=1
+applies_to=other
+invert=0
+arg0=obj
+arg1=hello
+*/
+#define Other_4
+/*"/*'/**//* YYD ACTION
+lib_id=1
+action_id=603
+applies_to=self
+*/
+b
+#define Other_10
+/*"/*'/**//* YYD ACTION
+lib_id=1
+action_id=603
+applies_to=self
+*/
+a
+#define Trigger_trig
+/*"/*'/**//* YYD ACTION
+lib_id=1
+action_id=603
+applies_to=self
+*/
+t()
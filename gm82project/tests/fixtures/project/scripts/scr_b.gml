@@ -0,0 +1 @@
+rm_5B0FF47E.x = 1
@@ -0,0 +1,149 @@
+use gm82project::{
+    actionlib::{ActionDefinition, ActionLibrary},
+    gamedata::{decrypt_gamedata, read_exe, read_gamedata, write_gamedata, write_settings},
+};
+use std::path::Path;
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/project/game.gm82");
+
+/// Just enough of GameMaker's main library for the fixture's actions.
+fn libs() -> Vec<ActionLibrary> {
+    let action = |id, kind, param_count, execution_type, fn_name: &str| ActionDefinition {
+        lib_id: 1,
+        id,
+        kind,
+        applies_to_something: true,
+        can_be_relative: kind == 0,
+        param_count,
+        execution_type,
+        fn_name: fn_name.into(),
+        ..Default::default()
+    };
+    vec![ActionLibrary {
+        id: 1,
+        init_code: "lib_init = 1".into(),
+        actions: vec![action(101, 0, 2, 1, "action_move"), action(603, 7, 0, 2, "")],
+        ..Default::default()
+    }]
+}
+
+/// Encrypts data the way GameMaker does, with padding and a shuffled swap table,
+/// which `write_gamedata` doesn't bother with.
+fn encrypt(plain: &[u8], seed: u8) -> Vec<u8> {
+    let mut table = [0u8; 256];
+    for (i, b) in table.iter_mut().enumerate() {
+        *b = (i as u8).wrapping_mul(167).wrapping_add(seed);
+    }
+    let mut data = plain.to_vec();
+    for i in 0..data.len() {
+        data.swap(i, i.saturating_sub(usize::from(table[i & 0xff])));
+    }
+    for i in 1..data.len() {
+        data[i] = table[usize::from(data[i].wrapping_add(data[i - 1]).wrapping_add(i as u8))];
+    }
+    let mut out = Vec::new();
+    out.extend_from_slice(&2u32.to_le_bytes());
+    out.extend_from_slice(&1u32.to_le_bytes());
+    out.extend_from_slice(&[0xee; 8]);
+    out.extend_from_slice(&table);
+    out.extend_from_slice(&[0xee; 4]);
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(&data);
+    out
+}
+
+#[test]
+fn decryption_undoes_encryption() {
+    let plain = (0..1000u32).map(|i| (i * 7 % 251) as u8).collect::<Vec<_>>();
+    for seed in [0, 1, 200, 255] {
+        assert_eq!(decrypt_gamedata(&encrypt(&plain, seed)).unwrap(), plain, "seed {}", seed);
+    }
+    assert_eq!(decrypt_gamedata(&encrypt(&[], 3)).unwrap(), []);
+    assert_eq!(decrypt_gamedata(&encrypt(&[42], 3)).unwrap(), [42]);
+}
+
+#[test]
+fn corrupt_swap_table_is_rejected() {
+    let mut data = encrypt(&[1, 2, 3, 4], 5);
+    // the table starts after the two padding lengths and 8 bytes of padding
+    data[16 + 1] = data[16];
+    assert!(decrypt_gamedata(&data).is_err());
+}
+
+#[test]
+fn truncated_gamedata_is_rejected() {
+    let data = encrypt(&[1, 2, 3, 4], 5);
+    for len in [0, 6, 12, 100, data.len() - 1] {
+        assert!(decrypt_gamedata(&data[..len]).is_err(), "length {}", len);
+    }
+    // padding that runs past the end
+    let mut data = data;
+    data[0..4].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(decrypt_gamedata(&data).is_err());
+}
+
+#[test]
+fn gamedata_round_trips() {
+    let project = gm82project::load_gmk(Path::new(FIXTURE)).unwrap();
+    let data = write_gamedata(&project, &libs()).unwrap();
+    let read = read_gamedata(&data).unwrap();
+
+    assert_eq!(read.game_id, project.game_id);
+    assert_eq!(read.constants, project.constants);
+    assert_eq!(read.triggers, project.triggers);
+    assert_eq!(read.game_info, project.game_info);
+    assert_eq!(read.scripts.names, project.scripts.names);
+    assert_eq!(read.scripts.assets, project.scripts.assets);
+    assert_eq!(read.objects.names, project.objects.names);
+    assert_eq!(read.objects.assets, project.objects.assets);
+    assert_eq!(read.rooms.names, project.rooms.names);
+    for ((_, old), (_, new)) in project.rooms.iter().zip(read.rooms.iter()) {
+        assert_eq!(old.creation_code, new.creation_code);
+        assert_eq!(old.instances.len(), new.instances.len());
+        for (old, new) in old.instances.iter().zip(&new.instances) {
+            assert_eq!((old.id, &old.object, &old.creation_code), (new.id, &new.object, &new.creation_code));
+        }
+    }
+}
+
+#[test]
+fn referenced_instances_get_constants() {
+    let project = gm82project::load_gmk(Path::new(FIXTURE)).unwrap();
+    let data = write_gamedata(&project, &libs()).unwrap();
+    let decrypted = decrypt_gamedata(&data).unwrap();
+    // scr_b refers to rm_5B0FF47E, but nothing refers to the room's other instance
+    assert!(decrypted.windows(11).any(|w| w == b"rm_5B0FF47E"));
+    let read = read_gamedata(&data).unwrap();
+    let names = read.rooms.get("rm").unwrap().instances.iter().map(|i| i.name).collect::<Vec<_>>();
+    assert_eq!(names, [0x5B0FF47E, 0]);
+}
+
+#[test]
+fn extensions_are_refused() {
+    let mut project = gm82project::load_gmk(Path::new(FIXTURE)).unwrap();
+    project.extensions.push("GM Windows Dialogs".into());
+    assert!(write_gamedata(&project, &libs()).is_err());
+}
+
+#[test]
+fn exe_round_trips() {
+    let project = gm82project::load_gmk(Path::new(FIXTURE)).unwrap();
+    let mut exe = b"MZ".to_vec();
+    exe.extend_from_slice(&[0x90; 100]);
+    exe.extend_from_slice(&1234321u32.to_le_bytes());
+    exe.extend_from_slice(&810u32.to_le_bytes());
+    exe.extend_from_slice(&0u32.to_le_bytes()); // debug
+    exe.extend_from_slice(&write_settings(&project).unwrap());
+    exe.extend_from_slice(&[0; 8]); // d3dx8.dll
+    exe.extend_from_slice(&write_gamedata(&project, &libs()).unwrap());
+    let read = read_exe(&exe).unwrap();
+    // the icon goes in the exe's resources rather than the settings block
+    let mut settings = project.settings.clone();
+    settings.icon = Vec::new();
+    assert_eq!(read.settings, settings);
+    assert_eq!(read.scripts.assets, project.scripts.assets);
+
+    assert!(read_exe(b"MZ not a game").is_err());
+    exe.truncate(exe.len() - 1);
+    assert!(read_exe(&exe).is_err());
+}
@@ -0,0 +1,28 @@
+use gm82project::GMLLines;
+
+fn lines(code: &str) -> Vec<&str> {
+    GMLLines::new(code).collect()
+}
+
+#[test]
+fn trailing_spaces_are_trimmed() {
+    assert_eq!(lines("a = 1;  \nb = 2;\t\n"), ["a = 1;", "b = 2;"]);
+}
+
+#[test]
+fn strings_keep_trailing_spaces() {
+    assert_eq!(lines("s = \"one  \ntwo\";  \n"), ["s = \"one  ", "two\";"]);
+    assert_eq!(lines("s = 'one  \ntwo';  \n"), ["s = 'one  ", "two';"]);
+    // the other kind of quote doesn't end a string
+    assert_eq!(lines("s = \"it's  \nfine\";  \n"), ["s = \"it's  ", "fine\";"]);
+}
+
+// this is where the format changed: quotes in comments used to count as strings,
+// so the lines after this one kept their trailing spaces
+#[test]
+fn quotes_in_comments_dont_start_strings() {
+    assert_eq!(lines("// don't  \na = 1;  \nb = 2;  \n"), ["// don't", "a = 1;", "b = 2;"]);
+    assert_eq!(lines("/* \"quoted  \n*/  \na = 1;  \n"), ["/* \"quoted", "*/", "a = 1;"]);
+    // which also means a string after such a comment isn't trimmed where it shouldn't be
+    assert_eq!(lines("// it's\ns = 'a  \nb';\n"), ["// it's", "s = 'a  ", "b';"]);
+}
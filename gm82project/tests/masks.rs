@@ -0,0 +1,101 @@
+use gm82project::mask::*;
+use std::path::Path;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/masks");
+
+fn parse_bbox(s: &str) -> [i32; 4] {
+    let v = s.split(',').map(|n| n.parse().unwrap()).collect::<Vec<_>>();
+    [v[0], v[1], v[2], v[3]]
+}
+
+/// Each fixture has the collision settings as `key=value` lines, the bounding box that should come out,
+/// then the mask the shared image should get, with `#` for solid pixels.
+fn check(name: &str) {
+    let dir = Path::new(FIXTURES);
+    let image = std::fs::read_to_string(dir.join("image.txt")).unwrap();
+    // '#' is fully opaque, '.' is transparent and digits are tens of alpha
+    let data = image
+        .lines()
+        .flat_map(str::chars)
+        .flat_map(|c| {
+            let alpha = match c {
+                '#' => 255,
+                '.' => 0,
+                d => d.to_digit(10).unwrap() as u8 * 10,
+            };
+            [0, 0, 0, alpha]
+        })
+        .collect::<Vec<_>>();
+    let (width, height) = (image.lines().next().unwrap().len() as u32, image.lines().count() as u32);
+
+    let fixture = std::fs::read_to_string(dir.join(format!("{}.txt", name))).unwrap();
+    let mut collision = Collision::default();
+    let mut bbox = [0; 4];
+    let mut rows = Vec::new();
+    for line in fixture.lines() {
+        match line.split_once('=') {
+            Some(("shape", v)) => {
+                collision.shape = match v {
+                    "precise" => SHAPE_PRECISE,
+                    "rectangle" => SHAPE_RECTANGLE,
+                    "disk" => SHAPE_DISK,
+                    "diamond" => SHAPE_DIAMOND,
+                    _ => panic!("unknown shape {}", v),
+                }
+            },
+            Some(("bbox_type", v)) => {
+                collision.bbox_type = match v {
+                    "automatic" => BBOX_AUTOMATIC,
+                    "full" => BBOX_FULL_IMAGE,
+                    "manual" => BBOX_MANUAL,
+                    _ => panic!("unknown bbox type {}", v),
+                }
+            },
+            Some(("tolerance", v)) => collision.alpha_tolerance = v.parse().unwrap(),
+            Some(("manual", v)) => {
+                [collision.bbox_left, collision.bbox_top, collision.bbox_right, collision.bbox_bottom] = parse_bbox(v)
+            },
+            Some(("bbox", v)) => bbox = parse_bbox(v),
+            _ => rows.push(line),
+        }
+    }
+
+    let mask = make_mask(&collision, &[FrameData { width, height, data: &data }]);
+    let pixels = mask
+        .data
+        .chunks(width as usize)
+        .map(|row| row.iter().map(|&s| if s { '#' } else { '.' }).collect::<String>())
+        .collect::<Vec<_>>();
+    assert_eq!(pixels, rows, "{}", name);
+    let bbox = bbox.map(|v| v as u32);
+    assert_eq!([mask.bbox_left, mask.bbox_top, mask.bbox_right, mask.bbox_bottom], bbox, "{}", name);
+}
+
+#[test]
+fn precise() {
+    check("precise_automatic");
+    check("precise_full");
+    check("precise_manual");
+    check("precise_tolerance");
+}
+
+#[test]
+fn rectangle() {
+    check("rectangle_automatic");
+    check("rectangle_full");
+    check("rectangle_manual");
+}
+
+#[test]
+fn disk() {
+    check("disk_automatic");
+    check("disk_full");
+    check("disk_manual");
+}
+
+#[test]
+fn diamond() {
+    check("diamond_automatic");
+    check("diamond_full");
+    check("diamond_manual");
+}
@@ -0,0 +1,65 @@
+use gm82project::merge::merge_file;
+
+fn clean(path: &str, base: &str, ours: &str, theirs: &str) -> String {
+    let (text, conflicts) = merge_file(path, base, ours, theirs).unwrap();
+    assert_eq!(conflicts, 0, "{}", text);
+    text
+}
+
+#[test]
+fn indexes_of_numbers_are_still_indexes() {
+    // all of these parse as depths, but it's an asset index, so slots keep their positions
+    let merged = clean("game/scripts/index.yyd", "1\n\n3\n", "1\n2\n3\n", "1\n\n3\n4\n");
+    assert_eq!(merged, "1\n2\n3\n4\n");
+}
+
+#[test]
+fn index_names_can_have_commas_and_equals_signs() {
+    let merged = clean("scripts/index.yyd", "a,b\n", "a,b\nc=d\n", "a,b\n\ne,f\n");
+    assert_eq!(merged, "a,b\nc=d\ne,f\n");
+}
+
+#[test]
+fn layers_keep_their_order() {
+    let merged = clean("rooms/rm/layers.txt", "1000000\n-5\n", "1000000\n-5\n20\n", "7\n1000000\n-5\n");
+    assert_eq!(merged, "7\n1000000\n-5\n20\n");
+    let merged = clean("rooms/rm/layers.txt", "3\n2\n1\n", "3\n1\n", "3\n2\n1\n0\n");
+    assert_eq!(merged, "3\n1\n0\n");
+}
+
+#[test]
+fn instances_added_on_both_sides_merge() {
+    let base = "obj,0,0,5B0FF47E,0,1,1,4294967295,0,1\n";
+    let ours = "obj,0,0,5B0FF47E,0,1,1,4294967295,0,1\nobj,16,0,00000001,0,1,1,4294967295,0,0\n";
+    let theirs = "obj,32,0,5B0FF47E,0,1,1,4294967295,0,1\nobj,48,0,00000002,0,1,1,4294967295,0,0\n";
+    let merged = clean("game/rooms/rm/instances.txt", base, ours, theirs);
+    assert_eq!(
+        merged,
+        "obj,32,0,5B0FF47E,0,1,1,4294967295,0,1\n\
+         obj,16,0,00000001,0,1,1,4294967295,0,0\n\
+         obj,48,0,00000002,0,1,1,4294967295,0,0\n"
+    );
+}
+
+#[test]
+fn instances_changed_on_both_sides_conflict() {
+    let base = "obj,0,0,5B0FF47E,0,1,1,4294967295,0,1\n";
+    let ours = "obj,16,0,5B0FF47E,0,1,1,4294967295,0,1\n";
+    let theirs = "obj,32,0,5B0FF47E,0,1,1,4294967295,0,1\n";
+    let (_, conflicts) = merge_file("rooms/rm/instances.txt", base, ours, theirs).unwrap();
+    assert_eq!(conflicts, 1);
+}
+
+#[test]
+fn timestamps_keep_the_later_time() {
+    let merged = clean("sprites/timestamps.yyd", "a=1\nb=1\n", "a=2\nb=1\n", "a=3\nb=1\nc=4\n");
+    assert_eq!(merged, "a=3\nb=1\nc=4\n");
+}
+
+#[test]
+fn other_files_are_left_to_git() {
+    assert!(merge_file("rooms/rm/room.txt", "1\n", "2\n", "3\n").is_none());
+    assert!(merge_file("scripts/scr.gml", "", "a\n", "b\n").is_none());
+    // tile layers are only recognised inside a room
+    assert!(merge_file("notes/5.txt", "", "a\n", "b\n").is_none());
+}
@@ -0,0 +1,108 @@
+use gm82project::asset::{Background, Font, FontFile, Frame, IncludedFile, Sound, Sprite};
+use std::path::{Path, PathBuf};
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/project");
+/// The same project saved as gm82_version 6, with backslashes in its text that had to be escaped.
+const ESCAPED_FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/escaped");
+
+/// Every file under `dir`, relative to it and sorted, leaving out the cache folder since it isn't part of the project.
+fn files(dir: &Path) -> Vec<PathBuf> {
+    fn walk(root: &Path, dir: &Path, out: &mut Vec<PathBuf>) {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                if path != root.join("cache") {
+                    walk(root, &path, out);
+                }
+            } else {
+                out.push(path.strip_prefix(root).unwrap().to_path_buf());
+            }
+        }
+    }
+    let mut out = Vec::new();
+    walk(dir, dir, &mut out);
+    out.sort();
+    out
+}
+
+/// Loads the project in `fixture` and saves it somewhere else, checking that every file comes out the same.
+fn assert_round_trips(fixture: &Path, name: &str) -> gm82project::Project {
+    let mut project = gm82project::load_gmk(fixture.join("game.gm82")).unwrap();
+    let out = std::env::temp_dir().join(format!("gm82project-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&out);
+    gm82project::save_gmk(&mut project, out.join("game.gm82")).unwrap();
+
+    let expected = files(fixture);
+    assert_eq!(files(&out), expected);
+    for file in &expected {
+        let (old, new) = (std::fs::read(fixture.join(file)).unwrap(), std::fs::read(out.join(file)).unwrap());
+        assert!(old == new, "{} changed when saved", file.display());
+    }
+    std::fs::remove_dir_all(out).unwrap();
+    project
+}
+
+#[test]
+fn save_reproduces_loaded_project() {
+    assert_round_trips(Path::new(FIXTURE), "roundtrip");
+}
+
+#[test]
+fn escaped_text_round_trips() {
+    let project = assert_round_trips(Path::new(ESCAPED_FIXTURE), "escaped");
+    assert!(project.escaped_utf16);
+    let script = project.scripts.get("scr_b").unwrap();
+    assert!(script.source.contains(r#"show_message("\u{41} is A")"#), "{:?}", script.source);
+    assert_eq!(project.constants[1], ("B".to_string(), r#""\u{42}""#.to_string()));
+}
+
+#[test]
+fn invalid_utf16_becomes_replacement_character() {
+    let dir = std::env::temp_dir().join(format!("gm82project-surrogate-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let mut project = gm82project::load_gmk(Path::new(ESCAPED_FIXTURE).join("game.gm82")).unwrap();
+    gm82project::save_gmk(&mut project, dir.join("game.gm82")).unwrap();
+    std::fs::write(dir.join("settings/constants.txt"), "A=\\u{D83D}\n").unwrap();
+    let project = gm82project::load_gmk(dir.join("game.gm82")).unwrap();
+    assert_eq!(project.constants, [("A".to_string(), "\u{FFFD}".to_string())]);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn saved_files_match_what_gets_saved() {
+    for (fixture, name) in [(FIXTURE, "listed"), (ESCAPED_FIXTURE, "listed-escaped")] {
+        let mut project = gm82project::load_gmk(Path::new(fixture).join("game.gm82")).unwrap();
+        // the fixtures don't have every kind of asset, so fill in the rest
+        let frame = Frame { width: 1, height: 1, data: vec![0, 0, 255, 255] };
+        project.sounds.push("snd", Sound { extension: ".wav".into(), data: Some(vec![1]), ..Default::default() });
+        project.sounds.push("snd_empty", Sound::default());
+        project.backgrounds.push("bg", Background { frame: frame.clone(), ..Default::default() });
+        project.backgrounds.push("bg_empty", Background::default());
+        project.paths.push("pth", Default::default());
+        project.fonts.push("fnt", Font {
+            file: Some(FontFile { extension: "ttf".into(), data: vec![2] }),
+            ..Default::default()
+        });
+        project.timelines.push("tl", Default::default());
+        project.sprites.push("spr_empty", Sprite::default());
+        project.included_files.push(IncludedFile {
+            file_name: "notes.txt".into(),
+            stored_in_gmk: true,
+            data: Some(b"hi".to_vec()),
+            ..Default::default()
+        });
+        let out = std::env::temp_dir().join(format!("gm82project-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&out);
+        gm82project::save_gmk(&mut project, out.join("game.gm82")).unwrap();
+
+        let mut saved = files(&out).iter().map(|f| f.to_string_lossy().replace('\\', "/")).collect::<Vec<_>>();
+        for cache in ["cache/sprites", "cache/backgrounds"] {
+            for entry in std::fs::read_dir(out.join(cache)).unwrap() {
+                saved.push(format!("{}/{}", cache, entry.unwrap().file_name().to_string_lossy()));
+            }
+        }
+        saved.sort();
+        assert_eq!(gm82project::save::saved_files(&project, "game.gm82"), saved);
+        std::fs::remove_dir_all(out).unwrap();
+    }
+}
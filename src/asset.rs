@@ -436,6 +436,10 @@ impl Timeline {
         unsafe { self.moment_events.get_unchecked(..self.moment_count) }
     }
 
+    pub fn get_events_mut(&mut self) -> &mut [DelphiBox<Event>] {
+        unsafe { self.moment_events.get_unchecked_mut(..self.moment_count) }
+    }
+
     pub fn get_times(&self) -> &[u32] {
         unsafe { self.moment_times.get_unchecked(..self.moment_count) }
     }
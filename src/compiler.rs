@@ -1,8 +1,7 @@
 use super::{EXTRA_DATA, InstanceExtra, TileExtra, patch, patch_call};
 use crate::{UStr, ide, ide::AssetListTrait};
-use lazy_static::lazy_static;
+use gm82project::gml;
 use rayon::prelude::*;
-use regex::Regex;
 use std::{
     arch::{asm, naked_asm},
     collections::HashSet,
@@ -17,10 +16,6 @@ unsafe extern "C" fn compile_constants_inj() {
     );
 }
 
-lazy_static! {
-    static ref INSTANCE_ID_REGEX: Regex = Regex::new(r"[A-Za-z0-9_]+_([0-9A-F]{8})").unwrap();
-}
-
 unsafe extern "fastcall" fn compile_constants(stream: usize) -> bool {
     if EXTRA_DATA.is_none() {
         let res: usize = delphi_call!(0x696744, stream, 1);
@@ -29,8 +24,6 @@ unsafe extern "fastcall" fn compile_constants(stream: usize) -> bool {
 
     let constant_names = ide::get_constant_names();
     let constant_values = ide::get_constants();
-    // this is just so my ide will give me autocomplete
-    let re = &INSTANCE_ID_REGEX;
 
     // we want to collect instance names that are actually used
     // iterate over all code
@@ -61,10 +54,8 @@ unsafe extern "fastcall" fn compile_constants(stream: usize) -> bool {
         .chain(constant_iter)
         .flat_map(|s| {
             // gotta collect into vec because otherwise string reference is lost
-            re.captures_iter(&s)
-                .filter_map(|c| c.get(1))
-                .flat_map(|m| u32::from_str_radix(m.as_str(), 16))
-                .collect::<Vec<_>>()
+            // only identifiers count, so names in strings and comments don't pull in unused instances
+            gml::identifiers(&s).filter_map(|t| Some(gml::instance_name(t.text)?.1)).collect::<Vec<_>>()
         })
         .collect();
 
@@ -681,6 +681,17 @@ impl UStr {
         out
     }
 
+    pub fn from_wide(s: &[u16]) -> Self {
+        let mut out = UStr(ptr::null_mut());
+        if !s.is_empty() {
+            unsafe {
+                UStrSetLength(&mut out, s.len());
+                slice::from_raw_parts_mut(out.0, s.len()).copy_from_slice(s);
+            }
+        }
+        out
+    }
+
     pub fn from_char(c: u16) -> Self {
         let mut out = UStr(ptr::null_mut());
         let _: u32 = unsafe { delphi_call!(0x408034, &mut out, u32::from(c)) };
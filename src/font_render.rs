@@ -1,5 +1,6 @@
 use crate::{UStr, asset::Font, delphi::TBitmap};
-use std::{ptr, slice};
+use gm82project::pack;
+use std::{io, ptr, slice};
 
 #[repr(C)]
 #[derive(Default)]
@@ -95,39 +96,6 @@ fn charset_to_codepage(charset: u32) -> u32 {
 }
 
 impl Font {
-    fn calculate_bitmap_size(&mut self, chars: &[u32]) {
-        let hspacing = 2;
-        let vspacing = 2;
-        let mut width = 0x800;
-        let mut height = 0x800;
-        let mut shrink_h = false;
-        loop {
-            let mut x = 0;
-            let mut y = 0;
-            let mut row_height = 0;
-            for &c in chars {
-                if x + self.s_w[c as usize] >= width {
-                    x = 0;
-                    y += row_height + vspacing;
-                    row_height = 0;
-                }
-                row_height = row_height.max(self.s_h[c as usize]);
-                x += self.s_w[c as usize] + hspacing;
-            }
-            if y + row_height > height {
-                break
-            }
-            self.s_bw = width;
-            self.s_bh = height;
-            shrink_h = !shrink_h;
-            if shrink_h {
-                height >>= 1;
-            } else {
-                width >>= 1;
-            }
-        }
-    }
-
     #[naked]
     unsafe extern "fastcall" fn copy_glyph_onto_atlas(
         &mut self,
@@ -148,7 +116,7 @@ impl Font {
         );
     }
 
-    pub fn render(&mut self) {
+    pub fn render(&mut self) -> io::Result<()> {
         unsafe {
             let mut bitmap = TBitmap::new();
             bitmap.SetPixelFormat(6);
@@ -160,7 +128,7 @@ impl Font {
             let matrix = [0u16, 1, 0, 0, 0, 0, 0, 1];
             let mut text_metrics = std::mem::zeroed();
             if GetTextMetricsW(hdc, &mut text_metrics) == 0 {
-                return
+                return Err(io::Error::last_os_error())
             }
             let codepage = charset_to_codepage(self.charset);
             self.s_bw = 0;
@@ -232,9 +200,19 @@ impl Font {
                 self.s_shift[0x20] = self.s_w[0x20];
             }
             // setup
-            let mut chars = (self.range_start..=self.range_end).collect::<Vec<_>>();
-            chars.sort_unstable_by_key(|&c| -((self.s_w[c as usize] * self.s_h[c as usize]) as i32));
-            self.calculate_bitmap_size(&chars);
+            let chars = (self.range_start..=self.range_end).collect::<Vec<_>>();
+            let sizes = chars.iter().map(|&c| (self.s_w[c as usize], self.s_h[c as usize])).collect::<Vec<_>>();
+            let packing = pack::pack(&sizes, 2, 0x800).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::Other,
+                    format!(
+                        "font {} doesn't fit on a 2048x2048 texture",
+                        self.sys_name.to_os_string().to_string_lossy()
+                    ),
+                )
+            })?;
+            self.s_bw = packing.width;
+            self.s_bh = packing.height;
             if !vector_font {
                 bitmap.SetSize(self.s_bw, self.s_bh);
                 let canvas = bitmap.GetCanvas();
@@ -250,19 +228,8 @@ impl Font {
             self.s_bytes.fill(0); // is this really necessary?
             let mut glyph_buf = vec![0; max_buffer_size as usize];
             // draw the characters
-            let mut x = 0;
-            let mut y = 0;
-            let mut row_height = 0;
-            let hspacing = 2;
-            let vspacing = 2;
-            for c in chars {
+            for (c, (x, y)) in chars.into_iter().zip(packing.positions) {
                 let c = c as usize;
-                if x + self.s_w[c] >= self.s_bw {
-                    x = 0;
-                    y += row_height + vspacing;
-                    row_height = 0;
-                }
-                row_height = row_height.max(self.s_h[c]);
                 self.s_x[c] = x;
                 self.s_y[c] = y;
                 if c != 0x20 {
@@ -298,7 +265,6 @@ impl Font {
                         }
                     }
                 }
-                x += self.s_w[c as usize] + hspacing;
             }
             if !vector_font {
                 // download bitmap
@@ -312,5 +278,6 @@ impl Font {
                 }
             }
         }
+        Ok(())
     }
 }
@@ -42,6 +42,7 @@ pub trait AssetListTrait<T>: Sync {
     fn names(&self) -> &[UStr];
     fn names_mut(&self) -> &'static mut [UStr];
     fn timestamps(&self) -> &[f64];
+    fn timestamps_mut(&self) -> &'static mut [f64];
     fn alloc(&self, count: usize);
 }
 
@@ -99,8 +100,6 @@ impl<T, const P1: usize, const P2: usize, const P3: usize, const P4: usize> Asse
 
     get_member_mut!(pub forms_mut, Form, forms);
 
-    get_member_mut!(pub timestamps_mut, f64, timestamps);
-
     unsafe fn range(&self) -> std::ops::RangeTo<usize> {
         ..(*self.0).count
     }
@@ -113,8 +112,6 @@ impl<T, const P1: usize, const P2: usize, const P3: usize, const P4: usize, cons
 
     get_member!(pub forms_mut, Form, forms);
 
-    get_member_mut!(pub timestamps_mut, f64, timestamps);
-
     get_member_mut!(pub thumbs_mut, i32, thumbs);
 
     unsafe fn range(&self) -> std::ops::RangeTo<usize> {
@@ -135,6 +132,8 @@ impl<T, const P1: usize, const P2: usize, const P3: usize, const P4: usize> Asse
 
     get_member!(timestamps, f64, timestamps);
 
+    get_member_mut!(timestamps_mut, f64, timestamps);
+
     fn alloc(&self, count: usize) {
         unsafe {
             (*self.0).count = count;
@@ -159,6 +158,8 @@ impl<T, const P1: usize, const P2: usize, const P3: usize, const P4: usize, cons
 
     get_member!(timestamps, f64, timestamps);
 
+    get_member_mut!(timestamps_mut, f64, timestamps);
+
     fn alloc(&self, count: usize) {
         unsafe {
             (*self.0).count = count;
@@ -173,7 +174,7 @@ impl<T, const P1: usize, const P2: usize, const P3: usize, const P4: usize, cons
 
 const TRIGGERS: *mut DelphiList<Option<DelphiBox<Trigger>>, 0x6bc93c> = 0x77f3f4 as _;
 const TRIGGER_COUNT: IntPtr = 0x77f3f8 as _;
-pub const TRIGGERS_UPDATED: *const bool = 0x790058 as _;
+pub const TRIGGERS_UPDATED: *mut bool = 0x790058 as _;
 
 const CONSTANT_COUNT: IntPtr = 0x77f3c4 as _;
 const CONSTANT_NAMES: *mut DelphiList<UStr, 0x696594> = 0x78c14c as _;
@@ -29,11 +29,13 @@ use crate::{
     save::GetAsset,
     save_exe::GetAssetList,
 };
+use gm82project::{
+    rename::Rename,
+    save::{derive_instance_name, escape_utf16},
+};
 use ide::AssetListTrait;
 use itertools::Itertools;
-use lazy_static::lazy_static;
 use rayon::prelude::*;
-use regex::Regex;
 use std::{
     arch::{asm, naked_asm},
     collections::{HashMap, HashSet},
@@ -41,7 +43,7 @@ use std::{
     io::Write,
     os::windows::process::CommandExt,
     path::PathBuf,
-    ptr::{self, addr_of, addr_of_mut},
+    ptr::{self, addr_of},
     time::SystemTime,
 };
 
@@ -62,6 +64,7 @@ pub enum Error {
     DuplicateAsset(String),
     DuplicateIncludedFile(String),
     DuplicateTrigger(String),
+    DuplicateId(&'static str, usize, String),
     BadAssetName(String, char),
     BadIncludedFileName(String, char),
     BadTriggerName(String, char),
@@ -78,6 +81,15 @@ impl std::fmt::Display for Error {
             }
             components.as_path().display()
         }
+        fn fmt_bad_name(f: &mut std::fmt::Formatter<'_>, what: &str, n: &str, c: char) -> std::fmt::Result {
+            if c == '.' || c == ' ' {
+                write!(f, "{what} \"{n}\" is illegal")
+            } else if c == char::REPLACEMENT_CHARACTER {
+                write!(f, "{what} {n} has a broken character in it, where the escape is")
+            } else {
+                write!(f, "{what} {n} may not contain character {}", c.escape_debug())
+            }
+        }
         match self {
             Self::IoError(e) => write!(f, "io error: {}", e),
             Self::FileIoError(e, p) => write!(f, "io error in file {}: {}", fmt_path(p), e),
@@ -94,15 +106,12 @@ impl std::fmt::Display for Error {
             Self::DuplicateAsset(n) => write!(f, "multiple assets named {}", n),
             Self::DuplicateIncludedFile(n) => write!(f, "multiple included files named {}", n),
             Self::DuplicateTrigger(n) => write!(f, "multiple triggers named {}", n),
-            Self::BadAssetName(n, c) => {
-                if *c == '.' || *c == ' ' {
-                    write!(f, "asset name \"{n}\" is illegal")
-                } else {
-                    write!(f, "asset name {n} may not contain character {c}")
-                }
+            Self::DuplicateId(t, id, room) => {
+                write!(f, "multiple {}s with id {} (found again in room {})", t, id, room)
             },
-            Self::BadIncludedFileName(n, c) => write!(f, "included file name {n} may not contain character {c}"),
-            Self::BadTriggerName(n, c) => write!(f, "trigger file name {n} may not contain character {c}"),
+            Self::BadAssetName(n, c) => fmt_bad_name(f, "asset name", n, *c),
+            Self::BadIncludedFileName(n, c) => fmt_bad_name(f, "included file name", n, *c),
+            Self::BadTriggerName(n, c) => fmt_bad_name(f, "trigger file name", n, *c),
             Self::OldGM82 => write!(f, "this project was made with a newer version of gm82save, please update"),
             Self::Other(s) => write!(f, "other error: {}", s),
         }
@@ -115,6 +124,19 @@ impl From<std::io::Error> for Error {
     }
 }
 
+// only what the shared code can actually fail with gets its own variant
+impl From<gm82project::Error> for Error {
+    fn from(err: gm82project::Error) -> Self {
+        match err {
+            gm82project::Error::IoError(e) => Error::IoError(e),
+            gm82project::Error::FileIoError(e, p) => Error::FileIoError(e, p),
+            gm82project::Error::DirIoError(e, p) => Error::DirIoError(e, p),
+            gm82project::Error::UnicodeError(s) => Error::UnicodeError(s),
+            e => Error::Other(e.to_string()),
+        }
+    }
+}
+
 impl From<std::num::ParseIntError> for Error {
     fn from(err: std::num::ParseIntError) -> Self {
         Error::ParseIntError(err)
@@ -127,33 +149,6 @@ impl From<std::num::ParseFloatError> for Error {
     }
 }
 
-// line iterator that strips right end but only if not in a string
-pub struct GMLLines<'a>(std::str::Lines<'a>, u8);
-
-impl<'a> GMLLines<'a> {
-    fn new(lines: std::str::Lines<'a>) -> Self {
-        Self(lines, 0)
-    }
-}
-
-impl<'a> Iterator for GMLLines<'a> {
-    type Item = &'a str;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        // trim line only if EOL is not in a string
-        let line = self.0.next()?;
-        let trimmed = line.trim_end();
-        for c in trimmed.bytes() {
-            if self.1 == 0 && (c == b'"' || c == b'\'') {
-                self.1 = c;
-            } else if c == self.1 {
-                self.1 = 0;
-            }
-        }
-        Some(if self.1 == 0 { trimmed } else { line })
-    }
-}
-
 pub type Result<T> = std::result::Result<T, Error>;
 
 const ACTION_TOKEN: &str = "/*\"/*'/**//* YYD ACTION";
@@ -234,29 +229,13 @@ where
 }
 
 static mut SAVE_END: SystemTime = SystemTime::UNIX_EPOCH;
-static mut LAST_SAVE: f64 = 0.0;
 
 fn update_timestamp() {
     unsafe {
         SAVE_END = SystemTime::now();
-        delphi::Now(addr_of_mut!(LAST_SAVE));
     }
 }
 
-#[naked]
-unsafe extern "C" fn reset_if_time_went_backwards() {
-    naked_asm!(
-        "movsd xmm0, qword ptr [{last_save}]", // load last save
-        "ucomisd xmm0, qword ptr [esp]",       // compare to now
-        "jb 2f", // jump if now > last save (i.e. no change needed)
-        "mov dword ptr [{last_save}], 0",      // null out last_save
-        "mov dword ptr [{last_save}+4], 0",
-        "2: add esp, 0x20", // return
-        "ret",
-        last_save = sym LAST_SAVE,
-    );
-}
-
 #[derive(Clone)]
 struct InstanceExtra {
     pub name: u32,
@@ -295,6 +274,7 @@ impl Default for TileExtra {
 
 unsafe extern "fastcall" fn stuff_to_do_on_project_init() {
     EXTRA_DATA = None;
+    PRESERVE_IDS = false;
     SEEN_ERROR = false;
     project_watcher::unwatch();
     let _: u32 = delphi_call!(0x7149c4); // reload action libraries (what this overwrote)
@@ -338,6 +318,8 @@ unsafe fn refresh_gm82room_checkbox() {
 }
 
 static mut EXTRA_DATA: Option<(HashMap<usize, InstanceExtra>, HashMap<usize, TileExtra>)> = None;
+// whether instance and tile ids get saved in the .gm82 project, set with preserve_ids=1 in the .gm82 file
+static mut PRESERVE_IDS: bool = false;
 
 unsafe extern "fastcall" fn about_inj(about_dialog: *const *const usize) {
     let info = UStr::new(concat!("gm82save: ", env!("ABOUT_BUILD_DATE")));
@@ -380,6 +362,7 @@ unsafe extern "C" fn save_inj() {
 // set the low byte to nonzero on success
 unsafe extern "fastcall" fn save(proj_path: &UStr, stream_ptr: *mut u32) -> u16 {
     SEEN_ERROR = false;
+    update_renamed_references();
     const IS_YYD: u16 = 0x100;
     let mut path: PathBuf = proj_path.to_os_string().into();
     // filename ".gm82" works in the ui but rust doesn't get it so check for that specifically
@@ -433,6 +416,8 @@ unsafe extern "fastcall" fn load(proj_path: &UStr, stream_ptr: *mut u32, result_
     // .gm82 works in the ui but rust doesn't get it so check for that specifically
     let is_gm82 = path.extension() == Some("gm82".as_ref()) || path.file_name() == Some(".gm82".as_ref());
     if !is_gm82 {
+        // gamemaker loads this one itself, so the names can't be known until it's saved
+        ASSET_NAMES = None;
         let stream = delphi_call!(0x405a4c, 0x52e8fc, 1);
         stream_ptr.write(stream);
         return false
@@ -447,6 +432,7 @@ unsafe extern "fastcall" fn load(proj_path: &UStr, stream_ptr: *mut u32, result_
         delphi::close_progress_form();
         result_ptr.write(true);
     }
+    remember_asset_names();
     true
 }
 
@@ -751,11 +737,9 @@ unsafe extern "C" fn reset_compression() {
 
 unsafe extern "stdcall" fn duplicate_room(room: &mut asset::Room, old_id: usize, new_id: usize) {
     let room_names = ide::ROOMS.names();
-    fix_instances_when_renaming_room(
-        room,
-        room_names[old_id].to_os_string().to_str().unwrap(),
-        room_names[new_id].to_os_string().to_str().unwrap(),
-    );
+    let (old_name, new_name) =
+        (escape_utf16(room_names[old_id].as_slice()), escape_utf16(room_names[new_id].as_slice()));
+    fix_instances_when_renaming_room(room, Rename::instances(&old_name, &new_name));
     freshen_room_ids(room);
 }
 
@@ -1864,7 +1848,7 @@ unsafe extern "C" fn rename_room_inj() {
 
 unsafe extern "fastcall" fn rename_room(room_id: usize, new_name: *const u16) -> *const UStr {
     let room_names = ide::ROOMS.names();
-    if let Some(room) = ide::ROOMS.assets_mut()[room_id].as_deref_mut() {
+    if ide::ROOMS.assets()[room_id].is_some() {
         let new_name = UStr::from_ptr(&new_name);
         let new_name_slice = new_name.as_slice();
         if new_name_slice.is_empty() {
@@ -1875,34 +1859,181 @@ unsafe extern "fastcall" fn rename_room(room_id: usize, new_name: *const u16) ->
             show_message("Can't use illegal character '=' in asset name.");
             return ptr::null()
         }
-        let old_name = room_names[room_id].to_os_string().into_string().unwrap();
-        let new_name = new_name.to_os_string().into_string().unwrap();
-        fix_instances_when_renaming_room(room, &old_name, &new_name);
+        // instance names can't mean anything else, so those are always updated
+        // references to the room itself are offered along with other renames when saving
+        let old_name = escape_utf16(room_names[room_id].as_slice());
+        let new_name = escape_utf16(new_name.as_slice());
+        rename_references(&Rename::instances(&old_name, &new_name), true);
     }
     &room_names[room_id]
 }
 
-lazy_static! {
-    static ref ROOM_RENAME_REGEX: Regex = Regex::new(r"=[ \t\r\n]*([^=]*?)_[0-9A-F]{8}").unwrap();
+/// Names of every asset by type and id as of the last load or save, along with the game id of the project they're from.
+/// Used to find out what's been renamed since.
+static mut ASSET_NAMES: Option<(usize, Vec<Vec<String>>)> = None;
+
+/// Asset types in the order of [`asset_names`], as they're called in messages.
+const ASSET_KINDS: [&str; 9] =
+    ["sprite", "sound", "background", "path", "script", "font", "timeline", "object", "room"];
+
+unsafe fn asset_names() -> Vec<Vec<String>> {
+    fn names<T, AL: AssetListTrait<T>>(list: &AL) -> Vec<String> {
+        list.names()
+            .iter()
+            .zip(list.assets())
+            .map(|(name, asset)| if asset.is_some() { escape_utf16(name.as_slice()) } else { String::new() })
+            .collect()
+    }
+    vec![
+        names(&ide::SPRITES),
+        names(&ide::SOUNDS),
+        names(&ide::BACKGROUNDS),
+        names(&ide::PATHS),
+        names(&ide::SCRIPTS),
+        names(&ide::FONTS),
+        names(&ide::TIMELINES),
+        names(&ide::OBJECTS),
+        names(&ide::ROOMS),
+    ]
+}
+
+unsafe fn remember_asset_names() {
+    ASSET_NAMES = Some((ide::GAME_ID.read(), asset_names()));
+}
+
+/// Finds assets that were renamed since the project was loaded or saved, and asks whether to update code that refers
+/// to them. GML doesn't say what an identifier refers to, so this can't be done without asking.
+unsafe fn update_renamed_references() {
+    let names = asset_names();
+    if let Some((game_id, old_names)) = &ASSET_NAMES {
+        if *game_id == ide::GAME_ID.read() {
+            let mut renames = Vec::new();
+            for (kind, (old_names, new_names)) in ASSET_KINDS.iter().zip(old_names.iter().zip(&names)) {
+                for (old, new) in old_names.iter().zip(new_names) {
+                    // if another asset has the old name now, the code might be referring to that
+                    if !old.is_empty() && !new.is_empty() && old != new && !new_names.contains(old) {
+                        let count = rename_references(&Rename::asset(old, new), false);
+                        if count != 0 {
+                            renames.push((kind, old, new, count));
+                        }
+                    }
+                }
+            }
+            if !renames.is_empty() {
+                let list = renames
+                    .iter()
+                    .map(|(kind, old, new, count)| {
+                        format!("{} {} to {} ({} {})", kind, old, new, count, if *count == 1 { "use" } else { "uses" })
+                    })
+                    .join("\n");
+                let message = UStr::new(format!(
+                    "Some resources have been renamed:\n{}\n\nUpdate the code that uses them? \
+                     Local variables with the same names will be renamed too.",
+                    list
+                ));
+                if show_question(&message) == 6 {
+                    for (kind, old, new, _) in &renames {
+                        let rename = if **kind == "room" { Rename::room(old, new) } else { Rename::asset(old, new) };
+                        rename_references(&rename, true);
+                    }
+                }
+            }
+        }
+    }
+    ASSET_NAMES = Some((ide::GAME_ID.read(), names));
 }
 
-fn fix_instances_when_renaming_room(room: &mut asset::Room, old_name: &str, new_name: &str) {
-    let re: &Regex = &ROOM_RENAME_REGEX;
-    for inst in room.get_instances_mut() {
-        let code = inst.creation_code.to_os_string().into_string().unwrap();
-        let mut it = re.captures_iter(&code).filter_map(|c| c.get(1)).filter(|m| m.as_str() == old_name).peekable();
-        if it.peek().is_none() {
-            continue
+/// Applies a rename to all the code in the project, the same way gm82tool's rename does, returning how many references
+/// there were. If `apply` is false, they're only counted. Anything that changes is marked as changed so it gets saved.
+unsafe fn rename_references(rename: &Rename, apply: bool) -> usize {
+    let rename_str = |s: &mut UStr| -> usize {
+        let mut code = escape_utf16(s.as_slice());
+        let count = rename.gml(&mut code);
+        if apply && count != 0 {
+            *s = load::unescape(&code);
+        }
+        count
+    };
+    let rename_event = |event: &mut asset::Event| -> usize {
+        let mut count = 0;
+        for action in event.get_actions_mut() {
+            let action = &mut **action;
+            let params = &mut action.param_strings;
+            match action.action_kind {
+                // arguments that are expressions, or strings that can be
+                0 => {
+                    for (param, ty) in params.iter_mut().zip(action.param_types).take(action.param_count as usize) {
+                        if matches!(ty, 0 | 2) {
+                            count += rename_str(param);
+                        }
+                    }
+                },
+                // repeat, or code
+                5 | 7 => count += rename_str(&mut params[0]),
+                // variable
+                6 => count += rename_str(&mut params[0]) + rename_str(&mut params[1]),
+                _ => (),
+            }
         }
-        let mut new_code = String::with_capacity(code.len());
-        let mut last_match = 0;
-        for m in it {
-            new_code.push_str(&code[last_match..m.start()]);
-            new_code.push_str(&new_name);
-            last_match = m.end();
+        count
+    };
+    let mut total = 0;
+    // marks something as changed if it had any references, and adds them to the total
+    let mut mark = |count: usize, updated: *mut bool, timestamp: Option<&mut f64>| {
+        if apply && count != 0 {
+            *updated = true;
+            if let Some(timestamp) = timestamp {
+                delphi::Now(timestamp);
+            }
+        }
+        total += count;
+    };
+    for value in ide::get_constants_mut() {
+        mark(rename_str(value), ide::CONSTANTS_UPDATED, None);
+    }
+    for trigger in ide::get_triggers_mut().iter_mut().flatten() {
+        mark(rename_str(&mut trigger.condition), ide::TRIGGERS_UPDATED, None);
+    }
+    for (script, timestamp) in ide::SCRIPTS.assets_mut().iter_mut().zip(ide::SCRIPTS.timestamps_mut()) {
+        if let Some(script) = script.as_deref_mut() {
+            mark(rename_str(&mut script.source), ide::SCRIPTS_UPDATED, Some(timestamp));
+        }
+    }
+    for (timeline, timestamp) in ide::TIMELINES.assets_mut().iter_mut().zip(ide::TIMELINES.timestamps_mut()) {
+        if let Some(timeline) = timeline.as_deref_mut() {
+            let count = timeline.get_events_mut().iter_mut().map(|event| rename_event(&mut **event)).sum();
+            mark(count, ide::TIMELINES_UPDATED, Some(timestamp));
+        }
+    }
+    for (object, timestamp) in ide::OBJECTS.assets_mut().iter_mut().zip(ide::OBJECTS.timestamps_mut()) {
+        if let Some(object) = object.as_deref_mut() {
+            let count = object
+                .events
+                .iter_mut()
+                .flat_map(|events| events.iter_mut())
+                .map(|event| rename_event(&mut **event))
+                .sum();
+            mark(count, ide::OBJECTS_UPDATED, Some(timestamp));
+        }
+    }
+    for (room, timestamp) in ide::ROOMS.assets_mut().iter_mut().zip(ide::ROOMS.timestamps_mut()) {
+        if let Some(room) = room.as_deref_mut() {
+            let mut count = rename_str(&mut room.creation_code);
+            for instance in room.get_instances_mut() {
+                count += rename_str(&mut instance.creation_code);
+            }
+            mark(count, ide::ROOMS_UPDATED, Some(timestamp));
+        }
+    }
+    total
+}
+
+fn fix_instances_when_renaming_room(room: &mut asset::Room, rename: Rename) {
+    for inst in room.get_instances_mut() {
+        let mut code = escape_utf16(inst.creation_code.as_slice());
+        if rename.gml(&mut code) != 0 {
+            inst.creation_code = load::unescape(&code);
         }
-        new_code.push_str(&code[last_match..]);
-        inst.creation_code = UStr::new(new_code);
     }
 }
 
@@ -1932,26 +2063,37 @@ unsafe extern "fastcall" fn dont_make_room_form(node: &TTreeNode) {
 unsafe extern "C" fn show_instance_id_inj() {
     naked_asm!(
         "mov ecx, eax",
-        "mov eax, [ebx + 0x630]",
-        "push eax",
+        "push dword ptr [ebx + 0x61c]", // room
+        "push dword ptr [ebx + 0x630]", // room id
         "call {}",
         "ret",
         sym show_instance_id,
     );
 }
 
-unsafe extern "fastcall" fn show_instance_id(id: usize, out: &mut UStr, room_id: usize) {
+unsafe extern "fastcall" fn show_instance_id(id: usize, out: &mut UStr, room_id: usize, room: &asset::Room) {
     if let Some((insts, _)) = EXTRA_DATA.as_mut() {
+        let room_name: String = ide::ROOMS.names().get_asset(room_id as _);
         let suffix = {
-            let mut name = insts.entry(id).or_default().name;
+            let mut name = insts.get(&id).map(|ex| ex.name).unwrap_or_default();
             if name == 0 {
-                loop {
-                    name = delphi::Random();
-                    if !insts.values().any(|ex| ex.name == name) {
-                        insts.get_mut(&id).unwrap().name = name;
-                        break
-                    }
-                }
+                // same as what saving would give it, as long as nothing changes in the meantime
+                let instances = room.get_instances();
+                let (object, x, y, counter) = match instances.iter().position(|i| i.id == id) {
+                    Some(pos) => {
+                        let inst = &instances[pos];
+                        let counter = instances[..pos]
+                            .iter()
+                            .filter(|i| (i.object, i.x, i.y) == (inst.object, inst.x, inst.y))
+                            .count();
+                        (inst.object, inst.x, inst.y, counter as u32)
+                    },
+                    None => (-1, 0, 0, 0),
+                };
+                let object: String = ide::OBJECTS.names().get_asset(object);
+                name =
+                    derive_instance_name(&room_name, &object, x, y, counter, |n| insts.values().any(|ex| ex.name == n));
+                insts.entry(id).or_default().name = name;
             }
             UStr::new(format!("_{:08X}", name))
         };
@@ -2112,7 +2254,7 @@ unsafe extern "fastcall" fn room_form(room_id: usize) -> u32 {
                 .zip(ide::PATHS.names_mut())
                 .try_for_each(|((name, asset), name_p)| -> Result<()> {
                     if !name.is_empty() {
-                        *name_p = UStr::new(name);
+                        *name_p = load::load_text(name);
                         *asset = Some(load::load_path(&mut asset_maps_path.join(name), &asset_maps)?);
                     }
                     Ok(())
@@ -2662,10 +2804,6 @@ unsafe fn injector() {
     patch_timestamp_mask(0x6f34e8);
     patch_timestamp_mask(0x6f3555);
 
-    // check for time going backwards
-    patch(0x4199fb, &[0xe9]);
-    patch_call(0x4199fb, reset_if_time_went_backwards as _);
-
     patch_call(0x6cd928, save_exe::save_assets_inj::<asset::Sound> as usize);
     patch_call(0x6cd943, save_exe::save_assets_inj::<asset::Sprite> as usize);
     patch_call(0x6cd95e, save_exe::save_assets_inj::<asset::Background> as usize);
@@ -1,5 +1,5 @@
 use crate::{
-    ACTION_TOKEN, EXTRA_DATA, Error, GMLLines, InstanceExtra, PATH_FORM_UPDATED, Result, TileExtra,
+    ACTION_TOKEN, EXTRA_DATA, Error, InstanceExtra, PATH_FORM_UPDATED, PRESERVE_IDS, Result, TileExtra,
     asset::*,
     delphi,
     delphi::{DelphiBox, UStr, advance_progress_form},
@@ -8,8 +8,11 @@ use crate::{
     regular::project_watcher,
     run_while_updating_bar, show_message, update_timestamp,
 };
+use gm82project::{
+    GMLLines,
+    load::{split_fields, unescape_defines, unescape_utf16},
+};
 use itertools::izip;
-use parking_lot::Mutex;
 use rayon::prelude::*;
 use std::{
     collections::{HashMap, HashSet},
@@ -25,8 +28,23 @@ fn undelimit(s: &str) -> String {
     s.replace("*\\/", "*/").replace("\\n", "\n").replace("\\r", "\r").replace("\\\\", "\\")
 }
 
+/// Whether the project on disk has invalid UTF-16 escaped, which started with gm82_version 6.
+/// In older projects, text that looks like an escape is just text.
+pub static mut ESCAPED_UTF16: bool = true;
+
+/// Reads text that was saved with any invalid UTF-16 escaped.
+pub fn unescape(s: &str) -> UStr {
+    UStr::from_wide(&unescape_utf16(s))
+}
+
+/// Reads text from the project, only unescaping it if the project is new enough to have been escaped.
+pub fn load_text(s: &str) -> UStr {
+    if unsafe { ESCAPED_UTF16 } { unescape(s) } else { UStr::new(s) }
+}
+
 pub trait UStrPtr {
     fn asg(self, s: impl AsRef<OsStr>);
+    fn asg_unescape(self, s: &str);
     fn asg_undelimit(self, s: &str);
 }
 
@@ -37,19 +55,25 @@ impl UStrPtr for *mut UStr {
         }
     }
 
+    fn asg_unescape(self, s: &str) {
+        unsafe {
+            delphi::UStrAsg(self.as_mut().unwrap(), &load_text(s));
+        }
+    }
+
     fn asg_undelimit(self, s: &str) {
-        self.asg(&undelimit(s));
+        self.asg_unescape(&undelimit(s));
     }
 }
 
 fn load_gml(code: &str) -> UStr {
     let mut buf = String::with_capacity(code.len());
     // don't use string.replace() in case your gml is \r\n for some reason
-    for line in GMLLines::new(code.lines()) {
+    for line in GMLLines::new(code) {
         buf += line;
         buf += "\r\n";
     }
-    UStr::new(buf)
+    load_text(&buf)
 }
 
 pub struct Assets {
@@ -135,7 +159,7 @@ pub unsafe fn read_resource_tree(
             0
         };
 
-        let node = &*nodes.AddChild(*stack.last().unwrap(), &UStr::new(name));
+        let node = &*nodes.AddChild(*stack.last().unwrap(), &load_text(name));
         node.SetData(delphi::TreeNodeData::new(rtype, kind, index));
         node.SetImageIndex(1);
         if rtype == 2 {
@@ -156,12 +180,12 @@ fn load_triggers(maps: &AssetMaps, path: &mut PathBuf) -> Result<()> {
             continue
         }
         let mut trig = Trigger::new();
-        trig.name = UStr::new(name);
+        trig.name = load_text(name);
         path.push(name);
         path.set_extension("txt");
         read_txt(&path, |k, v| {
             match k {
-                "constant" => trig.constant_name = UStr::new(v),
+                "constant" => trig.constant_name = load_text(v),
                 "kind" => trig.kind = v.parse()?,
                 _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
             }
@@ -193,9 +217,9 @@ unsafe fn load_sound(path: &mut PathBuf, _asset_maps: &AssetMaps) -> Result<Delp
         match k {
             "extension" => {
                 extension = v.to_string();
-                snd.extension = UStr::new(v)
+                snd.extension = load_text(v)
             },
-            "source" => snd.source = UStr::new(v),
+            "source" => snd.source = load_text(v),
             "exists" => exists = v.parse::<u8>()? != 0,
             "kind" => snd.kind = v.parse()?,
             "effects" => snd.effects = v.parse()?,
@@ -356,7 +380,7 @@ fn load_font(path: &mut PathBuf, _asset_maps: &AssetMaps) -> Result<DelphiBox<Fo
     path.set_extension("txt");
     read_txt(path, |k, v| {
         match k {
-            "name" => f.sys_name = UStr::new(v),
+            "name" => f.sys_name = load_text(v),
             "size" => f.size = v.parse()?,
             "bold" => f.bold = v.parse::<u8>()? != 0,
             "italic" => f.italic = v.parse::<u8>()? != 0,
@@ -428,8 +452,8 @@ unsafe fn load_event(
                         }
                     },
                     "invert" => action.invert_condition = v.parse::<u8>()? != 0,
-                    "var_name" | "repeats" => action.param_strings[0] = UStr::new(v),
-                    "var_value" => action.param_strings[1] = UStr::new(v),
+                    "var_name" | "repeats" => action.param_strings[0] = load_text(v),
+                    "var_value" => action.param_strings[1] = load_text(v),
                     "arg0" | "arg1" | "arg2" | "arg3" | "arg4" | "arg5" | "arg6" | "arg7" => {
                         if !act_id_set {
                             return Err(Error::SyntaxError(path.to_path_buf()))
@@ -461,7 +485,7 @@ unsafe fn load_event(
                                 .to_string(),
                             );
                         } else {
-                            action.param_strings[i] = UStr::new(undelimit(v));
+                            action.param_strings[i] = load_text(&undelimit(v));
                         }
                     },
                     _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
@@ -479,7 +503,7 @@ unsafe fn load_event(
             } else {
                 code
             };
-            action.param_strings[0] = load_gml(code);
+            action.param_strings[0] = load_gml(&unescape_defines(code));
         }
     }
     Ok(())
@@ -609,8 +633,8 @@ pub fn load_path(file_path: &mut PathBuf, asset_maps: &AssetMaps) -> Result<Delp
     let points_txt = read_file(&file_path)?;
     let point_lines: Vec<_> = points_txt.par_lines().collect();
     for (point, line) in path.alloc_points(point_lines.len()).iter_mut().zip(point_lines) {
-        let mut iter = line.split(',');
         let err = || Error::SyntaxError(file_path.to_path_buf());
+        let mut iter = split_fields(line).ok_or_else(err)?.into_iter();
         point.x = iter.next().ok_or_else(err)?.parse()?;
         point.y = iter.next().ok_or_else(err)?.parse()?;
         point.speed = iter.next().ok_or_else(err)?.parse()?;
@@ -623,6 +647,30 @@ pub fn load_path(file_path: &mut PathBuf, asset_maps: &AssetMaps) -> Result<Delp
     Ok(path)
 }
 
+/// Checks that an id from the project isn't taken already, or hands out a new one if there wasn't one,
+/// keeping `last_id` at the highest id in use.
+fn take_id<T>(
+    id: usize,
+    last_id: &mut usize,
+    taken: &HashMap<usize, T>,
+    kind: &'static str,
+    room: &str,
+) -> Result<usize> {
+    if id == 0 {
+        loop {
+            *last_id += 1;
+            if !taken.contains_key(last_id) {
+                return Ok(*last_id)
+            }
+        }
+    }
+    if taken.contains_key(&id) {
+        return Err(Error::DuplicateId(kind, id, room.to_string()))
+    }
+    *last_id = (*last_id).max(id);
+    Ok(id)
+}
+
 unsafe fn load_instances(room: &mut Room, path: &mut PathBuf, objs: &HashMap<String, usize>) -> Result<()> {
     path.push("instances.txt");
     let instances_txt = read_file(&path)?;
@@ -631,13 +679,11 @@ unsafe fn load_instances(room: &mut Room, path: &mut PathBuf, objs: &HashMap<Str
     path.pop();
     let room_name = path.file_name().map(OsStr::to_string_lossy).unwrap_or_default();
     let err = || Error::SyntaxError(inst_path.to_path_buf());
-    let last_instance_id = *ide::LAST_INSTANCE_ID + 1;
-    let ids = Mutex::new(HashSet::with_capacity(instances.len()));
     let mut extras: Vec<InstanceExtra> = vec![Default::default(); instances.len()];
-    room.alloc_instances(instances.len()).into_par_iter().zip(&instances).enumerate().zip(&mut extras).try_for_each(
-        |((i, (instance, line)), extra)| -> Result<()> {
-            let mut iter = line.split(',');
-            instance.object = match iter.next().ok_or_else(err)? {
+    room.alloc_instances(instances.len()).into_par_iter().zip(&instances).zip(&mut extras).try_for_each(
+        |((instance, line), extra)| -> Result<()> {
+            let mut iter = split_fields(line).ok_or_else(err)?.into_iter();
+            instance.object = match &*iter.next().ok_or_else(err)? {
                 "" => -1,
                 obj => *objs.get(obj).ok_or_else(|| {
                     Error::AssetNotFound(obj.to_string(), "object", format!("room {room_name} instances"))
@@ -646,26 +692,8 @@ unsafe fn load_instances(room: &mut Room, path: &mut PathBuf, objs: &HashMap<Str
             instance.x = iter.next().ok_or_else(err)?.parse()?;
             instance.y = iter.next().ok_or_else(err)?.parse()?;
             let code_hash = iter.next().ok_or_else(err)?;
-            extra.name = if !code_hash.is_empty() {
-                // check if hash is taken
-                let mut id = u32::from_str_radix(code_hash, 16)?;
-                // while it's taken, generate random id
-                while ids.lock().contains(&id) {
-                    id = delphi::Random();
-                }
-                id
-            } else {
-                // no id, generate random id until we get an unused one
-                loop {
-                    let id = delphi::Random();
-                    if !ids.lock().contains(&id) {
-                        break id
-                    }
-                }
-            };
-            ids.lock().insert(extra.name);
+            extra.name = if !code_hash.is_empty() { u32::from_str_radix(&code_hash, 16)? } else { 0 };
             instance.locked = iter.next().ok_or_else(err)?.parse::<u8>()? != 0;
-            instance.id = last_instance_id + i;
             if let Some(s) = iter.next() {
                 extra.xscale = s.parse()?;
             }
@@ -678,22 +706,31 @@ unsafe fn load_instances(room: &mut Room, path: &mut PathBuf, objs: &HashMap<Str
             if let Some(s) = iter.next() {
                 extra.angle = s.parse()?;
             }
-            // use the id from the file, not the generated one if relevant
+            // the code file goes by the name in the file, even if that gets dropped below for being taken
             let has_code = if let Some(s) = iter.next() { s.parse::<u8>()? != 0 } else { !code_hash.is_empty() };
             if has_code {
-                let mut path = path.join(code_hash);
+                let mut path = path.join(&*code_hash);
                 path.set_extension("gml");
                 instance.creation_code = load_gml(&read_file(&path)?);
             }
+            // only there if the project preserves ids, otherwise one gets handed out below
+            instance.id = if let Some(s) = iter.next() { s.parse()? } else { 0 };
             Ok(())
         },
     )?;
+    // names that are already taken go back to none, so saving derives new ones for them
+    let mut names = HashSet::with_capacity(extras.len());
+    for extra in &mut extras {
+        if extra.name != 0 && !names.insert(extra.name) {
+            extra.name = 0;
+        }
+    }
     let extra_data = &mut EXTRA_DATA.as_mut().unwrap().0;
     extra_data.reserve(extras.len());
-    for (inst, extra) in room.get_instances().iter().zip(extras.drain(..)) {
+    for (inst, extra) in room.get_instances_mut().iter_mut().zip(extras.drain(..)) {
+        inst.id = take_id(inst.id, &mut *ide::LAST_INSTANCE_ID, extra_data, "instance", &room_name)?;
         extra_data.insert(inst.id, extra);
     }
-    *ide::LAST_INSTANCE_ID += instances.len();
     Ok(())
 }
 
@@ -717,16 +754,14 @@ unsafe fn load_tiles(path: &mut PathBuf, bgs: &HashMap<String, usize>) -> Result
         let layer: Vec<_> = layer_txt.lines().filter(|s| !s.is_empty()).collect();
         tiles.reserve(layer.len());
         let err = || Error::SyntaxError(path.to_path_buf());
-        let last_tile_id = *ide::LAST_TILE_ID + 1;
         let mut extras: Vec<TileExtra> = vec![Default::default(); layer.len()];
-        let layer_tiles = layer
+        let mut layer_tiles = layer
             .par_iter()
-            .enumerate()
             .zip(&mut extras)
-            .map(|((i, tile), extra)| {
-                let mut iter = tile.split(',');
-                let t = Tile {
-                    source_bg: match iter.next().ok_or_else(err)? {
+            .map(|(tile, extra)| {
+                let mut iter = split_fields(tile).ok_or_else(err)?.into_iter();
+                let mut t = Tile {
+                    source_bg: match &*iter.next().ok_or_else(err)? {
                         "" => -1,
                         bg => *bgs.get(bg).ok_or_else(|| {
                             Error::AssetNotFound(bg.to_string(), "background", format!("room {room_name} tiles"))
@@ -740,7 +775,7 @@ unsafe fn load_tiles(path: &mut PathBuf, bgs: &HashMap<String, usize>) -> Result
                     height: iter.next().ok_or_else(err)?.parse()?,
                     locked: iter.next().ok_or_else(err)?.parse::<u8>()? != 0,
                     depth,
-                    id: last_tile_id + i,
+                    id: 0,
                 };
                 if let Some(s) = iter.next() {
                     extra.xscale = s.parse()?;
@@ -751,6 +786,10 @@ unsafe fn load_tiles(path: &mut PathBuf, bgs: &HashMap<String, usize>) -> Result
                 if let Some(s) = iter.next() {
                     extra.blend = s.parse()?;
                 }
+                // only there if the project preserves ids
+                if let Some(s) = iter.next() {
+                    t.id = s.parse()?;
+                }
                 if iter.next() != None {
                     return Err(err())
                 }
@@ -758,10 +797,10 @@ unsafe fn load_tiles(path: &mut PathBuf, bgs: &HashMap<String, usize>) -> Result
             })
             .collect::<Result<Vec<_>>>()?;
         extra_data.reserve(extras.len());
-        for (tile, extra) in layer_tiles.iter().zip(extras.drain(..)) {
+        for (tile, extra) in layer_tiles.iter_mut().zip(extras.drain(..)) {
+            tile.id = take_id(tile.id, &mut *ide::LAST_TILE_ID, extra_data, "tile", &room_name)?;
             extra_data.insert(tile.id, extra);
         }
-        *ide::LAST_TILE_ID += layer_tiles.len();
         tiles.extend_from_slice(&layer_tiles);
         path.pop();
     }
@@ -774,7 +813,7 @@ pub unsafe fn load_room(path: &mut PathBuf, asset_maps: &AssetMaps) -> Result<De
     let room_name = path.parent().and_then(std::path::Path::file_name).map(OsStr::to_string_lossy).unwrap_or_default();
     read_txt(&path, |k, v| {
         match k {
-            "caption" => room.caption = UStr::new(v),
+            "caption" => room.caption = load_text(v),
             "width" => room.width = v.parse()?,
             "height" => room.height = v.parse()?,
             "snap_x" => room.snap_x = v.parse()?,
@@ -880,8 +919,8 @@ unsafe fn load_constants(path: &mut PathBuf) -> Result<()> {
     ide::alloc_constants(lines.len());
     for (line, name_p, value_p) in izip!(lines, ide::get_constant_names_mut(), ide::get_constants_mut()) {
         decode_line(&path, line, &mut |name, value| {
-            *name_p = UStr::new(name);
-            *value_p = UStr::new(value);
+            *name_p = load_text(name);
+            *value_p = load_text(value);
             Ok(())
         })?;
     }
@@ -896,7 +935,7 @@ unsafe fn load_included_files(path: &mut PathBuf) -> Result<()> {
     let files: Vec<_> = index.par_lines().collect();
     ide::alloc_included_files(files.len());
     for (fname, file) in files.iter().zip(ide::get_included_files_mut()) {
-        file.file_name = UStr::new(fname);
+        file.file_name = load_text(fname);
         path.push(fname.to_string() + ".txt");
         read_txt(&path, |k, v| {
             match k {
@@ -905,7 +944,7 @@ unsafe fn load_included_files(path: &mut PathBuf) -> Result<()> {
                 "overwrite" => file.overwrite_file = v.parse::<u8>()? != 0,
                 "remove" => file.remove_at_end = v.parse::<u8>()? != 0,
                 "export" => file.export_setting = v.parse()?,
-                "export_folder" => file.export_custom_folder = UStr::new(v),
+                "export_folder" => file.export_custom_folder = load_text(v),
                 _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
             }
             Ok(())
@@ -942,7 +981,7 @@ unsafe fn load_game_information(path: &mut PathBuf) -> Result<()> {
                 let _: u32 = delphi_call!(0x4ee858, editor, v.parse::<u32>()?);
             },
             "new_window" => NEW_WINDOW.write(v.parse::<u8>()? != 0),
-            "caption" => CAPTION.asg(v),
+            "caption" => CAPTION.asg_unescape(v),
             "left" => LEFT.write(v.parse()?),
             "top" => TOP.write(v.parse()?),
             "width" => WIDTH.write(v.parse()?),
@@ -1061,7 +1100,7 @@ unsafe fn load_settings(path: &mut PathBuf) -> Result<()> {
             if let Some((_, loaded)) = ide::get_extensions()
                 .iter()
                 .zip(ide::get_extensions_loaded_mut())
-                .find(|(ex, _)| ex.name.to_os_string() == OsStr::new(&name))
+                .find(|(ex, _)| ex.name.as_slice() == load_text(&name).as_slice())
             {
                 *loaded = true;
                 any = true;
@@ -1111,12 +1150,18 @@ unsafe fn load_assets<'a, T: 'static + Sync, AL: AssetListTrait<T> + Sync>(
     if names.is_empty() && name == "objects" {
         the_assets.alloc(1);
     }
+    let timestamps = gm82project::load::read_timestamps(&path.join("timestamps.yyd"))?;
+    for (name, timestamp) in names.iter().zip(the_assets.timestamps_mut()) {
+        if let Some(t) = timestamps.get(name) {
+            *timestamp = *t;
+        }
+    }
     if name != "rooms" {
         run_while_updating_bar(bar_start, bar_end, names.len() as u32, |tx| {
             names.par_iter().zip(the_assets.assets_mut()).zip(the_assets.names_mut()).try_for_each(
                 |((name, asset), name_p)| -> Result<()> {
                     if !name.is_empty() {
-                        *name_p = UStr::new(name);
+                        *name_p = load_text(name);
                         *asset = Some(load_asset(&mut path.join(name), asset_maps)?);
                     }
                     let _ = tx.send(());
@@ -1129,7 +1174,7 @@ unsafe fn load_assets<'a, T: 'static + Sync, AL: AssetListTrait<T> + Sync>(
             names.iter().zip(the_assets.assets_mut()).zip(the_assets.names_mut()).try_for_each(
                 |((name, asset), name_p)| -> Result<()> {
                     if !name.is_empty() {
-                        *name_p = UStr::new(name);
+                        *name_p = load_text(name);
                         *asset = Some(load_asset(&mut path.join(name), asset_maps)?);
                     }
                     let _ = tx.send(());
@@ -1169,9 +1214,15 @@ pub fn load_asset_maps(
 }
 
 pub unsafe fn load_gmk(mut path: PathBuf) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        // a save that got interrupted after it was committed still needs to be finished
+        gm82project::transaction::recover(dir)?;
+    }
     ide::initialize_project();
     PATH_FORM_UPDATED = false;
     EXTRA_DATA = Some(Default::default());
+    PRESERVE_IDS = false;
+    ESCAPED_UTF16 = false;
     let mut has_backgrounds = true;
     let mut has_datafiles = true;
     let mut has_fonts = true;
@@ -1185,20 +1236,23 @@ pub unsafe fn load_gmk(mut path: PathBuf) -> Result<()> {
     let mut importing_old_version = false;
     read_txt(&path, |k, v| {
         match k {
-            "gm82_version" => match v.parse::<u8>()? {
-                newer if newer > 5 => return Err(Error::OldGM82),
-                older if older < 5 => importing_old_version = true,
-                _ => (),
+            "gm82_version" => {
+                let version = v.parse::<u8>()?;
+                if version > 6 {
+                    return Err(Error::OldGM82)
+                }
+                importing_old_version = version < 5;
+                ESCAPED_UTF16 = version >= 6;
             },
             "gameid" => ide::GAME_ID.write(v.parse()?),
-            "info_author" => ide::settings::INFO_AUTHOR.asg(v),
-            "info_version" => ide::settings::INFO_VERSION.asg(v),
+            "info_author" => ide::settings::INFO_AUTHOR.asg_unescape(v),
+            "info_version" => ide::settings::INFO_VERSION.asg_unescape(v),
             "info_timestamp" => ide::settings::INFO_TIMESTAMP.write(v.parse()?), // legacy
             "info_information" => ide::settings::INFO_INFORMATION.asg_undelimit(v),
-            "exe_company" => ide::settings::EXE_COMPANY.asg(v),
-            "exe_product" => ide::settings::EXE_PRODUCT.asg(v),
-            "exe_copyright" => ide::settings::EXE_COPYRIGHT.asg(v),
-            "exe_description" => ide::settings::EXE_DESCRIPTION.asg(v),
+            "exe_company" => ide::settings::EXE_COMPANY.asg_unescape(v),
+            "exe_product" => ide::settings::EXE_PRODUCT.asg_unescape(v),
+            "exe_copyright" => ide::settings::EXE_COPYRIGHT.asg_unescape(v),
+            "exe_description" => ide::settings::EXE_DESCRIPTION.asg_unescape(v),
             "exe_version" => {
                 let err = || Error::InvalidVersion(v.to_string());
                 let mut iter = v.split('.');
@@ -1220,6 +1274,10 @@ pub unsafe fn load_gmk(mut path: PathBuf) -> Result<()> {
             "has_sprites" => has_sprites = v.parse::<u8>()? != 0,
             "has_timelines" => has_timelines = v.parse::<u8>()? != 0,
             "has_triggers" => has_triggers = v.parse::<u8>()? != 0,
+            "preserve_ids" => PRESERVE_IDS = v.parse::<u8>()? != 0,
+            // fresh ids have to go after every id in the project
+            "last_instance_id" => *ide::LAST_INSTANCE_ID = (*ide::LAST_INSTANCE_ID).max(v.parse()?),
+            "last_tile_id" => *ide::LAST_TILE_ID = (*ide::LAST_TILE_ID).max(v.parse()?),
             _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
         }
         Ok(())
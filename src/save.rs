@@ -1,19 +1,26 @@
 use crate::{
-    ACTION_TOKEN, EXTRA_DATA, Error, GMLLines, InstanceExtra, LAST_SAVE, PATH_FORM_UPDATED, Result,
-    SAW_APPLIES_TO_WARNING, TileExtra,
+    ACTION_TOKEN, EXTRA_DATA, Error, InstanceExtra, PATH_FORM_UPDATED, PRESERVE_IDS, Result, SAW_APPLIES_TO_WARNING,
+    TileExtra,
     asset::*,
     delphi,
     delphi::{DelphiBox, TTreeNode, UStr, advance_progress_form},
     events, ide,
     ide::AssetListTrait,
+    load,
     regular::project_watcher,
     run_while_updating_bar, show_message, update_timestamp,
 };
-use itertools::Itertools;
+use gm82project::{
+    GMLLines,
+    save::{delimit, derive_instance_name, escape_defines, escape_utf16, filename_invalid, quote_field},
+    transaction::{self, Transaction},
+};
+use itertools::{Itertools, izip};
 use png::Compression;
 use rayon::prelude::*;
 use std::{
     collections::{HashMap, HashSet},
+    ffi::OsString,
     fs::File,
     io::{BufWriter, Write},
     path::PathBuf,
@@ -21,19 +28,23 @@ use std::{
 };
 
 impl UStr {
-    fn try_decode(&self) -> Result<String> {
-        self.to_os_string().into_string().map_err(|e| Error::UnicodeError(e.to_string_lossy().into()))
+    // GameMaker doesn't care if its UTF-16 is valid, so anything that isn't gets escaped instead of failing the save
+    fn decode(&self) -> String {
+        escape_utf16(self.as_slice())
     }
 
-    fn try_decode_opt(&self) -> Option<String> {
-        // i feel like having this separately might provide better optimization?
-        self.to_os_string().into_string().ok()
+    fn delimit(&self) -> String {
+        delimit(&self.decode())
     }
+}
 
-    fn try_delimit(&self) -> Result<String> {
-        self.try_decode()
-            .map(|s| s.replace('\\', "\\\\").replace('\r', "\\r").replace('\n', "\\n").replace("*/", "*\\/"))
+/// Checks that a name can be a file, returning the character that's the problem.
+/// Invalid UTF-16 can't be escaped in a file name the way it is in text, so that gives U+FFFD.
+fn name_invalid(name: &UStr) -> Option<char> {
+    if char::decode_utf16(name.as_slice().iter().copied()).any(|c| c.is_err()) {
+        return Some(char::REPLACEMENT_CHARACTER)
     }
+    filename_invalid(&name.decode()).map(char::from)
 }
 
 pub trait GetAsset<T> {
@@ -55,7 +66,7 @@ impl<'a, T> GetAsset<Option<&'a T>> for &'a [Option<&'a T>] {
 impl<'a> GetAsset<String> for &'a [UStr] {
     fn get_asset(&self, id: i32) -> String {
         // it's ok to ignore errors here because it's invalid UTF-16 that'll get caught elsewhere
-        usize::try_from(id).ok().and_then(|id| self.get(id)).and_then(|s| s.try_decode_opt()).unwrap_or_default()
+        usize::try_from(id).ok().and_then(|id| self.get(id)).map(|s| s.decode()).unwrap_or_default()
     }
 }
 
@@ -65,21 +76,6 @@ impl<'a> GetAsset<f64> for &'a [f64] {
     }
 }
 
-fn filename_invalid(s: &str) -> Option<u8> {
-    if s == "." || s == ".." || s.as_bytes().last().copied() == Some(b'.') {
-        return Some(b'.')
-    }
-    if s.trim().is_empty() {
-        return Some(b' ')
-    }
-    for c in b"<>:\"/\\|?*" {
-        if s.as_bytes().contains(c) {
-            return Some(*c)
-        }
-    }
-    return None
-}
-
 fn make_unicase(s: String, u: &UStr) -> unicase::UniCase<String> {
     // implement my own ascii check because it's Faster
     if s.len() == u.len() {
@@ -103,8 +99,22 @@ fn write_file(path: &std::path::Path, content: impl AsRef<[u8]>) -> Result<()> {
     std::fs::write(path, content).map_err(|e| Error::FileIoError(e, path.to_path_buf()))
 }
 
-fn write_gml<F: Write>(f: &mut F, code: &UStr) -> Result<()> {
-    for line in GMLLines::new(code.try_decode()?.trim_end().lines()) {
+/// Delphi's SaveToFile doesn't report failure, so check that it actually wrote something.
+/// Otherwise the staged project would be missing the file, and committing it would delete the real one.
+fn save_with_delphi(path: &std::path::Path, save: impl FnOnce(&UStr)) -> Result<()> {
+    save(&UStr::new(path));
+    if path.is_file() {
+        Ok(())
+    } else {
+        Err(Error::FileIoError(
+            std::io::Error::new(std::io::ErrorKind::Other, "failed to write file"),
+            path.to_path_buf(),
+        ))
+    }
+}
+
+fn write_gml<F: Write>(f: &mut F, code: &str) -> Result<()> {
+    for line in GMLLines::new(code.trim_end()) {
         writeln!(f, "{}", line)?;
     }
     Ok(())
@@ -112,7 +122,7 @@ fn write_gml<F: Write>(f: &mut F, code: &UStr) -> Result<()> {
 
 fn save_gml(path: &std::path::Path, code: &UStr) -> Result<()> {
     let mut f = open_file(path)?;
-    write_gml(&mut f, code)?;
+    write_gml(&mut f, &code.decode())?;
     f.flush()?;
     Ok(())
 }
@@ -136,12 +146,8 @@ fn save_frame(frame: &Frame, path: &std::path::Path) -> Result<()> {
     Ok(())
 }
 
-fn no_dependencies<T>(_: &T) -> bool {
-    false
-}
-
 fn save_sound(sound: &Sound, path: &mut PathBuf) -> Result<()> {
-    let extension = sound.extension.try_decode()?;
+    let extension = sound.extension.decode();
     path.set_extension(extension.trim_matches('.'));
     if let Some(data) = sound.data.as_ref() {
         write_file(&path, data.get_slice())?;
@@ -150,7 +156,7 @@ fn save_sound(sound: &Sound, path: &mut PathBuf) -> Result<()> {
     let mut f = open_file(&path)?;
     writeln!(f, "extension={}", extension)?;
     writeln!(f, "exists={}", u8::from(!sound.data.is_none()))?;
-    writeln!(f, "source={}", sound.source.try_decode()?)?;
+    writeln!(f, "source={}", sound.source.decode())?;
     writeln!(f, "kind={}", sound.kind)?;
     writeln!(f, "effects={}", sound.effects)?;
     writeln!(f, "volume={}", sound.volume)?;
@@ -205,13 +211,6 @@ fn save_background(back: &Background, path: &mut PathBuf) -> Result<()> {
     Ok(())
 }
 
-unsafe fn path_needs_update(path: &Path) -> bool {
-    PATH_FORM_UPDATED
-        || path.path_editor_room_background >= 0
-            && (ide::ROOMS.assets().get_asset(path.path_editor_room_background).is_none()
-                || ide::ROOMS.timestamps().get_asset(path.path_editor_room_background) > LAST_SAVE)
-}
-
 fn save_path(path: &Path, file_path: &mut PathBuf) -> Result<()> {
     create_dirs(&file_path)?;
     file_path.push("path.txt");
@@ -241,9 +240,14 @@ fn save_script(script: &Script, path: &mut PathBuf) -> Result<()> {
 }
 
 fn save_font(font: &Font, path: &mut PathBuf) -> Result<()> {
+    // the ide doesn't know about font files, so carry over any that are already there
+    for extension in ["ttf", "otf"] {
+        path.set_extension(extension);
+        transaction::keep(path)?;
+    }
     path.set_extension("txt");
     let mut f = open_file(path)?;
-    writeln!(f, "name={}", font.sys_name.try_decode()?)?;
+    writeln!(f, "name={}", font.sys_name.decode())?;
     writeln!(f, "size={}", font.size)?;
     writeln!(f, "bold={}", font.bold as u8)?;
     writeln!(f, "italic={}", font.italic as u8)?;
@@ -255,87 +259,6 @@ fn save_font(font: &Font, path: &mut PathBuf) -> Result<()> {
     Ok(())
 }
 
-unsafe fn event_needs_update(ev: &Event) -> bool {
-    for action in ev.get_actions() {
-        if ide::OBJECTS.timestamps().get_asset(action.applies_to) > LAST_SAVE {
-            return true
-        }
-        if action.action_kind == 0 {
-            for (ty, val) in action.param_types.iter().zip(&action.param_strings) {
-                fn parameter_get_timestamp(ty: u32, val: &UStr) -> Result<f64> {
-                    let val = val.try_decode()?.parse()?;
-                    if val < 0 {
-                        return Ok(0.0)
-                    }
-                    let time = match ty {
-                        5 => {
-                            if ide::SPRITES.assets().get_asset(val).is_none() {
-                                return Ok(f64::MAX)
-                            }
-                            ide::SPRITES.timestamps().get_asset(val)
-                        },
-                        6 => {
-                            if ide::SOUNDS.assets().get_asset(val).is_none() {
-                                return Ok(f64::MAX)
-                            }
-                            ide::SOUNDS.timestamps().get_asset(val)
-                        },
-                        7 => {
-                            if ide::BACKGROUNDS.assets().get_asset(val).is_none() {
-                                return Ok(f64::MAX)
-                            }
-                            ide::BACKGROUNDS.timestamps().get_asset(val)
-                        },
-                        8 => {
-                            if ide::PATHS.assets().get_asset(val).is_none() {
-                                return Ok(f64::MAX)
-                            }
-                            ide::PATHS.timestamps().get_asset(val)
-                        },
-                        9 => {
-                            if ide::SCRIPTS.assets().get_asset(val).is_none() {
-                                return Ok(f64::MAX)
-                            }
-                            ide::SCRIPTS.timestamps().get_asset(val)
-                        },
-                        10 => {
-                            if ide::OBJECTS.assets().get_asset(val).is_none() {
-                                return Ok(f64::MAX)
-                            }
-                            ide::OBJECTS.timestamps().get_asset(val)
-                        },
-                        11 => {
-                            if ide::ROOMS.assets().get_asset(val).is_none() {
-                                return Ok(f64::MAX)
-                            }
-                            ide::ROOMS.timestamps().get_asset(val)
-                        },
-                        12 => {
-                            if ide::FONTS.assets().get_asset(val).is_none() {
-                                return Ok(f64::MAX)
-                            }
-                            ide::FONTS.timestamps().get_asset(val)
-                        },
-                        14 => {
-                            if ide::TIMELINES.assets().get_asset(val).is_none() {
-                                return Ok(f64::MAX)
-                            }
-                            ide::TIMELINES.timestamps().get_asset(val)
-                        },
-                        _ => return Ok(0.0),
-                    };
-
-                    Ok(time)
-                }
-                if !matches!(parameter_get_timestamp(*ty, val).map(|t| t > LAST_SAVE), Ok(false)) {
-                    return true
-                }
-            }
-        }
-    }
-    return false
-}
-
 unsafe fn save_event<F: Write>(ev: &Event, name: &str, file: &mut F) -> Result<()> {
     writeln!(file, "#define {}", name)?;
     for action in ev.get_actions() {
@@ -369,48 +292,40 @@ unsafe fn save_event<F: Write>(ev: &Event, name: &str, file: &mut F) -> Result<(
                 writeln!(file, "invert={}", u8::from(action.invert_condition))?;
                 for i in 0..action.param_count as usize {
                     writeln!(file, "arg{}={}", i, match action.param_types[i] {
-                        5 => ide::SPRITES.names().get_asset(action.param_strings[i].try_decode()?.parse()?),
-                        6 => ide::SOUNDS.names().get_asset(action.param_strings[i].try_decode()?.parse()?),
-                        7 => ide::BACKGROUNDS.names().get_asset(action.param_strings[i].try_decode()?.parse()?),
-                        8 => ide::PATHS.names().get_asset(action.param_strings[i].try_decode()?.parse()?),
-                        9 => ide::SCRIPTS.names().get_asset(action.param_strings[i].try_decode()?.parse()?),
-                        10 => ide::OBJECTS.names().get_asset(action.param_strings[i].try_decode()?.parse()?),
-                        11 => ide::ROOMS.names().get_asset(action.param_strings[i].try_decode()?.parse()?),
-                        12 => ide::FONTS.names().get_asset(action.param_strings[i].try_decode()?.parse()?),
-                        14 => ide::TIMELINES.names().get_asset(action.param_strings[i].try_decode()?.parse()?),
+                        5 => ide::SPRITES.names().get_asset(action.param_strings[i].decode().parse()?),
+                        6 => ide::SOUNDS.names().get_asset(action.param_strings[i].decode().parse()?),
+                        7 => ide::BACKGROUNDS.names().get_asset(action.param_strings[i].decode().parse()?),
+                        8 => ide::PATHS.names().get_asset(action.param_strings[i].decode().parse()?),
+                        9 => ide::SCRIPTS.names().get_asset(action.param_strings[i].decode().parse()?),
+                        10 => ide::OBJECTS.names().get_asset(action.param_strings[i].decode().parse()?),
+                        11 => ide::ROOMS.names().get_asset(action.param_strings[i].decode().parse()?),
+                        12 => ide::FONTS.names().get_asset(action.param_strings[i].decode().parse()?),
+                        14 => ide::TIMELINES.names().get_asset(action.param_strings[i].decode().parse()?),
                         // params can have newlines so delimit
-                        _ => action.param_strings[i].try_delimit()?,
+                        _ => action.param_strings[i].delimit(),
                     })?;
                 }
             },
             5 => {
                 // repeat
-                writeln!(file, "repeats={}", action.param_strings[0].try_decode()?)?;
+                writeln!(file, "repeats={}", action.param_strings[0].decode())?;
             },
             6 => {
                 // variable
-                writeln!(file, "var_name={}", action.param_strings[0].try_decode()?)?;
-                writeln!(file, "var_value={}", action.param_strings[1].try_decode()?)?;
+                writeln!(file, "var_name={}", action.param_strings[0].decode())?;
+                writeln!(file, "var_value={}", action.param_strings[1].decode())?;
             },
             _ => (),
         }
         writeln!(file, "*/")?;
         if action.action_kind == 7 {
             // code
-            let code = action.param_strings[0].try_decode()?;
-            if code.starts_with("#define") || code.contains("\n#define") {
-                return Err(Error::Other("events should not contain #define".to_string()))
-            }
-            write_gml(file, &action.param_strings[0])?;
+            write_gml(file, &escape_defines(&action.param_strings[0].decode()))?;
         }
     }
     Ok(())
 }
 
-unsafe fn timeline_needs_update(tl: &Timeline) -> bool {
-    tl.get_events().iter().any(|e| event_needs_update(e))
-}
-
 unsafe fn save_timeline(tl: &Timeline, path: &mut PathBuf) -> Result<()> {
     path.set_extension("gml");
     let mut f = open_file(path)?;
@@ -431,30 +346,12 @@ fn event_name(ev_type: usize, ev_numb: usize) -> String {
         events::EV_TRIGGER => format!(
             "{}_{}",
             events::EVENT_NAMES[ev_type],
-            ide::get_triggers().get_asset(ev_numb as _).and_then(|t| t.name.try_decode_opt()).unwrap_or_default()
+            ide::get_triggers().get_asset(ev_numb as _).map(|t| t.name.decode()).unwrap_or_default()
         ),
         _ => format!("{}_{}", events::EVENT_NAMES[ev_type], ev_numb),
     }
 }
 
-unsafe fn object_needs_update(obj: &Object) -> bool {
-    return obj.sprite_index >= 0
-        && (ide::SPRITES.assets().get_asset(obj.sprite_index).is_none()
-            || ide::SPRITES.timestamps().get_asset(obj.sprite_index) > LAST_SAVE)
-        || obj.mask_index >= 0
-            && (ide::SPRITES.assets().get_asset(obj.mask_index).is_none()
-                || ide::SPRITES.timestamps().get_asset(obj.mask_index) > LAST_SAVE)
-        || obj.parent_index >= 0
-            && (ide::OBJECTS.assets().get_asset(obj.parent_index).is_none()
-                || ide::OBJECTS.timestamps().get_asset(obj.parent_index) > LAST_SAVE)
-        || obj.events.iter().flatten().any(|e| event_needs_update(e))
-        || !obj.events[events::EV_TRIGGER].is_empty() && *ide::TRIGGERS_UPDATED
-        || obj.events[events::EV_COLLISION]
-            .iter()
-            .enumerate()
-            .any(|(i, e)| e.action_count != 0 && ide::OBJECTS.timestamps().get_asset(i as _) > LAST_SAVE)
-}
-
 unsafe fn save_object(obj: &Object, path: &mut PathBuf) -> Result<()> {
     path.set_extension("txt");
     {
@@ -489,26 +386,6 @@ unsafe fn save_object(obj: &Object, path: &mut PathBuf) -> Result<()> {
     Ok(())
 }
 
-unsafe fn room_needs_update(room: &Room) -> bool {
-    return room.backgrounds.iter().any(|bg| {
-        bg.source_bg >= 0
-            && (ide::BACKGROUNDS.assets().get_asset(bg.source_bg).is_none()
-                || ide::BACKGROUNDS.timestamps().get_asset(bg.source_bg) > LAST_SAVE)
-    }) || room.views.iter().any(|view| {
-        view.following_target >= 0
-            && (ide::OBJECTS.assets().get_asset(view.following_target).is_none()
-                || ide::OBJECTS.timestamps().get_asset(view.following_target) > LAST_SAVE)
-    }) || room.get_instances().iter().any(|i| {
-        i.object >= 0
-            && (ide::OBJECTS.assets().get_asset(i.object).is_none()
-                || ide::OBJECTS.timestamps().get_asset(i.object) > LAST_SAVE)
-    }) || room.get_tiles().iter().any(|t| {
-        t.source_bg >= 0
-            && (ide::BACKGROUNDS.assets().get_asset(t.source_bg).is_none()
-                || ide::BACKGROUNDS.timestamps().get_asset(t.source_bg) > LAST_SAVE)
-    })
-}
-
 unsafe fn save_tiles(tiles: &[Tile], path: &mut PathBuf) -> Result<()> {
     let mut layers = HashMap::new();
     for tile in tiles {
@@ -523,10 +400,10 @@ unsafe fn save_tiles(tiles: &[Tile], path: &mut PathBuf) -> Result<()> {
         };
         let TileExtra { xscale, yscale, blend } =
             EXTRA_DATA.as_ref().and_then(|e| e.1.get(&tile.id).cloned()).unwrap_or_default();
-        writeln!(
+        write!(
             f,
             "{},{},{},{},{},{},{},{},{},{},{}",
-            ide::BACKGROUNDS.names().get_asset(tile.source_bg),
+            quote_field(&ide::BACKGROUNDS.names().get_asset(tile.source_bg)),
             tile.x,
             tile.y,
             tile.u,
@@ -538,6 +415,10 @@ unsafe fn save_tiles(tiles: &[Tile], path: &mut PathBuf) -> Result<()> {
             yscale,
             blend,
         )?;
+        if PRESERVE_IDS {
+            write!(f, ",{}", tile.id)?;
+        }
+        writeln!(f)?;
     }
     layers.values_mut().try_for_each(Write::flush)?;
     path.push("layers.txt");
@@ -558,7 +439,7 @@ fn save_instances(instances: &[Instance], path: &mut PathBuf) -> Result<()> {
 
     for instance in instances {
         let mut code = Vec::with_capacity(instance.creation_code.len());
-        write_gml(&mut code, &instance.creation_code)?;
+        write_gml(&mut code, &instance.creation_code.decode())?;
         // get id
         let fname = format!("{:08X}", extra_data.get(&instance.id).unwrap().name);
         if !code.is_empty() {
@@ -569,10 +450,10 @@ fn save_instances(instances: &[Instance], path: &mut PathBuf) -> Result<()> {
         }
         let InstanceExtra { xscale, yscale, blend, angle, .. } =
             unsafe { EXTRA_DATA.as_ref().and_then(|e| e.0.get(&instance.id).cloned()).unwrap_or_default() };
-        writeln!(
+        write!(
             f,
             "{},{},{},{},{},{},{},{},{},{}",
-            ide::OBJECTS.names().get_asset(instance.object),
+            quote_field(&ide::OBJECTS.names().get_asset(instance.object)),
             instance.x,
             instance.y,
             fname,
@@ -583,6 +464,10 @@ fn save_instances(instances: &[Instance], path: &mut PathBuf) -> Result<()> {
             angle,
             u8::from(!code.is_empty()),
         )?;
+        if unsafe { PRESERVE_IDS } {
+            write!(f, ",{}", instance.id)?;
+        }
+        writeln!(f)?;
     }
     f.flush()?;
     Ok(())
@@ -594,7 +479,7 @@ unsafe fn save_room(room: &Room, path: &mut PathBuf) -> Result<()> {
     path.push("room.txt");
     {
         let mut f = open_file(&path)?;
-        writeln!(f, "caption={}", room.caption.try_decode()?)?;
+        writeln!(f, "caption={}", room.caption.decode())?;
         writeln!(f, "width={}", room.width)?;
         writeln!(f, "height={}", room.height)?;
         writeln!(f, "snap_x={}", room.snap_x)?;
@@ -673,107 +558,99 @@ fn save_constants(path: &mut PathBuf) -> Result<()> {
     let names = ide::get_constant_names();
     let values = ide::get_constants();
     for (name, value) in names.iter().zip(values) {
-        writeln!(f, "{}={}", name.try_decode()?, value.try_decode()?)?;
+        writeln!(f, "{}={}", name.decode(), value.decode())?;
     }
     f.flush()?;
     Ok(())
 }
 
-unsafe fn save_settings(path: &mut PathBuf, smart_save: bool) -> Result<()> {
+unsafe fn save_settings(path: &mut PathBuf) -> Result<()> {
     use ide::settings::*;
     path.push("settings");
     create_dirs(&path)?;
-    if !smart_save || *ide::CONSTANTS_UPDATED {
-        save_constants(path)?;
+    save_constants(path)?;
+    // not the usual behaviour, but i don't feel like adding more flags than necessary
+    if *HAS_CUSTOM_LOAD_IMAGE && (*CUSTOM_LOAD_IMAGE).is_none() {
+        HAS_CUSTOM_LOAD_IMAGE.write(false);
     }
-    if !smart_save || *ide::SETTINGS_UPDATED {
-        // not the usual behaviour, but i don't feel like adding more flags than necessary
-        if *HAS_CUSTOM_LOAD_IMAGE && (*CUSTOM_LOAD_IMAGE).is_none() {
-            HAS_CUSTOM_LOAD_IMAGE.write(false);
-        }
-        {
-            path.push("settings.txt");
-            let mut f = open_file(&path)?;
+    {
+        path.push("settings.txt");
+        let mut f = open_file(&path)?;
+        path.pop();
+        writeln!(f, "fullscreen={}", u8::from(FULLSCREEN.read()))?;
+        writeln!(f, "interpolate_pixels={}", u8::from(INTERPOLATE_PIXELS.read()))?;
+        writeln!(f, "dont_draw_border={}", u8::from(DONT_DRAW_BORDER.read()))?;
+        writeln!(f, "display_cursor={}", u8::from(DISPLAY_CURSOR.read()))?;
+        writeln!(f, "scaling={}", SCALING.read())?;
+        writeln!(f, "allow_resize={}", u8::from(ALLOW_RESIZE.read()))?;
+        writeln!(f, "window_on_top={}", u8::from(*WINDOW_ON_TOP))?;
+        writeln!(f, "clear_color={}", *CLEAR_COLOUR)?;
+        writeln!(f, "set_resolution={}", u8::from(*SET_RESOLUTION))?;
+        writeln!(f, "color_depth={}", *COLOUR_DEPTH)?;
+        writeln!(f, "resolution={}", *RESOLUTION)?;
+        writeln!(f, "frequency={}", *FREQUENCY)?;
+        writeln!(f, "dont_show_buttons={}", u8::from(*DONT_SHOW_BUTTONS))?;
+        writeln!(f, "vsync={}", *VSYNC_AND_FORCE_CPU & 1)?;
+        writeln!(f, "swap_creation_events={}", u8::from(*VSYNC_AND_FORCE_CPU & (1 << 31) != 0))?;
+        writeln!(f, "disable_screensaver={}", u8::from(*DISABLE_SCREENSAVER))?;
+        writeln!(f, "f4_fullscreen_toggle={}", u8::from(*F4_FULLSCREEN))?;
+        writeln!(f, "f1_help_menu={}", u8::from(*F1_HELP))?;
+        writeln!(f, "esc_close_game={}", u8::from(*ESC_CLOSE))?;
+        writeln!(f, "f5_save_f6_load={}", u8::from(*F5_SAVE_F6_LOAD))?;
+        writeln!(f, "f9_screenshot={}", u8::from(*F9_SCREENSHOT))?;
+        writeln!(f, "treat_close_as_esc={}", u8::from(*TREAT_CLOSE_AS_ESC))?;
+        writeln!(f, "priority={}", *PRIORITY)?;
+        writeln!(f, "freeze_on_lose_focus={}", u8::from(*FREEZE_ON_LOSE_FOCUS))?;
+        writeln!(f, "custom_loader={}", u8::from(*HAS_CUSTOM_LOAD_IMAGE))?;
+        writeln!(f, "custom_bar={}", *LOADING_BAR)?;
+        writeln!(f, "bar_has_bg={}", u8::from(!(*LOADING_BACKGROUND).is_none()))?;
+        writeln!(f, "bar_has_fg={}", u8::from(!(*LOADING_FOREGROUND).is_none()))?;
+        writeln!(f, "transparent={}", u8::from(*LOADING_TRANSPARENT))?;
+        writeln!(f, "translucency={}", *LOADING_TRANSLUCENCY)?;
+        writeln!(f, "scale_progress_bar={}", u8::from(*LOADING_PROGRESS_BAR_SCALE))?;
+        writeln!(f, "show_error_messages={}", u8::from(*SHOW_ERROR_MESSAGES))?;
+        writeln!(f, "log_errors={}", u8::from(*LOG_ERRORS))?;
+        writeln!(f, "always_abort={}", u8::from(*ALWAYS_ABORT))?;
+        writeln!(f, "zero_uninitialized_vars={}", u8::from(*ZERO_UNINITIALIZED_VARS))?;
+        writeln!(f, "error_on_uninitialized_args={}", u8::from(*ERROR_ON_UNINITIALIZED_ARGS))?;
+        f.flush()?;
+    }
+    if LOADING_BAR.read() == 2 {
+        if let Some(bg) = (*LOADING_BACKGROUND).as_ref() {
+            path.push("back.bmp");
+            save_with_delphi(path, |p| bg.SaveToFile(p))?;
             path.pop();
-            writeln!(f, "fullscreen={}", u8::from(FULLSCREEN.read()))?;
-            writeln!(f, "interpolate_pixels={}", u8::from(INTERPOLATE_PIXELS.read()))?;
-            writeln!(f, "dont_draw_border={}", u8::from(DONT_DRAW_BORDER.read()))?;
-            writeln!(f, "display_cursor={}", u8::from(DISPLAY_CURSOR.read()))?;
-            writeln!(f, "scaling={}", SCALING.read())?;
-            writeln!(f, "allow_resize={}", u8::from(ALLOW_RESIZE.read()))?;
-            writeln!(f, "window_on_top={}", u8::from(*WINDOW_ON_TOP))?;
-            writeln!(f, "clear_color={}", *CLEAR_COLOUR)?;
-            writeln!(f, "set_resolution={}", u8::from(*SET_RESOLUTION))?;
-            writeln!(f, "color_depth={}", *COLOUR_DEPTH)?;
-            writeln!(f, "resolution={}", *RESOLUTION)?;
-            writeln!(f, "frequency={}", *FREQUENCY)?;
-            writeln!(f, "dont_show_buttons={}", u8::from(*DONT_SHOW_BUTTONS))?;
-            writeln!(f, "vsync={}", *VSYNC_AND_FORCE_CPU & 1)?;
-            writeln!(f, "swap_creation_events={}", u8::from(*VSYNC_AND_FORCE_CPU & (1 << 31) != 0))?;
-            writeln!(f, "disable_screensaver={}", u8::from(*DISABLE_SCREENSAVER))?;
-            writeln!(f, "f4_fullscreen_toggle={}", u8::from(*F4_FULLSCREEN))?;
-            writeln!(f, "f1_help_menu={}", u8::from(*F1_HELP))?;
-            writeln!(f, "esc_close_game={}", u8::from(*ESC_CLOSE))?;
-            writeln!(f, "f5_save_f6_load={}", u8::from(*F5_SAVE_F6_LOAD))?;
-            writeln!(f, "f9_screenshot={}", u8::from(*F9_SCREENSHOT))?;
-            writeln!(f, "treat_close_as_esc={}", u8::from(*TREAT_CLOSE_AS_ESC))?;
-            writeln!(f, "priority={}", *PRIORITY)?;
-            writeln!(f, "freeze_on_lose_focus={}", u8::from(*FREEZE_ON_LOSE_FOCUS))?;
-            writeln!(f, "custom_loader={}", u8::from(*HAS_CUSTOM_LOAD_IMAGE))?;
-            writeln!(f, "custom_bar={}", *LOADING_BAR)?;
-            writeln!(f, "bar_has_bg={}", u8::from(!(*LOADING_BACKGROUND).is_none()))?;
-            writeln!(f, "bar_has_fg={}", u8::from(!(*LOADING_FOREGROUND).is_none()))?;
-            writeln!(f, "transparent={}", u8::from(*LOADING_TRANSPARENT))?;
-            writeln!(f, "translucency={}", *LOADING_TRANSLUCENCY)?;
-            writeln!(f, "scale_progress_bar={}", u8::from(*LOADING_PROGRESS_BAR_SCALE))?;
-            writeln!(f, "show_error_messages={}", u8::from(*SHOW_ERROR_MESSAGES))?;
-            writeln!(f, "log_errors={}", u8::from(*LOG_ERRORS))?;
-            writeln!(f, "always_abort={}", u8::from(*ALWAYS_ABORT))?;
-            writeln!(f, "zero_uninitialized_vars={}", u8::from(*ZERO_UNINITIALIZED_VARS))?;
-            writeln!(f, "error_on_uninitialized_args={}", u8::from(*ERROR_ON_UNINITIALIZED_ARGS))?;
-            f.flush()?;
         }
-        if LOADING_BAR.read() == 2 {
-            if let Some(bg) = (*LOADING_BACKGROUND).as_ref() {
-                path.push("back.bmp");
-                bg.SaveToFile(&UStr::new(&path));
-                path.pop();
-            }
-            if let Some(fg) = (*LOADING_FOREGROUND).as_ref() {
-                path.push("front.bmp");
-                fg.SaveToFile(&UStr::new(&path));
-                path.pop();
-            }
-        }
-        if HAS_CUSTOM_LOAD_IMAGE.read() {
-            path.push("loader.bmp");
-            (*CUSTOM_LOAD_IMAGE).as_ref().unwrap().SaveToFile(&UStr::new(&path));
+        if let Some(fg) = (*LOADING_FOREGROUND).as_ref() {
+            path.push("front.bmp");
+            save_with_delphi(path, |p| fg.SaveToFile(p))?;
             path.pop();
         }
-        // icon is never legally null, so no need to check
-        path.push("icon.ico");
-        (*ICON).SaveToFile(&UStr::new(&path));
+    }
+    if HAS_CUSTOM_LOAD_IMAGE.read() {
+        path.push("loader.bmp");
+        save_with_delphi(path, |p| (*CUSTOM_LOAD_IMAGE).as_ref().unwrap().SaveToFile(p))?;
         path.pop();
     }
-    if !smart_save || *ide::EXTENSIONS_UPDATED {
-        path.push("extensions.txt");
-        let extensions = ide::get_extensions();
-        let extensions_loaded = ide::get_extensions_loaded();
-        {
-            let mut f = open_file(&path)?;
-            for (extension, &loaded) in extensions.iter().zip(extensions_loaded) {
-                if loaded {
-                    writeln!(f, "{}", extension.name.try_decode()?)?;
-                }
+    // icon is never legally null, so no need to check
+    path.push("icon.ico");
+    save_with_delphi(path, |p| (*ICON).SaveToFile(p))?;
+    path.pop();
+    path.push("extensions.txt");
+    let extensions = ide::get_extensions();
+    let extensions_loaded = ide::get_extensions_loaded();
+    {
+        let mut f = open_file(&path)?;
+        for (extension, &loaded) in extensions.iter().zip(extensions_loaded) {
+            if loaded {
+                writeln!(f, "{}", extension.name.decode())?;
             }
-            f.flush()?;
         }
-        path.pop();
-    }
-    if !smart_save || *ide::GAME_INFO_UPDATED {
-        save_game_information(path)?;
+        f.flush()?;
     }
     path.pop();
+    save_game_information(path)?;
+    path.pop();
     Ok(())
 }
 
@@ -786,13 +663,13 @@ fn save_triggers(path: &mut PathBuf) -> Result<()> {
         let mut name_set = HashSet::with_capacity(triggers.len());
         for trigger in triggers {
             if let Some(trigger) = trigger.as_ref() {
-                let name = trigger.name.try_decode()?;
+                let name = trigger.name.decode();
                 writeln!(index, "{}", name)?;
-                if let Some(c) = filename_invalid(&name) {
-                    return Err(Error::BadTriggerName(name, char::from(c)))
+                if let Some(c) = name_invalid(&trigger.name) {
+                    return Err(Error::BadTriggerName(name, c))
                 }
                 if !name_set.insert(make_unicase(name, &trigger.name)) {
-                    return Err(Error::DuplicateTrigger(trigger.name.try_decode()?))
+                    return Err(Error::DuplicateTrigger(trigger.name.decode()))
                 }
             } else {
                 writeln!(index)?;
@@ -804,12 +681,12 @@ fn save_triggers(path: &mut PathBuf) -> Result<()> {
     }
     for trigger in triggers {
         if let Some(trigger) = trigger.as_ref() {
-            let name = trigger.name.try_decode()?;
+            let name = trigger.name.decode();
             path.push(&name);
             path.set_extension("txt");
             {
                 let mut f = open_file(&path)?;
-                writeln!(f, "constant={}", trigger.constant_name.try_decode()?)?;
+                writeln!(f, "constant={}", trigger.constant_name.decode())?;
                 writeln!(f, "kind={}", trigger.kind)?;
                 f.flush()?;
             }
@@ -831,8 +708,6 @@ unsafe fn save_assets<'a, T: Sync>(
     timestamps: &[f64],
     tree: *const *const TTreeNode,
     save_func: unsafe fn(&T, &mut PathBuf) -> Result<()>,
-    smart_save: bool,
-    dependency_check: unsafe fn(&T) -> bool,
     path: &mut PathBuf,
 ) -> Result<()> {
     path.push(name);
@@ -842,15 +717,15 @@ unsafe fn save_assets<'a, T: Sync>(
         let mut name_set = HashSet::with_capacity(names.len());
         let mut index = Vec::with_capacity(names.len());
         for name_wide in names {
-            let name = name_wide.try_decode()?;
+            let name = name_wide.decode();
             writeln!(&mut index, "{}", name)?;
             if !name.is_empty() {
                 count += 1;
-                if let Some(c) = filename_invalid(&name) {
-                    return Err(Error::BadAssetName(name, char::from(c)))
+                if let Some(c) = name_invalid(name_wide) {
+                    return Err(Error::BadAssetName(name, c))
                 }
                 if !name_set.insert(make_unicase(name, name_wide)) {
-                    return Err(Error::DuplicateAsset(name_wide.try_decode()?))
+                    return Err(Error::DuplicateAsset(name_wide.decode()))
                 }
             }
         }
@@ -858,15 +733,25 @@ unsafe fn save_assets<'a, T: Sync>(
         write_file(&path, index)?;
         path.pop();
     }
+    {
+        let mut entries = Vec::with_capacity(count as usize);
+        for ((asset, name), timestamp) in assets.iter().zip(names).zip(timestamps) {
+            if asset.is_some() {
+                entries.push((name.decode(), *timestamp));
+            }
+        }
+        path.push("timestamps.yyd");
+        gm82project::save::write_timestamps(&path, entries.iter().map(|(n, t)| (n.as_str(), *t)))?;
+        path.pop();
+    }
     run_while_updating_bar(_bar_start, _bar_end, count, |tx| {
-        (assets, names, timestamps).into_par_iter().try_for_each(|(asset, name, timestamp)| -> Result<()> {
+        // everything gets written, and files that come out the same are left alone when the save is committed
+        (assets, names).into_par_iter().try_for_each(|(asset, name)| -> Result<()> {
             if let Some(asset) = asset {
-                if !smart_save || *timestamp > LAST_SAVE || dependency_check(asset) {
-                    let name = name.try_decode()?;
-                    let mut p = path.join(name);
-                    save_func(asset, &mut p)?;
-                    let _ = tx.send(());
-                }
+                let name = name.decode();
+                let mut p = path.join(name);
+                save_func(asset, &mut p)?;
+                let _ = tx.send(());
             }
             Ok(())
         })
@@ -882,7 +767,7 @@ unsafe fn save_assets<'a, T: Sync>(
     Ok(())
 }
 
-unsafe fn save_included_files(path: &mut PathBuf, smart_save: bool) -> Result<()> {
+unsafe fn save_included_files(path: &mut PathBuf) -> Result<()> {
     path.push("datafiles");
     path.push("include");
     create_dirs(&path)?;
@@ -892,26 +777,23 @@ unsafe fn save_included_files(path: &mut PathBuf, smart_save: bool) -> Result<()
         let mut index = Vec::with_capacity(files.len());
         let mut names_set = HashSet::with_capacity(files.len());
         for file in files {
-            let name = file.file_name.try_decode()?;
+            let name = file.file_name.decode();
             writeln!(index, "{}", name)?;
 
-            if let Some(c) = filename_invalid(&name) {
-                return Err(Error::BadIncludedFileName(name, char::from(c)))
+            if let Some(c) = name_invalid(&file.file_name) {
+                return Err(Error::BadIncludedFileName(name, c))
             }
             if !names_set.insert(name) {
-                return Err(Error::DuplicateIncludedFile(file.file_name.try_decode()?))
+                return Err(Error::DuplicateIncludedFile(file.file_name.decode()))
             }
         }
         path.push("index.yyd");
         write_file(&path, index)?;
         path.pop();
     }
-    for (file, timestamp) in files.iter().zip(ide::get_included_file_timestamps()) {
-        if smart_save && *timestamp < LAST_SAVE {
-            continue
-        }
+    for file in files {
         let file = &**file;
-        let mut name = file.file_name.try_decode()?;
+        let mut name = file.file_name.decode();
         if file.data_exists {
             if file.stored_in_gmk {
                 path.push("include");
@@ -923,7 +805,7 @@ unsafe fn save_included_files(path: &mut PathBuf, smart_save: bool) -> Result<()
                 // try to copy it to gmk dir if not already done
                 path.push("include");
                 path.push(&name);
-                if !path.exists() {
+                if !transaction::keep(&path)? {
                     std::fs::copy(file.source_path.to_os_string(), &path)
                         .map_err(|e| Error::FileIoError(e, path.to_path_buf()))?;
                 }
@@ -940,7 +822,7 @@ unsafe fn save_included_files(path: &mut PathBuf, smart_save: bool) -> Result<()
         writeln!(f, "remove={}", u8::from(file.remove_at_end))?;
         writeln!(f, "export={}", file.export_setting)?;
         if file.export_setting == 3 {
-            writeln!(f, "export_folder={}", file.export_custom_folder.try_decode()?)?;
+            writeln!(f, "export_folder={}", file.export_custom_folder.decode())?;
         }
         f.flush()?;
         path.pop();
@@ -957,7 +839,7 @@ unsafe fn save_game_information(path: &mut PathBuf) -> Result<()> {
         let mut f = open_file(&path)?;
         writeln!(f, "color={}", editor.colour)?;
         writeln!(f, "new_window={}", u8::from(*NEW_WINDOW))?;
-        writeln!(f, "caption={}", (&*CAPTION).try_decode()?)?;
+        writeln!(f, "caption={}", (&*CAPTION).decode())?;
         writeln!(f, "left={}", *LEFT)?;
         writeln!(f, "top={}", *TOP)?;
         writeln!(f, "width={}", *WIDTH)?;
@@ -969,7 +851,7 @@ unsafe fn save_game_information(path: &mut PathBuf) -> Result<()> {
         f.flush()?;
     }
     path.set_extension("rtf");
-    (&*editor.rich_edit_strings).SaveToFile(&UStr::new(&path));
+    save_with_delphi(path, |p| (&*editor.rich_edit_strings).SaveToFile(p))?;
     path.pop();
     Ok(())
 }
@@ -982,7 +864,7 @@ unsafe fn write_tree_children<F: Write>(
 ) -> Result<()> {
     for i in 0..parent.GetCount() {
         let node = &*parent.GetItem(i);
-        let name = node.name.try_decode()?;
+        let name = node.name.decode();
         match (*node.data).rtype {
             2 => {
                 writeln!(f, "{}+{}", tabs, name)?;
@@ -991,25 +873,22 @@ unsafe fn write_tree_children<F: Write>(
                 write_tree_children(node, names, tabs, f)?;
                 tabs.pop();
             },
-            3 => writeln!(f, "{}|{}", tabs, names[(*node.data).index].try_decode()?)?,
+            3 => writeln!(f, "{}|{}", tabs, names[(*node.data).index].decode())?,
             _ => return Err(Error::Other(format!("failed to save resource tree {}", name))),
         }
     }
     Ok(())
 }
 
-unsafe fn save_icon_cache(path: &mut PathBuf, smart_save: bool) -> Result<()> {
+unsafe fn save_icon_cache(path: &mut PathBuf) -> Result<()> {
     const BMP_HEADER: &[u8] = include_bytes!("../assets/thumb_header.dat");
     const BMP_SIZE: usize = 16 * 16 * 4 + BMP_HEADER.len();
-    unsafe fn save_frame(frame: &Frame, name: &UStr, path: &std::path::Path, only_if_needed: bool) -> Result<()> {
+    unsafe fn save_frame(frame: &Frame, name: &UStr, path: &std::path::Path) -> Result<()> {
         if frame.width == 0 || frame.height == 0 {
             return Ok(())
         }
         let mut path = path.join(name.to_os_string());
         path.set_extension("bmp");
-        if only_if_needed && path.exists() {
-            return Ok(())
-        }
         let mut out = Vec::with_capacity(BMP_SIZE);
         out.extend_from_slice(BMP_HEADER);
         out.set_len(BMP_SIZE);
@@ -1019,35 +898,42 @@ unsafe fn save_icon_cache(path: &mut PathBuf, smart_save: bool) -> Result<()> {
     path.push("cache");
     path.push("sprites");
     create_dirs(path)?;
-    (ide::SPRITES.assets(), ide::SPRITES.names(), ide::SPRITES.timestamps()).into_par_iter().try_for_each(
-        |(sprite, name, timestamp)| -> Result<()> {
-            if let Some(frame) = sprite.as_deref().and_then(|s| s.get_frames().get(0)) {
-                save_frame(frame, name, path, smart_save && *timestamp < LAST_SAVE)?;
-            }
-            Ok(())
-        },
-    )?;
+    (ide::SPRITES.assets(), ide::SPRITES.names()).into_par_iter().try_for_each(|(sprite, name)| -> Result<()> {
+        if let Some(frame) = sprite.as_deref().and_then(|s| s.get_frames().get(0)) {
+            save_frame(frame, name, path)?;
+        }
+        Ok(())
+    })?;
     path.pop();
     path.push("backgrounds");
     create_dirs(path)?;
-    (ide::BACKGROUNDS.assets(), ide::BACKGROUNDS.names(), ide::BACKGROUNDS.timestamps()).into_par_iter().try_for_each(
-        |(bg, name, timestamp)| -> Result<()> {
-            if let Some(bg) = bg {
-                save_frame(&bg.frame, name, path, smart_save && *timestamp < LAST_SAVE)?;
-            }
-            Ok(())
-        },
-    )?;
+    (ide::BACKGROUNDS.assets(), ide::BACKGROUNDS.names()).into_par_iter().try_for_each(|(bg, name)| -> Result<()> {
+        if let Some(bg) = bg {
+            save_frame(&bg.frame, name, path)?;
+        }
+        Ok(())
+    })?;
     path.pop();
     path.pop();
     Ok(())
 }
 
+/// Saves the project to `path`, the .gm82 file, leaving `path` pointing at its directory.
+/// Everything is staged first, so a save that fails leaves the files from the last one alone.
 pub unsafe fn save_gmk(path: &mut PathBuf) -> Result<()> {
-    // if we have a watcher, we can do a smart save
-    // but if time went backwards, we must do a full save
-    let smart_save = project_watcher::watching() && LAST_SAVE != 0.0;
+    let file_name = path.file_name().map(OsString::from).unwrap_or_else(|| ".gm82".into());
+    path.pop();
+    // stop watching before anything gets touched, or the watcher would pick up the save itself
     project_watcher::unwatch();
+    let transaction = Transaction::begin(&*path)?;
+    save_staged(&mut transaction.staging().join(file_name))?;
+    transaction.commit()?;
+    load::ESCAPED_UTF16 = true;
+    update_timestamp();
+    Ok(())
+}
+
+unsafe fn save_staged(path: &mut PathBuf) -> Result<()> {
     PATH_FORM_UPDATED = false;
     // check if we have any assets to save
     let has_backgrounds = ide::BACKGROUNDS.assets().iter().any(Option::is_some);
@@ -1060,22 +946,21 @@ pub unsafe fn save_gmk(path: &mut PathBuf) -> Result<()> {
     let has_sprites = ide::SPRITES.assets().iter().any(Option::is_some);
     let has_timelines = ide::TIMELINES.assets().iter().any(Option::is_some);
     let has_triggers = !ide::get_triggers().is_empty();
-    // always rewrite the .gm82 file so you can easily see the timestamp
     {
         create_dirs(path.parent().unwrap())?;
         // some stuff to go in the main gmk
         let mut f = open_file(&path)?;
-        writeln!(f, "gm82_version=5")?;
+        writeln!(f, "gm82_version=6")?;
         writeln!(f, "gameid={}", ide::GAME_ID.read())?;
         writeln!(f)?;
-        writeln!(f, "info_author={}", (&*ide::settings::INFO_AUTHOR).try_decode()?)?;
-        writeln!(f, "info_version={}", (&*ide::settings::INFO_VERSION).try_decode()?)?;
-        writeln!(f, "info_information={}", (&*ide::settings::INFO_INFORMATION).try_delimit()?)?;
+        writeln!(f, "info_author={}", (&*ide::settings::INFO_AUTHOR).decode())?;
+        writeln!(f, "info_version={}", (&*ide::settings::INFO_VERSION).decode())?;
+        writeln!(f, "info_information={}", (&*ide::settings::INFO_INFORMATION).delimit())?;
         writeln!(f)?;
-        writeln!(f, "exe_company={}", (&*ide::settings::EXE_COMPANY).try_decode()?)?;
-        writeln!(f, "exe_product={}", (&*ide::settings::EXE_PRODUCT).try_decode()?)?;
-        writeln!(f, "exe_copyright={}", (&*ide::settings::EXE_COPYRIGHT).try_decode()?)?;
-        writeln!(f, "exe_description={}", (&*ide::settings::EXE_DESCRIPTION).try_decode()?)?;
+        writeln!(f, "exe_company={}", (&*ide::settings::EXE_COMPANY).decode())?;
+        writeln!(f, "exe_product={}", (&*ide::settings::EXE_PRODUCT).decode())?;
+        writeln!(f, "exe_copyright={}", (&*ide::settings::EXE_COPYRIGHT).decode())?;
+        writeln!(f, "exe_description={}", (&*ide::settings::EXE_DESCRIPTION).decode())?;
         writeln!(
             f,
             "exe_version={}.{}.{}.{}",
@@ -1095,21 +980,23 @@ pub unsafe fn save_gmk(path: &mut PathBuf) -> Result<()> {
         writeln!(f, "has_sprites={}", u8::from(has_sprites))?;
         writeln!(f, "has_timelines={}", u8::from(has_timelines))?;
         writeln!(f, "has_triggers={}", u8::from(has_triggers))?;
-        /*
-        writeln!(f, "last_instance_id={}", *ide::_LAST_INSTANCE_ID)?;
-        writeln!(f, "last_tile_id={}", *ide::_LAST_TILE_ID)?;
-         */
+        if PRESERVE_IDS {
+            writeln!(f)?;
+            writeln!(f, "preserve_ids=1")?;
+            writeln!(f, "last_instance_id={}", *ide::LAST_INSTANCE_ID)?;
+            writeln!(f, "last_tile_id={}", *ide::LAST_TILE_ID)?;
+        }
         f.flush()?;
     }
     path.pop();
     advance_progress_form(5);
-    save_settings(path, smart_save)?;
+    save_settings(path)?;
     advance_progress_form(10);
-    if has_triggers && (!smart_save || *ide::TRIGGERS_UPDATED) {
+    if has_triggers {
         save_triggers(path)?;
     }
     advance_progress_form(15);
-    if has_sounds && (!smart_save || *ide::SOUNDS_UPDATED || *ide::RESOURCE_TREE_UPDATED) {
+    if has_sounds {
         save_assets(
             15,
             30,
@@ -1119,13 +1006,11 @@ pub unsafe fn save_gmk(path: &mut PathBuf) -> Result<()> {
             ide::SOUNDS.timestamps(),
             ide::RT_SOUNDS,
             save_sound,
-            smart_save,
-            no_dependencies,
             path,
         )?;
     }
     advance_progress_form(30);
-    if has_sprites && (!smart_save || *ide::SPRITES_UPDATED || *ide::RESOURCE_TREE_UPDATED) {
+    if has_sprites {
         save_assets(
             30,
             55,
@@ -1135,13 +1020,11 @@ pub unsafe fn save_gmk(path: &mut PathBuf) -> Result<()> {
             ide::SPRITES.timestamps(),
             ide::RT_SPRITES,
             save_sprite,
-            smart_save,
-            no_dependencies,
             path,
         )?;
     }
     advance_progress_form(55);
-    if has_backgrounds && (!smart_save || *ide::BACKGROUNDS_UPDATED || *ide::RESOURCE_TREE_UPDATED) {
+    if has_backgrounds {
         save_assets(
             55,
             65,
@@ -1151,8 +1034,6 @@ pub unsafe fn save_gmk(path: &mut PathBuf) -> Result<()> {
             ide::BACKGROUNDS.timestamps(),
             ide::RT_BACKGROUNDS,
             save_background,
-            smart_save,
-            no_dependencies,
             path,
         )?;
     }
@@ -1167,13 +1048,11 @@ pub unsafe fn save_gmk(path: &mut PathBuf) -> Result<()> {
             ide::PATHS.timestamps(),
             ide::RT_PATHS,
             save_path,
-            smart_save,
-            path_needs_update,
             path,
         )?;
     }
     advance_progress_form(70);
-    if has_scripts && (!smart_save || *ide::SCRIPTS_UPDATED || *ide::RESOURCE_TREE_UPDATED) {
+    if has_scripts {
         save_assets(
             70,
             75,
@@ -1183,13 +1062,11 @@ pub unsafe fn save_gmk(path: &mut PathBuf) -> Result<()> {
             ide::SCRIPTS.timestamps(),
             ide::RT_SCRIPTS,
             save_script,
-            smart_save,
-            no_dependencies,
             path,
         )?;
     }
     advance_progress_form(75);
-    if has_fonts && (!smart_save || *ide::FONTS_UPDATED || *ide::RESOURCE_TREE_UPDATED) {
+    if has_fonts {
         save_assets(
             75,
             80,
@@ -1199,8 +1076,6 @@ pub unsafe fn save_gmk(path: &mut PathBuf) -> Result<()> {
             ide::FONTS.timestamps(),
             ide::RT_FONTS,
             save_font,
-            smart_save,
-            no_dependencies,
             path,
         )?;
     }
@@ -1215,8 +1090,6 @@ pub unsafe fn save_gmk(path: &mut PathBuf) -> Result<()> {
             ide::TIMELINES.timestamps(),
             ide::RT_TIMELINES,
             save_timeline,
-            smart_save,
-            timeline_needs_update,
             path,
         )?;
     }
@@ -1231,30 +1104,30 @@ pub unsafe fn save_gmk(path: &mut PathBuf) -> Result<()> {
             ide::OBJECTS.timestamps(),
             ide::RT_OBJECTS,
             save_object,
-            smart_save,
-            object_needs_update,
             path,
         )?;
     }
     advance_progress_form(90);
-    // give instances ids if they don't already have one
-    for (room, timestamp) in
-        ide::ROOMS.assets().iter().zip(ide::ROOMS.timestamps_mut()).filter_map(|(r, t)| Some((r.as_deref()?, t)))
+    // give instances names if they don't already have one
+    let extra_data = &mut EXTRA_DATA.get_or_insert_with(Default::default).0;
+    let mut taken: HashSet<u32> = extra_data.values().map(|ex| ex.name).filter(|&n| n != 0).collect();
+    for (room, room_name, timestamp) in izip!(ide::ROOMS.assets(), ide::ROOMS.names(), ide::ROOMS.timestamps_mut())
+        .filter_map(|(r, n, t)| Some((r.as_deref()?, n, t)))
     {
-        let extra_data = &mut EXTRA_DATA.get_or_insert_with(Default::default).0;
-        for id in room.get_instances().iter().map(|i| i.id) {
-            let mut name = extra_data.entry(id).or_default().name;
-            if name == 0 {
+        let room_name = room_name.decode();
+        let mut counters = HashMap::new();
+        for instance in room.get_instances() {
+            let counter = counters.entry((instance.object, instance.x, instance.y)).or_insert(0);
+            let extra = extra_data.entry(instance.id).or_default();
+            if extra.name == 0 {
                 *ide::ROOMS_UPDATED = true;
                 delphi::Now(timestamp);
-                loop {
-                    name = delphi::Random();
-                    if !extra_data.values().any(|ex| ex.name == name) {
-                        extra_data.get_mut(&id).unwrap().name = name;
-                        break
-                    }
-                }
+                let object: String = ide::OBJECTS.names().get_asset(instance.object);
+                extra.name =
+                    derive_instance_name(&room_name, &object, instance.x, instance.y, *counter, |n| taken.contains(&n));
+                taken.insert(extra.name);
             }
+            *counter += 1;
         }
     }
     save_assets(
@@ -1266,27 +1139,16 @@ pub unsafe fn save_gmk(path: &mut PathBuf) -> Result<()> {
         ide::ROOMS.timestamps(),
         ide::RT_ROOMS,
         save_room,
-        smart_save,
-        room_needs_update,
         path,
     )?;
     advance_progress_form(95);
-    if has_datafiles && (!smart_save || *ide::INCLUDED_FILES_UPDATED) {
-        save_included_files(path, smart_save)?;
+    if has_datafiles {
+        save_included_files(path)?;
     }
 
-    if !smart_save || *ide::SPRITES_UPDATED || *ide::BACKGROUNDS_UPDATED || {
-        path.push("cache");
-        let exists = path.exists();
-        path.pop();
-        !exists
-    } {
-        save_icon_cache(path, smart_save)?;
-    }
+    save_icon_cache(path)?;
 
     advance_progress_form(100);
 
-    update_timestamp();
-
     Ok(())
 }
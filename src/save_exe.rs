@@ -1,16 +1,12 @@
 use crate::{
     AssetListTrait, DEFLATE_LEVEL, EXTRA_DATA, InstanceExtra, TileExtra, UStr, asset, delphi, delphi::TMemoryStream,
-    ide, regular::extension_watcher::update_extensions,
+    ide, regular::extension_watcher::update_extensions, update_renamed_references,
 };
 use byteorder::{LE, WriteBytesExt};
 use flate2::{Compression, write::ZlibEncoder};
+use gm82project::mask;
 use rayon::prelude::*;
-use std::{
-    arch::{asm, naked_asm},
-    io,
-    io::Write,
-    ptr, slice,
-};
+use std::{arch::naked_asm, io, io::Write, ptr, slice};
 
 pub trait GetAssetList: Sync + 'static {
     fn get_asset_list() -> &'static dyn AssetListTrait<Self>;
@@ -50,97 +46,24 @@ impl GetAssetList for asset::Sprite {
             out.write_i32::<LE>(self.bbox_right)?;
             out.write_i32::<LE>(self.bbox_bottom)?;
             out.write_i32::<LE>(self.bbox_top)?;
-        } else if self.frame_count > 0 {
-            unsafe {
-                #[repr(C)]
-                struct Mask {
-                    _padding: u32,
-                    i_w: u32,
-                    i_h: u32,
-                    i_mask: *const *const u8,
-                    i_bbox: [u32; 4],
-                }
-                unsafe fn write_mask(mask: &Mask, mut out: impl Write) -> io::Result<()> {
-                    out.write_u32::<LE>(800)?;
-                    out.write_u32::<LE>(mask.i_w)?;
-                    out.write_u32::<LE>(mask.i_h)?;
-                    out.write_u32::<LE>(mask.i_bbox[0])?;
-                    out.write_u32::<LE>(mask.i_bbox[2])?;
-                    out.write_u32::<LE>(mask.i_bbox[3])?;
-                    out.write_u32::<LE>(mask.i_bbox[1])?;
-                    for y in 0..mask.i_h as usize {
-                        for x in 0..mask.i_w as usize {
-                            out.write_u32::<LE>(mask.i_mask.add(x).read().add(y).read().into())?;
-                        }
-                    }
-                    Ok(())
-                }
-                // TODO speed up
-                if !self.per_frame_colliders {
-                    // create mask
-                    let f: &asset::Frame = &self.get_frames()[0];
-                    let mut mask: *const Mask;
-                    asm!(
-                        "push dword ptr [{sprite}+0x1c]",
-                        "push {bbox}",
-                        "push dword ptr [{sprite}+0x10]",
-                        "push dword ptr [{sprite}+0x14]",
-                        "call {call}",
-                        sprite = in(reg) &*self,
-                        call = in(reg) 0x5aecac,
-                        bbox = in(reg) &self.bbox_left,
-                        inlateout("eax") 0x5ae848 => mask,
-                        in("edx") 1,
-                        in("ecx") f,
-                        clobber_abi("C"),
-                    );
-                    // merge mask
-                    for f in &self.get_frames()[1..] {
-                        let f: &asset::Frame = f;
-                        asm!(
-                            "push {frame}",
-                            "push dword ptr [{sprite}+0x10]",
-                            "push dword ptr [{sprite}+0x14]",
-                            "call {call}",
-                            call = in(reg) 0x5af188,
-                            sprite = in(reg) &*self,
-                            frame = in(reg) &self.bbox_left,
-                            in("eax") mask,
-                            in("edx") f,
-                            in("ecx") self.bbox_type,
-                            clobber_abi("C"),
-                        );
-                    }
-                    write_mask(&*mask, &mut out)?;
-                    // free mask
-                    let _: u32 = delphi_call!(0x405a7c, mask);
-                } else {
-                    // TODO
-                    for f in self.get_frames() {
-                        let f: &asset::Frame = f;
-                        // create mask
-                        let mut mask: *const Mask;
-                        asm!(
-                            "push dword ptr [{sprite}+0x1c]",
-                            "lea eax, [{sprite}+0x20]",
-                            "push eax",
-                            "push dword ptr [{sprite}+0x10]",
-                            "push dword ptr [{sprite}+0x14]",
-                            "mov edx, 1",
-                            "mov eax, 0x5ae848",
-                            "call {call}",
-                            sprite = in(reg) &*self,
-                            call = in(reg) 0x5aecac,
-                            inlateout("eax") 0x5ae848 => mask,
-                            in("edx") 1,
-                            in("ecx") f,
-                            clobber_abi("C"),
-                        );
-                        write_mask(&*mask, &mut out)?;
-                        // free mask
-                        let _: u32 = delphi_call!(0x405a7c, mask);
-                    }
-                }
+        } else {
+            let collision = mask::Collision {
+                shape: self.collision_shape,
+                alpha_tolerance: self.alpha_tolerance,
+                per_frame: self.per_frame_colliders,
+                bbox_type: self.bbox_type,
+                bbox_left: self.bbox_left,
+                bbox_top: self.bbox_top,
+                bbox_right: self.bbox_right,
+                bbox_bottom: self.bbox_bottom,
+            };
+            let frames = self
+                .get_frames()
+                .iter()
+                .map(|f| mask::FrameData { width: f.width, height: f.height, data: f.get_data() })
+                .collect::<Vec<_>>();
+            for m in mask::make_masks(&collision, &frames) {
+                mask::write_mask(&m, &mut out)?;
             }
         }
         Ok(())
@@ -335,7 +258,7 @@ impl GetAssetList for asset::Font {
         out.write_u32::<LE>((self.range_start & 0xffff) | (charset << 16) | ((self.aa_level + 1) << 24))?;
         out.write_u32::<LE>(self.range_end)?;
         if exe {
-            self.render(); // the only reason all these functions are &mut self
+            self.render()?; // the only reason all these functions are &mut self
             for i in 0..256 {
                 out.write_u32::<LE>(self.s_x[i])?;
                 out.write_u32::<LE>(self.s_y[i])?;
@@ -562,6 +485,8 @@ pub unsafe extern "C" fn write_encrypted_gamedata_inj() {
 pub unsafe extern "fastcall" fn write_encrypted_gamedata(stream: &mut TMemoryStream) -> bool {
     // update extensions if needed
     update_extensions();
+    // and code that uses renamed assets, so it runs the way it will once it's saved
+    update_renamed_references();
     // write encryption headers
     // no garbage data
     stream.write_u32::<LE>(0).ok();